@@ -2,6 +2,7 @@
 # This scripts runs various CI-like checks in a convenient way.
 set -eux
 
+cargo check --quiet --workspace --all-targets --features gui
 cargo check --quiet --workspace --all-targets
 cargo check --quiet --workspace --all-features --lib --target wasm32-unknown-unknown
 cargo fmt --all -- --check
@@ -1,7 +1,38 @@
-mod game;
+mod animation_export;
+mod color_map;
+mod file_saver;
+mod save_slots;
+mod share_link;
+mod snapshot;
+mod soup_search_runner;
+mod svg_export;
+use std::collections::{HashMap, HashSet};
+use crate::game;
 use egui::color_picker::Alpha;
-use game::Game;
-use crate::app::game::field::CellState;
+use animation_export::{AnimationExportSettings, AnimationFormat, CellColors, FrameStyle, ANIMATION_FORMATS, AnimationRecorder};
+use color_map::{ColorMap, ColorRamp, COLOR_MAPS};
+use save_slots::{SaveSlot, SaveSlots, SlotDisplaySettings, Thumbnail, SAVE_SLOTS_KEY, get_timestamp_text};
+use share_link::SharedBoard;
+use snapshot::{ImageImportSettings, SnapshotScale, SNAPSHOT_SCALES, encode_snapshot, import_image};
+use svg_export::{SvgExportSettings, encode_svg};
+use game::{Game, GridType, AntPhases};
+use game::rule::Rule;
+use game::rule_table::{RuleTable, WIREWORLD_RULE, BRIANS_BRAIN_RULE};
+use game::turmite::{Ant, Direction, Turmite, ANT_COLORS, LANGTONS_ANT};
+use game::one_dimensional::{OneDimensionalRule, OneDimensionalSeed};
+use game::margolus::{MargolusRule, BILLIARD_BALL_MACHINE, CRITTERS, TRON, SAND};
+use game::continuous_field::ContinuousField;
+use game::activity::{ActivityMeasure, ACTIVITY_MEASURES};
+use game::update_mode::UpdateMode;
+use game::soup::{SoupGradient, SoupSettings, SOUP_SYMMETRIES};
+use game::soup_search::SoupSearchSettings;
+use game::predecessor::{PredecessorSearch, PredecessorSearchResult};
+use game::methuselah_search::{MethuselahSearch, MethuselahSearchSettings, METHUSELAH_FITNESSES};
+use game::ship_search::{SearchOrder, ShipSearch, ShipSearchSettings, MAX_SEARCH_NODES, MAX_SEARCH_WIDTH, SEARCH_SYMMETRIES};
+use soup_search_runner::SoupSearchRunner;
+use game::three_dimensional::{ThreeDimensionalView, VoxelNeighbourhood, BAYS_4555, BAYS_5766};
+use game::continuous_rule::{ContinuousRule, LeniaCreature, LeniaParameters, SmoothLifeParameters, LENIA_CREATURES};
+use game::field::{CellRegion, CellState, Field, TriangularNeighbourhood};
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -12,124 +43,2529 @@ pub struct TemplateApp {
     living_cell_color: egui::Color32,
     dead_cell_color: egui::Color32,
     border_color: egui::Color32,
+    /// How continuous cell states are shown
+    color_map: ColorMap,
+    /// Whether living cells are coloured by age along `age_color_ramp` instead of `living_cell_color`
+    shade_by_age: bool,
+    /// Age at the end of the ramp; older cells keep its last colour
+    max_shaded_age: u32,
+    age_color_ramp: ColorRamp,
+    /// Whether recently dead cells fade from `ghost_color` to `dead_cell_color`
+    ghost_trails: bool,
+    /// Generations a ghost takes to fade out
+    ghost_trail_length: u32,
+    ghost_color: egui::Color32,
+
+    /// Whether the activity heat map is drawn over the board
+    show_activity_map: bool,
+    activity_measure: ActivityMeasure,
+    activity_color_map: ColorMap,
+    /// Opacity of the heat map over the cells, from 0.0 to 1.0
+    activity_map_opacity: f32,
+
+    animation_export_settings: AnimationExportSettings,
+    /// File the animation is written to (only its name is used on the web, where it is downloaded)
+    animation_export_path: String,
+
+    #[serde(skip)]
+    animation_export_message: String,
+
+    /// Animation being exported, a few generations per frame
+    #[serde(skip)]
+    animation_recorder: Option<AnimationRecorder>,
+
+    snapshot_scale: SnapshotScale,
+    snapshot_path: String,
+    image_import_settings: ImageImportSettings,
+    /// Image imported by the import button on native (images can also be dropped onto the window, also on the web)
+    image_import_path: String,
+
+    svg_export_settings: SvgExportSettings,
+    svg_path: String,
+
+    #[serde(skip)]
+    image_message: String,
+
+    /// Share link pasted to be opened (on the web, links are opened by visiting them)
+    #[serde(skip)]
+    share_link_to_open: String,
+
+    #[serde(skip)]
+    share_link_message: String,
+
+    /// Saved under their own storage key, see `save`
+    #[serde(skip)]
+    save_slots: SaveSlots,
+
+    /// Name the current board is saved under
+    save_slot_name: String,
+
+    /// Index of the slot being renamed, with its new name
+    #[serde(skip)]
+    renamed_slot: Option<(usize, String)>,
+
+    /// File the saved boards are exported to, and imported from on native
+    save_slots_path: String,
+
+    /// Thumbnail textures, by `SaveSlot::get_thumbnail_key`
+    #[serde(skip)]
+    save_slot_thumbnails: HashMap<String, egui::TextureHandle>,
+
+    #[serde(skip)]
+    save_slots_message: String,
 
     n_generations_to_advance: usize,
 
-    new_game_width: usize,
-    new_game_height: usize,
+    /// Threads the game steps on, see `Game::set_n_threads`
+    n_threads: usize,
+
+    new_game_width: usize,
+    new_game_height: usize,
+    new_game_grid_type: GridType,
+    /// Number of Z layers of new three-dimensional games
+    new_game_depth: usize,
+
+    /// Probability of living cells, symmetry, region and gradient of soups
+    soup_settings: SoupSettings,
+    /// Seed of the next soup
+    soup_seed: u64,
+    /// Whether every randomization picks a new seed, instead of reusing `soup_seed`
+    new_soup_seed_every_time: bool,
+
+    soup_search_first_seed: u64,
+    soup_search_max_generations: usize,
+
+    #[serde(skip)]
+    soup_search_runner: SoupSearchRunner,
+
+    #[serde(skip)]
+    soup_search_error_message: String,
+
+    consider_extremes_adjacent: bool,
+
+    rule_text: String,
+
+    /// State painted by left clicks when a rule table is loaded
+    selected_state: u8,
+
+    turmite_text: String,
+
+    /// Whether clicks on the board place/remove ants instead of editing cells
+    placing_ants: bool,
+
+    /// Whether dragging on the board selects cells instead of editing them
+    selecting_cells: bool,
+    /// Rectangle of cells selected on a square board, e.g. the target of predecessor searches
+    selection: Option<CellRegion>,
+    /// Cell where the selection being dragged started
+    #[serde(skip)]
+    selection_start: Option<(usize, usize)>,
+
+    /// Whether parents must also leave the cells around the selection dead
+    predecessor_surroundings_must_die: bool,
+    predecessor_max_parents: usize,
+    predecessor_conflict_limit: usize,
+
+    #[serde(skip)]
+    predecessor_search: Option<PredecessorSearch>,
+
+    ship_search_settings: ShipSearchSettings,
+
+    #[serde(skip)]
+    ship_search: Option<ShipSearch>,
+
+    /// Whether the ship search advances every frame (it can be paused and resumed)
+    #[serde(skip)]
+    is_ship_search_running: bool,
+
+    #[serde(skip)]
+    ship_search_error_message: String,
+
+    methuselah_search_settings: MethuselahSearchSettings,
+
+    #[serde(skip)]
+    methuselah_search: Option<MethuselahSearch>,
+
+    #[serde(skip)]
+    is_methuselah_search_running: bool,
+
+    #[serde(skip)]
+    methuselah_search_error_message: String,
+
+    #[serde(skip)] // This how you opt-out of serialization of a field
+    debug_message: String,
+
+    #[serde(skip)]
+    rule_error_message: String,
+
+    #[serde(skip)]
+    rule_table_text: String,
+
+    #[serde(skip)]
+    rule_table_error_message: String,
+
+    #[serde(skip)]
+    turmite_error_message: String,
+
+    one_dimensional_rule: OneDimensionalRule,
+    one_dimensional_seed_text: String,
+
+    #[serde(skip)]
+    one_dimensional_error_message: String,
+
+    margolus_rule_text: String,
+
+    #[serde(skip)]
+    margolus_error_message: String,
+
+    /// Continuous rule being edited, applied with the "Apply" button
+    continuous_rule: ContinuousRule,
+    ring_heights_text: String,
+
+    #[serde(skip)]
+    continuous_error_message: String,
+
+    three_dimensional_view: ThreeDimensionalView,
+    /// Z layer shown (and edited) in the slice view; the isometric view shows the layers up to it
+    shown_z_slice: usize,
+
+    /// Seed edited in the update mode window, applied with its button
+    update_seed: u64,
+
+    /// Saved apart, in the versioned save format, under `GAME_KEY`
+    #[serde(skip)]
+    game: Game,
+
+    /// Why some of the saved state couldn't be loaded at startup
+    #[serde(skip)]
+    load_errors: Vec<String>,
+
+    /// Storage keys and texts of the saves that couldn't be loaded, kept so that the defaults replacing them don't overwrite them
+    #[serde(skip)]
+    unreadable_saves: Vec<(String, String)>,
+}
+
+/// Storage key of the game, saved in the versioned format of `save_format`
+const GAME_KEY: &str = "game";
+
+/// Layout of the app state before the game was saved apart under `GAME_KEY`
+#[derive(serde::Deserialize)]
+struct LegacyAppState {
+    game: Option<Game>
+}
+
+impl Default for TemplateApp {
+    fn default() -> Self {
+        let default_soup_settings = SoupSettings::default();
+        let default_game_width = 20;
+        let default_game_height = 20;
+        let default_soup_seed = get_new_soup_seed();
+        let mut game: Game = Game::new(default_game_width, default_game_height);
+        game.randomize(&default_soup_settings, default_soup_seed);
+        Self {
+            cell_size: 15,
+            cell_border_size: 1,
+            living_cell_color: egui::Color32::GREEN,
+            dead_cell_color: egui::Color32::GRAY,
+            border_color: egui::Color32::BLACK,
+            color_map: ColorMap::default(),
+            shade_by_age: false,
+            max_shaded_age: 100,
+            age_color_ramp: ColorRamp::default(),
+            ghost_trails: false,
+            ghost_trail_length: 10,
+            ghost_color: egui::Color32::from_rgb(0, 90, 160),
+            show_activity_map: false,
+            activity_measure: ActivityMeasure::default(),
+            activity_color_map: ColorMap::Inferno,
+            activity_map_opacity: 0.75,
+            animation_export_settings: AnimationExportSettings::default(),
+            animation_export_path: String::from("rusty-life.gif"),
+            animation_export_message: String::new(),
+            animation_recorder: None,
+            snapshot_scale: SnapshotScale::default(),
+            snapshot_path: String::from("rusty-life.png"),
+            image_import_settings: ImageImportSettings::default(),
+            image_import_path: String::new(),
+            svg_export_settings: SvgExportSettings::default(),
+            svg_path: String::from("rusty-life.svg"),
+            image_message: String::new(),
+            share_link_to_open: String::new(),
+            share_link_message: String::new(),
+            save_slots: SaveSlots::default(),
+            save_slot_name: String::from("My board"),
+            renamed_slot: None,
+            save_slots_path: String::from("rusty-life-boards.ron"),
+            save_slot_thumbnails: HashMap::new(),
+            save_slots_message: String::new(),
+            n_generations_to_advance: 100,
+            n_threads: 0,
+            new_game_width: default_game_width,
+            new_game_height: default_game_height,
+            new_game_grid_type: GridType::Square,
+            new_game_depth: default_game_width,
+            soup_settings: default_soup_settings,
+            soup_seed: default_soup_seed,
+            new_soup_seed_every_time: true,
+            soup_search_first_seed: 0,
+            soup_search_max_generations: 10_000,
+            soup_search_runner: SoupSearchRunner::default(),
+            soup_search_error_message: String::new(),
+            consider_extremes_adjacent: true,
+            rule_text: Rule::default().to_rule_string(),
+            selected_state: 1,
+            turmite_text: String::from(LANGTONS_ANT),
+            placing_ants: false,
+            selecting_cells: false,
+            selection: None,
+            selection_start: None,
+            predecessor_surroundings_must_die: false,
+            predecessor_max_parents: 5,
+            predecessor_conflict_limit: 100_000,
+            predecessor_search: None,
+            ship_search_settings: ShipSearchSettings::default(),
+            ship_search: None,
+            is_ship_search_running: false,
+            ship_search_error_message: String::new(),
+            methuselah_search_settings: MethuselahSearchSettings::default(),
+            methuselah_search: None,
+            is_methuselah_search_running: false,
+            methuselah_search_error_message: String::new(),
+            debug_message: String::from("<NO DEBUG MESSAGE>"),
+            rule_error_message: String::new(),
+            rule_table_text: String::new(),
+            rule_table_error_message: String::new(),
+            turmite_error_message: String::new(),
+            one_dimensional_rule: OneDimensionalRule::default(),
+            one_dimensional_seed_text: String::from("1"),
+            one_dimensional_error_message: String::new(),
+            margolus_rule_text: String::from(BILLIARD_BALL_MACHINE),
+            margolus_error_message: String::new(),
+            continuous_rule: ContinuousRule::Lenia(LeniaParameters::default()),
+            ring_heights_text: String::from("1"),
+            continuous_error_message: String::new(),
+            three_dimensional_view: ThreeDimensionalView::default(),
+            shown_z_slice: 0,
+            update_seed: 0,
+            game,
+            load_errors: Vec::new(),
+            unreadable_saves: Vec::new()
+        }
+    }
+}
+
+impl TemplateApp {
+    /// Called once before the first frame.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        // This is also where you can customize the look and feel of egui using
+        // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
+
+        // Load previous app state (if any).
+        // Note that you must enable the `persistence` feature for this to work.
+        #[allow(unused_mut)] // only modified on the web
+        let mut app: TemplateApp = match cc.storage {
+            Some(storage) => TemplateApp::load(storage),
+            None => Default::default(),
+        };
+
+        // A share link opens its board over the saved one
+        #[cfg(target_arch = "wasm32")]
+        if let Some(fragment) = share_link::get_page_fragment() {
+            share_link::clear_page_fragment();
+            app.share_link_message = match SharedBoard::from_fragment(&fragment) {
+                Ok(shared_board) => {
+                    app.open_shared_board(shared_board);
+                    String::from("Opened the shared board")
+                }
+                Err(error) => error,
+            };
+        }
+
+        app
+    }
+
+    /// Reads the app state, the game and the saved boards; whatever can't be read is left at its default, with the reason in `load_errors`
+    fn load(storage: &dyn eframe::Storage) -> TemplateApp {
+        let app_state = storage.get_string(eframe::APP_KEY);
+        let mut load_errors: Vec<String> = Vec::new();
+        let mut unreadable_saves: Vec<(String, String)> = Vec::new();
+        let mut app: TemplateApp = match app_state.as_deref().map(ron::from_str::<TemplateApp>) {
+            Some(Ok(app)) => app,
+            Some(Err(error)) => {
+                load_errors.push(String::from("Couldn't read the settings: ") + &error.to_string());
+                unreadable_saves.push((String::from(eframe::APP_KEY), app_state.clone().unwrap_or_default()));
+                TemplateApp::default()
+            }
+            None => TemplateApp::default(),
+        };
+
+        let game = match storage.get_string(GAME_KEY) {
+            Some(text) => game::save_format::load_game(&text).map(Some).map_err(|error| (error, text)),
+            // Older versions saved the game within the app state
+            None => app_state.as_deref().map_or(Ok(None), |text| ron::Options::default()
+                .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+                .from_str::<LegacyAppState>(text)
+                .map(|legacy_app_state| legacy_app_state.game)
+                .map_err(|error| String::from("Couldn't read the game saved with the settings: ") + &error.to_string())
+                .and_then(|game| match game {
+                    Some(game) => game.check_dimensions().map(|()| Some(game))
+                        .map_err(|error| String::from("The game saved with the settings is damaged: ") + &error.to_string()),
+                    None => Ok(None),
+                })
+                .map_err(|error| (error, String::from(text)))),
+        };
+        match game {
+            Ok(Some(game)) => app.game = game,
+            Ok(None) => (),
+            Err((error, text)) => {
+                load_errors.push(error);
+                unreadable_saves.push((String::from(GAME_KEY), text));
+            }
+        }
+
+        if let Some(text) = storage.get_string(SAVE_SLOTS_KEY) {
+            match ron::from_str::<SaveSlots>(&text) {
+                Ok(save_slots) => app.save_slots = save_slots,
+                Err(error) => {
+                    load_errors.push(String::from("Couldn't read the saved boards: ") + &error.to_string());
+                    unreadable_saves.push((String::from(SAVE_SLOTS_KEY), text));
+                }
+            }
+        }
+        app.load_errors = load_errors;
+        app.unreadable_saves = unreadable_saves;
+        app.apply_game_settings();
+        app
+    }
+
+    /// Passes the settings of the app that the game depends on to a game that was just made or loaded
+    fn apply_game_settings(&mut self) {
+        self.game.set_activity_tracking(self.show_activity_map);
+        self.game.set_n_threads(self.n_threads);
+    }
+
+    /// Whether the board is a plain square field of living and dead cells under a B/S rule, as the pattern searches require
+    fn is_binary_square_game(&self) -> bool {
+        self.game.get_grid_type() == GridType::Square && self.game.get_rule_table().is_none()
+            && self.game.get_margolus_rule().is_none() && self.game.get_continuous_rule().is_none()
+    }
+
+    /// Randomizes the cells with the soup seed, picking a new one first if asked to
+    fn randomize_game(&mut self) {
+        if self.new_soup_seed_every_time {
+            self.soup_seed = get_new_soup_seed();
+        }
+        self.game.randomize(&self.soup_settings, self.soup_seed);
+    }
+
+    fn draw_board(&mut self, response: &egui::Response, painter: &egui::Painter) {
+        match self.game.get_grid_type() {
+            GridType::Square => {
+                self.draw_square_board(response, painter);
+                self.draw_margolus_blocks(response, painter);
+                self.draw_ants(response, painter);
+                self.draw_activity_map(response, painter);
+                self.draw_selection(response, painter);
+            }
+            GridType::Triangular => {
+                self.draw_triangular_board(response, painter);
+                self.draw_activity_map(response, painter);
+            }
+            GridType::OneDimensional => self.draw_square_board(response, painter),
+            GridType::ThreeDimensional => match self.three_dimensional_view {
+                ThreeDimensionalView::Slice => self.draw_square_board(response, painter),
+                ThreeDimensionalView::Isometric => self.draw_isometric_voxels(response, painter),
+            },
+        }
+    }
+
+    /// Outlines the 2x2 blocks the next Margolus step will replace
+    fn draw_margolus_blocks(&mut self, response: &egui::Response, painter: &egui::Painter) {
+        if self.game.get_margolus_rule().is_none() {
+            return;
+        }
+        let offset = self.game.get_margolus_block_offset();
+        let width = self.game.get_field().get_width();
+        let height = self.game.get_field().get_height();
+        let block_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(255, 0, 0, 160));
+        let cell_size = self.cell_size as f32;
+        for block_x in MargolusRule::get_block_starts(width, offset, self.consider_extremes_adjacent) {
+            for block_y in MargolusRule::get_block_starts(height, offset, self.consider_extremes_adjacent) {
+                let block_rect = egui::Rect::from_min_size(
+                    response.rect.min + egui::vec2(block_x as f32 * cell_size, block_y as f32 * cell_size),
+                    egui::vec2(2.0 * cell_size, 2.0 * cell_size)
+                );
+                painter.rect_stroke(block_rect, 0.0, block_stroke);
+            }
+        }
+    }
+
+    /// Colours the cells that saw activity over the heat map window, leaving the others as they are
+    fn draw_activity_map(&self, response: &egui::Response, painter: &egui::Painter) {
+        if !self.show_activity_map || !self.game.is_running_field() {
+            return;
+        }
+        let activity_map = self.game.get_activity_map();
+        let max_count = activity_map.get_max_count(self.activity_measure);
+        if max_count == 0 {
+            return;
+        }
+        let alpha = (self.activity_map_opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let cell_size = self.cell_size as f32;
+        for x in 0..activity_map.get_width() {
+            for y in 0..activity_map.get_height() {
+                let count = activity_map.get_count(self.activity_measure, x, y);
+                if count == 0 {
+                    continue;
+                }
+                let color = self.activity_color_map.get_color(count as f32 / max_count as f32, self.dead_cell_color, self.living_cell_color);
+                let color = egui::Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha);
+                if self.game.get_grid_type() == GridType::Triangular {
+                    let vertices = get_triangle_vertices(x, y, self.cell_size).map(|vertex| vertex + response.rect.min.to_vec2());
+                    painter.add(egui::Shape::convex_polygon(vertices.to_vec(), color, egui::Stroke::NONE));
+                }
+                else {
+                    let cell_rect = egui::Rect::from_min_size(response.rect.min + egui::vec2(x as f32 * cell_size, y as f32 * cell_size), egui::vec2(cell_size, cell_size));
+                    painter.rect_filled(cell_rect, 0.0, color);
+                }
+            }
+        }
+    }
+
+    fn draw_selection(&self, response: &egui::Response, painter: &egui::Painter) {
+        let Some(selection) = self.selection else {
+            return;
+        };
+        let cell_size = self.cell_size as f32;
+        let selection_rect = egui::Rect::from_min_size(
+            response.rect.min + egui::vec2(selection.x as f32 * cell_size, selection.y as f32 * cell_size),
+            egui::vec2(selection.width as f32 * cell_size, selection.height as f32 * cell_size)
+        );
+        painter.rect_stroke(selection_rect, 0.0, egui::Stroke::new(2.0, egui::Color32::from_rgb(0, 160, 255)));
+    }
+
+    /// Each ant is drawn as a triangle pointing where it is heading
+    fn draw_ants(&self, response: &egui::Response, painter: &egui::Painter) {
+        let cell_size = self.cell_size as f32;
+        let inset = self.cell_border_size as f32 + cell_size * 0.1;
+        for ant in self.game.get_ants() {
+            let left = response.rect.min.x + ant.x as f32 * cell_size + inset;
+            let top = response.rect.min.y + ant.y as f32 * cell_size + inset;
+            let right = response.rect.min.x + (ant.x + 1) as f32 * cell_size - inset;
+            let bottom = response.rect.min.y + (ant.y + 1) as f32 * cell_size - inset;
+            let center = egui::pos2((left + right) / 2.0, (top + bottom) / 2.0);
+            let vertices = match ant.direction {
+                Direction::North => [egui::pos2(center.x, top), egui::pos2(right, bottom), egui::pos2(left, bottom)],
+                Direction::East => [egui::pos2(right, center.y), egui::pos2(left, bottom), egui::pos2(left, top)],
+                Direction::South => [egui::pos2(center.x, bottom), egui::pos2(left, top), egui::pos2(right, top)],
+                Direction::West => [egui::pos2(left, center.y), egui::pos2(right, top), egui::pos2(right, bottom)],
+            };
+            let [r, g, b] = ant.color;
+            painter.add(egui::Shape::convex_polygon(vertices.to_vec(), egui::Color32::from_rgb(r, g, b), egui::Stroke::new(1.0, egui::Color32::BLACK)));
+        }
+    }
+
+    fn draw_square_board(&mut self, response: &egui::Response, painter: &egui::Painter) {
+        let cell_width = self.cell_size;
+        let cell_height = self.cell_size;
+        let cell_border_size = self.cell_border_size;
+        for i in 0..self.game.get_field().get_width() {
+            for j in 0..self.game.get_field().get_height() {
+                let cell_color: egui::Color32 = self.get_square_cell_color(i, j);
+                draw_rectangle(response, painter, cell_width * i, cell_height * j, cell_width, cell_height, self.border_color);
+                draw_rectangle(response, painter, cell_width * i + cell_border_size, cell_height * j + cell_border_size, cell_width - 2 * cell_border_size, cell_height - 2 * cell_border_size, cell_color);
+            }
+        }
+    }
+
+    /// Draws the living cells of the layers up to the shown Z slice as cubes, back to front, with the Z axis pointing up
+    fn draw_isometric_voxels(&mut self, response: &egui::Response, painter: &egui::Painter) {
+        let voxel_field = self.game.get_voxel_field().clone();
+        let cell_size = self.cell_size as f32;
+        let half_width = cell_size * 0.866;
+        let half_height = cell_size * 0.5;
+        let origin = response.rect.min + egui::vec2(voxel_field.get_height() as f32 * half_width, voxel_field.get_depth() as f32 * cell_size);
+        let project = |x: usize, y: usize, z: usize| {
+            origin + egui::vec2((x as f32 - y as f32) * half_width, (x + y) as f32 * half_height - z as f32 * cell_size)
+        };
+        let border_stroke = egui::Stroke::new(1.0, self.border_color);
+        let width = voxel_field.get_width();
+        let height = voxel_field.get_height();
+        let shown_depth = (self.shown_z_slice + 1).min(voxel_field.get_depth());
+
+        // outline of the floor, for orientation
+        let floor = vec![project(0, 0, 0), project(width, 0, 0), project(width, height, 0), project(0, height, 0)];
+        painter.add(egui::Shape::convex_polygon(floor, self.dead_cell_color, border_stroke));
+
+        // cubes nearer to the viewer (larger x + y + z) are drawn later, over the ones behind them
+        let mut voxels: Vec<(usize, usize, usize)> = Vec::new();
+        for z in 0..shown_depth {
+            for y in 0..height {
+                for x in 0..width {
+                    if voxel_field.get_cell_state(x, y, z) == CellState::Alive {
+                        voxels.push((x, y, z));
+                    }
+                }
+            }
+        }
+        voxels.sort_by_key(|(x, y, z)| x + y + z);
+        let is_alive = |x: usize, y: usize, z: usize| z < shown_depth && voxel_field.get_cell_state(x, y, z) == CellState::Alive;
+        for (x, y, z) in voxels {
+            // higher layers are closer to the living cell color
+            let color = self.dead_cell_color.lerp_to_gamma(self.living_cell_color, 0.5 + 0.5 * (z + 1) as f32 / shown_depth as f32);
+            let shade = |factor: f32| egui::Color32::from_rgb((color.r() as f32 * factor) as u8, (color.g() as f32 * factor) as u8, (color.b() as f32 * factor) as u8);
+            if !is_alive(x, y, z + 1) {
+                let top = vec![project(x, y, z + 1), project(x + 1, y, z + 1), project(x + 1, y + 1, z + 1), project(x, y + 1, z + 1)];
+                painter.add(egui::Shape::convex_polygon(top, color, border_stroke));
+            }
+            if !is_alive(x + 1, y, z) {
+                let right = vec![project(x + 1, y, z), project(x + 1, y + 1, z), project(x + 1, y + 1, z + 1), project(x + 1, y, z + 1)];
+                painter.add(egui::Shape::convex_polygon(right, shade(0.75), border_stroke));
+            }
+            if !is_alive(x, y + 1, z) {
+                let left = vec![project(x, y + 1, z), project(x + 1, y + 1, z), project(x + 1, y + 1, z + 1), project(x, y + 1, z + 1)];
+                painter.add(egui::Shape::convex_polygon(left, shade(0.55), border_stroke));
+            }
+        }
+    }
+
+    fn get_square_cell_color(&mut self, x: usize, y: usize) -> egui::Color32 {
+        if self.game.get_grid_type() == GridType::OneDimensional {
+            // states are shaded from the dead cell color (0) to the living cell color (highest state)
+            let highest_state = self.game.get_one_dimensional().get_rule().get_n_colors() - 1;
+            let cell_state = self.game.get_multi_state_field().get_cell_state(x, y);
+            return self.dead_cell_color.lerp_to_gamma(self.living_cell_color, cell_state as f32 / highest_state as f32);
+        }
+        if self.game.get_grid_type() == GridType::ThreeDimensional {
+            let cell_state: CellState = self.game.get_voxel_field().get_cell_state(x, y, self.shown_z_slice);
+            return if cell_state == CellState::Alive {self.living_cell_color} else {self.dead_cell_color};
+        }
+        if self.game.get_rule_table().is_some() {
+            let cell_state = self.game.get_multi_state_field().get_cell_state(x, y);
+            let [r, g, b] = self.game.get_rule_table().map_or([0, 0, 0], |rule_table| rule_table.get_state_color(cell_state));
+            return egui::Color32::from_rgb(r, g, b);
+        }
+        if self.game.get_continuous_rule().is_some() {
+            let cell_state = self.game.get_continuous_field().get_cell_state(x, y);
+            return self.color_map.get_color(cell_state, self.dead_cell_color, self.living_cell_color);
+        }
+        self.get_field_cell_color(x, y)
+    }
+
+    /// Colour of a cell of `field`, shaded by age or as a ghost when those modes are on
+    fn get_field_cell_color(&mut self, x: usize, y: usize) -> egui::Color32 {
+        let cell_state: CellState = self.game.get_field().get_cell_state(x, y);
+        let cell_ages = self.game.get_cell_ages();
+        if cell_state == CellState::Alive {
+            if !self.shade_by_age {
+                return self.living_cell_color;
+            }
+            // newborn cells (age 1, or 0 for cells drawn since the last generation) are at the start of the ramp
+            let age = cell_ages.get_age(x, y).clamp(1, self.max_shaded_age.max(1));
+            return self.age_color_ramp.get_color((age - 1) as f32 / (self.max_shaded_age.max(2) - 1) as f32);
+        }
+        match cell_ages.get_generations_since_death(x, y) {
+            Some(generations_since_death) if self.ghost_trails && generations_since_death <= self.ghost_trail_length => {
+                let fading = (generations_since_death - 1) as f32 / self.ghost_trail_length as f32;
+                self.ghost_color.lerp_to_gamma(self.dead_cell_color, fading)
+            }
+            _ => self.dead_cell_color,
+        }
+    }
+
+    fn draw_triangular_board(&mut self, response: &egui::Response, painter: &egui::Painter) {
+        let border_stroke = egui::Stroke::new(self.cell_border_size as f32, self.border_color);
+        for i in 0..self.game.get_field().get_width() {
+            for j in 0..self.game.get_field().get_height() {
+                let cell_color: egui::Color32 = self.get_field_cell_color(i, j);
+                let vertices = get_triangle_vertices(i, j, self.cell_size)
+                    .map(|vertex| vertex + response.rect.min.to_vec2());
+                painter.add(egui::Shape::convex_polygon(vertices.to_vec(), cell_color, border_stroke));
+            }
+        }
+    }
+
+    fn on_mouse_press_on_game_window(&mut self, pos : egui::Pos2, was_right_click: bool) {
+        match self.game.get_grid_type() {
+            GridType::Square => self.on_mouse_press_on_square_game_window(pos, was_right_click),
+            GridType::Triangular => self.on_mouse_press_on_triangular_game_window(pos, was_right_click),
+            GridType::OneDimensional => self.on_mouse_press_on_square_game_window(pos, was_right_click),
+            GridType::ThreeDimensional => {
+                // only the slice view can be edited
+                if self.three_dimensional_view == ThreeDimensionalView::Slice {
+                    self.on_mouse_press_on_square_game_window(pos, was_right_click);
+                }
+            }
+        }
+    }
+
+    fn on_mouse_press_on_triangular_game_window(&mut self, pos : egui::Pos2, was_right_click: bool) {
+        let y_clicked_cell = (pos.y / get_triangle_row_height(self.cell_size)) as usize;
+        // each triangle overlaps horizontally with half of each of its two neighbours in the row
+        let column = (pos.x / (self.cell_size as f32 / 2.0)) as usize;
+        for x_clicked_cell in [column.saturating_sub(1), column] {
+            let [a, b, c] = get_triangle_vertices(x_clicked_cell, y_clicked_cell, self.cell_size);
+            if is_point_within_triangle(pos, a, b, c) {
+                self.cell_clicked(x_clicked_cell, y_clicked_cell, was_right_click);
+                return;
+            }
+        }
+    }
+
+    fn on_mouse_press_on_square_game_window(&mut self, pos : egui::Pos2, was_right_click: bool) {
+        let x_pressed = pos.x as usize;
+        let y_pressed = pos.y as usize;
+        let x_within_square_representation = x_pressed % self.cell_size;
+        let y_within_square_representation = y_pressed % self.cell_size;
+        let x_was_within_square : bool = self.cell_border_size < x_within_square_representation && x_within_square_representation + self.cell_border_size < self.cell_size;
+        let y_was_within_square : bool = self.cell_border_size < y_within_square_representation && y_within_square_representation + self.cell_border_size < self.cell_size;
+        if x_was_within_square && y_was_within_square {
+            let x_clicked_cell = x_pressed / self.cell_size;
+            let y_clicked_cell = y_pressed / self.cell_size;
+            self.cell_clicked(x_clicked_cell, y_clicked_cell, was_right_click);
+        }
+        //self.debug_message = "Mouse button at: ".to_owned() + &x_pressed.to_string() + "-" + &y_pressed.to_string();
+    }
+
+    fn cell_clicked(&mut self, x: usize, y: usize, was_right_click: bool) {
+        if self.selecting_cells && self.game.get_grid_type() == GridType::Square {
+            self.selection_cell_clicked(x, y, was_right_click);
+            return;
+        }
+        if self.placing_ants && self.game.get_grid_type() == GridType::Square {
+            self.ant_cell_clicked(x, y, was_right_click);
+            return;
+        }
+        if self.game.get_grid_type() == GridType::OneDimensional {
+            let new_cell_state: u8 = if was_right_click {0} else {self.selected_state};
+            self.game.set_one_dimensional_cell_state(x, y, new_cell_state);
+            return;
+        }
+        if self.game.get_grid_type() == GridType::ThreeDimensional {
+            let new_cell_state: CellState = if was_right_click {CellState::Dead} else {CellState::Alive};
+            self.game.get_voxel_field_mut().set_cell_state(x, y, self.shown_z_slice, new_cell_state);
+            return;
+        }
+        if self.game.get_rule_table().is_some() {
+            let new_cell_state: u8 = if was_right_click {0} else {self.selected_state};
+            self.game.get_multi_state_field_mut().set_cell_state(x, y, new_cell_state);
+            return;
+        }
+        if self.game.get_continuous_rule().is_some() {
+            let new_cell_state: f32 = if was_right_click {0.0} else {1.0};
+            self.game.get_continuous_field_mut().set_cell_state(x, y, new_cell_state);
+            return;
+        }
+        let new_cell_state: CellState = if was_right_click {CellState::Dead} else {CellState::Alive};
+        self.game.get_field_mut().set_cell_state(x, y, new_cell_state);
+        //self.debug_message = "Clicked cell: ".to_owned() + &x.to_string() + "-" + &y.to_string();
+    }
+
+    /// Left click starts a new selection (extended by dragging), right click clears it
+    fn selection_cell_clicked(&mut self, x: usize, y: usize, was_right_click: bool) {
+        if was_right_click {
+            self.selection = None;
+            self.selection_start = None;
+        }
+        else {
+            self.selection = Some(CellRegion::from_corners(x, y, x, y));
+            self.selection_start = Some((x, y));
+        }
+    }
+
+    /// Extends the selection being dragged to the cell under the pointer
+    fn drag_selection(&mut self, response: &egui::Response, ui: &egui::Ui) {
+        let Some((start_x, start_y)) = self.selection_start else {
+            return;
+        };
+        if !ui.input(|input| input.pointer.primary_down()) {
+            self.selection_start = None;
+            return;
+        }
+        if let Some(pos) = ui.input(|input| input.pointer.interact_pos()) {
+            let field = self.game.get_field();
+            let x = ((pos.x - response.rect.min.x).max(0.0) as usize / self.cell_size).min(field.get_width() - 1);
+            let y = ((pos.y - response.rect.min.y).max(0.0) as usize / self.cell_size).min(field.get_height() - 1);
+            self.selection = Some(CellRegion::from_corners(start_x, start_y, x, y));
+        }
+    }
+
+    /// Left click places a new ant heading north, right click removes the ants on the cell
+    fn ant_cell_clicked(&mut self, x: usize, y: usize, was_right_click: bool) {
+        let ants = self.game.get_ants_mut();
+        if was_right_click {
+            ants.retain(|ant| ant.x != x || ant.y != y);
+        }
+        else {
+            let color = ANT_COLORS[ants.len() % ANT_COLORS.len()];
+            ants.push(Ant{x, y, direction: Direction::North, state: 0, color});
+        }
+    }
+
+    fn show_color_controls_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Color controls")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.vertical(|ui| {
+                        ui.label("Living cell color:");
+                        egui::widgets::color_picker::color_picker_color32(ui, &mut self.living_cell_color, Alpha::Opaque);
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        ui.label("Dead cell color:");
+                        egui::widgets::color_picker::color_picker_color32(ui, &mut self.dead_cell_color, Alpha::Opaque);
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        ui.label("Border color:");
+                        egui::widgets::color_picker::color_picker_color32(ui, &mut self.border_color, Alpha::Opaque);
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Continuous states color map:");
+                    for (color_map, name) in COLOR_MAPS {
+                        ui.radio_value(&mut self.color_map, color_map, name);
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.shade_by_age, "Shade living cells by age");
+                    ui.add(egui::Slider::new(&mut self.max_shaded_age, 1..=1000).logarithmic(true).text("Age at the end of the ramp"));
+                });
+                ui.label("Age color ramp (youngest first):");
+                let mut removed_stop: Option<usize> = None;
+                ui.horizontal_wrapped(|ui| {
+                    let n_stops = self.age_color_ramp.stops.len();
+                    for (index, stop) in self.age_color_ramp.stops.iter_mut().enumerate() {
+                        ui.vertical(|ui| {
+                            egui::widgets::color_picker::color_edit_button_srgba(ui, stop, Alpha::Opaque);
+                            if ui.add_enabled(n_stops > 1, egui::Button::new("✖").small()).on_hover_text("Remove this color").clicked() {
+                                removed_stop = Some(index);
+                            }
+                        });
+                    }
+                    if ui.button("+").on_hover_text("Add a color at the old end of the ramp").clicked() {
+                        let last_stop = self.age_color_ramp.stops.last().copied().unwrap_or(self.living_cell_color);
+                        self.age_color_ramp.stops.push(last_stop);
+                    }
+                    if ui.button("Reset ramp").clicked() {
+                        self.age_color_ramp = ColorRamp::default();
+                    }
+                });
+                if let Some(index) = removed_stop {
+                    self.age_color_ramp.stops.remove(index);
+                }
+                draw_gradient(ui, |value| self.age_color_ramp.get_color(value));
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.ghost_trails, "Ghost trails of dead cells");
+                    ui.add(egui::Slider::new(&mut self.ghost_trail_length, 1..=100).text("Generations to fade out"));
+                    egui::widgets::color_picker::color_edit_button_srgba(ui, &mut self.ghost_color, Alpha::Opaque);
+                });
+            });
+    }
+}
+
+impl TemplateApp {
+    fn load_rule_table(&mut self, rule_text: &str) {
+        match RuleTable::parse(rule_text) {
+            Ok(rule_table) => {
+                if self.selected_state as usize >= rule_table.get_n_states() {
+                    self.selected_state = 1;
+                }
+                self.game.load_rule_table(rule_table);
+                self.rule_table_error_message.clear();
+            }
+            Err(error_message) => self.rule_table_error_message = error_message,
+        }
+    }
+
+    fn load_dropped_rule_tables(&mut self, ctx: &egui::Context) {
+        let dropped_files = ctx.input(|input| input.raw.dropped_files.clone());
+        for dropped_file in dropped_files {
+            // images and saved boards dropped onto the window are for the other windows
+            let is_rule_table = [".rule", ".table"].iter().any(|extension| dropped_file.name.to_lowercase().ends_with(extension)
+                || dropped_file.path.as_ref().is_some_and(|path| path.to_string_lossy().to_lowercase().ends_with(extension)));
+            if !is_rule_table {
+                continue;
+            }
+            // on the web the contents come with the file, natively only its path does
+            let contents: Option<String> = match (&dropped_file.bytes, &dropped_file.path) {
+                (Some(bytes), _) => Some(String::from_utf8_lossy(bytes).into_owned()),
+                (None, Some(path)) => std::fs::read_to_string(path).ok(),
+                (None, None) => None,
+            };
+            match contents {
+                Some(contents) => {
+                    self.rule_table_text = contents;
+                    let rule_table_text = self.rule_table_text.clone();
+                    self.load_rule_table(&rule_table_text);
+                }
+                None => self.rule_table_error_message = String::from("Could not read the dropped file ") + &dropped_file.name,
+            }
+        }
+    }
+
+    fn show_rule_table_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Rule tables")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 300.0))
+            .show(ctx, |ui| {
+                if self.game.get_grid_type() != GridType::Square {
+                    ui.label("Rule tables can only be used on square grids");
+                    return;
+                }
+
+                ui.label("Multi-state rules in Golly's .rule format (@TABLE and @COLORS sections)");
+                ui.horizontal(|ui| {
+                    if ui.button("WireWorld").clicked() {
+                        self.rule_table_text = String::from(WIREWORLD_RULE);
+                        self.load_rule_table(WIREWORLD_RULE);
+                    }
+                    if ui.button("Brian's Brain").clicked() {
+                        self.rule_table_text = String::from(BRIANS_BRAIN_RULE);
+                        self.load_rule_table(BRIANS_BRAIN_RULE);
+                    }
+                });
+
+                ui.label("Paste a .rule file below, or drop one onto the window:");
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut self.rule_table_text).code_editor().desired_rows(8));
+                });
+                if ui.button("Load rule table").clicked() {
+                    let rule_table_text = self.rule_table_text.clone();
+                    self.load_rule_table(&rule_table_text);
+                }
+                if !self.rule_table_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.rule_table_error_message);
+                }
+
+                let Some(rule_table) = self.game.get_rule_table() else {
+                    return;
+                };
+                ui.separator();
+                let rule_table_text = String::from("Running ") + rule_table.get_name() + " (" + &rule_table.get_n_states().to_string() + " states)";
+                ui.label(rule_table_text);
+
+                ui.label("State painted by left clicks:");
+                let state_colors: Vec<[u8; 3]> = (0..rule_table.get_n_states()).map(|state| rule_table.get_state_color(state as u8)).collect();
+                ui.horizontal_wrapped(|ui| {
+                    for (state, [r, g, b]) in state_colors.into_iter().enumerate() {
+                        let state_button = egui::Button::new(egui::RichText::new(state.to_string()).color(get_contrasting_text_color(r, g, b)))
+                            .fill(egui::Color32::from_rgb(r, g, b))
+                            .selected(self.selected_state as usize == state);
+                        if ui.add(state_button).clicked() {
+                            self.selected_state = state as u8;
+                        }
+                    }
+                });
+
+                if ui.button("Back to B/S rules").clicked() {
+                    self.game.unload_rule_table();
+                }
+            });
+    }
+}
+
+impl TemplateApp {
+    fn show_ants_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Ants and turmites")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 600.0))
+            .show(ctx, |ui| {
+                if self.game.get_grid_type() != GridType::Square {
+                    ui.label("Ants can only walk on square grids");
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Turmite:");
+                    ui.text_edit_singleline(&mut self.turmite_text)
+                        .on_hover_text("For each internal state, for each cell colour: {colour to write, turn, next state}, where the turn is 1 (none), 2 (right), 4 (u-turn) or 8 (left); Langton's ant is {{{1,2,0},{0,8,0}}}");
+                    if ui.button("Apply").clicked() {
+                        match Turmite::parse(&self.turmite_text) {
+                            Ok(turmite) => {
+                                self.turmite_text = turmite.to_specification_string();
+                                self.game.set_turmite(turmite);
+                                self.turmite_error_message.clear();
+                            }
+                            Err(error_message) => self.turmite_error_message = error_message,
+                        }
+                    }
+                    if ui.button("Langton's ant").clicked() {
+                        self.turmite_text = String::from(LANGTONS_ANT);
+                        self.game.set_turmite(Turmite::default());
+                        self.turmite_error_message.clear();
+                    }
+                });
+                if !self.turmite_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.turmite_error_message);
+                }
+                let turmite = self.game.get_turmite();
+                let turmite_text = String::from("Current turmite: ") + &turmite.get_n_states().to_string() + " state(s), " + &turmite.get_n_colors().to_string() + " colours";
+                ui.label(turmite_text);
+                if turmite.get_n_colors() > self.game.get_n_cell_colors() {
+                    ui.colored_label(egui::Color32::YELLOW, "The board has fewer cell states than the turmite has colours, so colours wrap around; load a rule table with enough states to see them all");
+                }
+
+                ui.checkbox(&mut self.placing_ants, "Clicking the board places ants")
+                    .on_hover_text("Left click places an ant heading north, right click removes the ants on a cell");
+
+                let mut ant_phases = self.game.get_ant_phases();
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut ant_phases, AntPhases::AntsOnly, "Ants only");
+                    ui.radio_value(&mut ant_phases, AntPhases::AlternatingWithRule, "Alternate ants and cell rule");
+                });
+                self.game.set_ant_phases(ant_phases);
+                if !self.game.get_ants().is_empty() && ant_phases == AntPhases::AlternatingWithRule {
+                    let next_phase_text = if self.game.is_ant_phase_next() {"Next phase: ants"} else {"Next phase: cell rule"};
+                    ui.label(next_phase_text);
+                }
+
+                ui.separator();
+                let mut ant_to_remove: Option<usize> = None;
+                egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                    for (index, ant) in self.game.get_ants_mut().iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            let [r, g, b] = ant.color;
+                            let mut color = egui::Color32::from_rgb(r, g, b);
+                            egui::widgets::color_picker::color_edit_button_srgba(ui, &mut color, Alpha::Opaque);
+                            ant.color = [color.r(), color.g(), color.b()];
+                            let ant_text = String::from("Ant at ") + &ant.x.to_string() + "-" + &ant.y.to_string() + ", state " + &ant.state.to_string();
+                            ui.label(ant_text);
+                            if ui.small_button("Remove").clicked() {
+                                ant_to_remove = Some(index);
+                            }
+                        });
+                    }
+                });
+                if let Some(index) = ant_to_remove {
+                    self.game.get_ants_mut().remove(index);
+                }
+                if ui.button("Remove all ants").clicked() {
+                    self.game.get_ants_mut().clear();
+                }
+            });
+    }
+}
+
+impl TemplateApp {
+    fn show_one_dimensional_window(&mut self, ctx: &egui::Context) {
+        if self.game.get_grid_type() != GridType::OneDimensional {
+            return;
+        }
+        egui::Window::new("One-dimensional automata")
+            .resizable(true)
+            .collapsible(true)
+            .default_pos(egui::pos2(200.0, 300.0))
+            .show(ctx, |ui| {
+                let mut is_elementary = matches!(self.one_dimensional_rule, OneDimensionalRule::Elementary(_));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut is_elementary, true, "Elementary");
+                    ui.radio_value(&mut is_elementary, false, "Totalistic");
+                });
+                match (is_elementary, &mut self.one_dimensional_rule) {
+                    (true, OneDimensionalRule::Elementary(rule_number)) => {
+                        ui.add(egui::Slider::new(rule_number, 0..=255).text("Wolfram rule number"));
+                    }
+                    (true, OneDimensionalRule::Totalistic{..}) => {
+                        self.one_dimensional_rule = OneDimensionalRule::Elementary(30);
+                    }
+                    (false, OneDimensionalRule::Totalistic{n_colors, radius, code}) => {
+                        ui.add(egui::Slider::new(n_colors, 2..=9).text("Colours"));
+                        ui.add(egui::Slider::new(radius, 1..=5).text("Radius"));
+                        ui.horizontal(|ui| {
+                            ui.label("Code:");
+                            ui.add(egui::DragValue::new(code));
+                        }).response.on_hover_text("Digit i (in base <colours>, least significant first) of the code is the next state of a cell whose neighbourhood sums to i");
+                    }
+                    (false, OneDimensionalRule::Elementary(_)) => {
+                        self.one_dimensional_rule = OneDimensionalRule::Totalistic{n_colors: 3, radius: 1, code: 777};
+                    }
+                }
+                if ui.button("Apply rule").clicked() {
+                    let rule = match self.one_dimensional_rule {
+                        OneDimensionalRule::Totalistic{n_colors, radius, code} => OneDimensionalRule::new_totalistic(n_colors, radius, code),
+                        elementary_rule => Ok(elementary_rule),
+                    };
+                    match rule {
+                        Ok(rule) => {
+                            self.game.set_one_dimensional_rule(rule);
+                            self.one_dimensional_error_message.clear();
+                        }
+                        Err(error_message) => self.one_dimensional_error_message = error_message,
+                    }
+                }
+                let current_rule_text = String::from("Current rule: ") + &self.game.get_one_dimensional().get_rule().to_rule_string();
+                ui.label(current_rule_text);
+
+                ui.separator();
+                ui.label("First row:");
+                ui.horizontal(|ui| {
+                    if ui.button("Single cell").clicked() {
+                        self.seed_one_dimensional(OneDimensionalSeed::SingleCell);
+                    }
+                    if ui.button("Random").clicked() {
+                        self.randomize_game();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.one_dimensional_seed_text)
+                        .on_hover_text("One digit per cell, e.g. 0110101, centred on the row");
+                    if ui.button("Use cells").clicked() {
+                        self.seed_one_dimensional(OneDimensionalSeed::Cells(self.one_dimensional_seed_text.clone()));
+                    }
+                });
+                if !self.one_dimensional_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.one_dimensional_error_message);
+                }
+
+                let highest_state = self.game.get_one_dimensional().get_rule().get_n_colors() - 1;
+                if highest_state > 1 {
+                    self.selected_state = self.selected_state.clamp(1, highest_state as u8);
+                    ui.add(egui::Slider::new(&mut self.selected_state, 1..=highest_state as u8).text("State painted by left clicks"));
+                }
+                else {
+                    self.selected_state = 1;
+                }
+
+                ui.separator();
+                let field_height = self.game.get_multi_state_field().get_height();
+                let one_dimensional = self.game.get_one_dimensional();
+                let first_generation = one_dimensional.get_first_history_generation();
+                let last_view_start = (one_dimensional.get_current_generation() + 1).saturating_sub(field_height).max(first_generation);
+                let mut view_start = one_dimensional.get_shown_first_generation(field_height);
+                let mut follow_current_generation = one_dimensional.get_view_start().is_none();
+                ui.checkbox(&mut follow_current_generation, "Follow the current generation");
+                let slider_response = ui.add_enabled(
+                    last_view_start > first_generation,
+                    egui::Slider::new(&mut view_start, first_generation..=last_view_start).text("First generation shown")
+                );
+                if slider_response.changed() {
+                    follow_current_generation = view_start == last_view_start;
+                }
+                let new_view_start = if follow_current_generation {None} else {Some(view_start)};
+                if new_view_start != self.game.get_one_dimensional().get_view_start() {
+                    self.game.set_one_dimensional_view_start(new_view_start);
+                }
+            });
+    }
+
+    fn seed_one_dimensional(&mut self, seed: OneDimensionalSeed) {
+        match self.game.seed_one_dimensional(seed) {
+            Ok(()) => self.one_dimensional_error_message.clear(),
+            Err(error_message) => self.one_dimensional_error_message = error_message,
+        }
+    }
+}
+
+impl TemplateApp {
+    fn show_margolus_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Margolus block rules")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 450.0))
+            .show(ctx, |ui| {
+                if self.game.get_grid_type() != GridType::Square {
+                    ui.label("Block rules can only be used on square grids");
+                    return;
+                }
+
+                ui.label("The board is split into 2x2 blocks, shifted diagonally by one cell every step, and each block is replaced according to a table of 16 block states");
+                ui.horizontal(|ui| {
+                    for (name, rule_string) in [("Billiard ball machine", BILLIARD_BALL_MACHINE), ("Critters", CRITTERS), ("Tron", TRON), ("Sand", SAND)] {
+                        if ui.button(name).clicked() {
+                            self.margolus_rule_text = String::from(rule_string);
+                            self.apply_margolus_rule();
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Rule:");
+                    ui.text_edit_singleline(&mut self.margolus_rule_text)
+                        .on_hover_text("The new state of each of the 16 block states, where a block's state adds up 1 for its upper-left, 2 for its upper-right, 4 for its lower-left and 8 for its lower-right living cell, e.g. M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15 or, in MCell notation, MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15");
+                    if ui.button("Apply").clicked() {
+                        self.apply_margolus_rule();
+                    }
+                });
+                if !self.margolus_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.margolus_error_message);
+                }
+
+                let Some(margolus_rule) = self.game.get_margolus_rule() else {
+                    return;
+                };
+                ui.separator();
+                let reversibility_text = if margolus_rule.is_reversible() {" (reversible)"} else {""};
+                let margolus_rule_text = String::from("Running ") + &margolus_rule.to_rule_string() + reversibility_text;
+                ui.label(margolus_rule_text);
+                let offset = self.game.get_margolus_block_offset().to_string();
+                let phase_text = String::from("Next step uses the blocks starting at (") + &offset + ", " + &offset + "), outlined on the board";
+                ui.label(phase_text);
+                let field = self.game.get_field();
+                let has_odd_dimensions = field.get_width() % 2 == 1 || field.get_height() % 2 == 1;
+                if self.consider_extremes_adjacent && has_odd_dimensions {
+                    ui.colored_label(egui::Color32::YELLOW, "Blocks can only wrap around boards with an even width and height; along odd dimensions, the extremes are treated as walls");
+                }
+                if ui.button("Back to B/S rules").clicked() {
+                    self.game.set_margolus_rule(None);
+                }
+            });
+    }
+
+    fn apply_margolus_rule(&mut self) {
+        match MargolusRule::parse(&self.margolus_rule_text) {
+            Ok(margolus_rule) => {
+                self.margolus_rule_text = margolus_rule.to_rule_string();
+                self.game.set_margolus_rule(Some(margolus_rule));
+                self.margolus_error_message.clear();
+            }
+            Err(error_message) => self.margolus_error_message = error_message,
+        }
+    }
+}
+
+impl TemplateApp {
+    fn show_continuous_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Continuous automata")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 500.0))
+            .show(ctx, |ui| {
+                if self.game.get_grid_type() != GridType::Square {
+                    ui.label("Continuous rules can only be used on square grids");
+                    return;
+                }
+
+                ui.label("Cells hold a state between 0 and 1, updated from weighted sums of their surroundings (computed with FFT convolutions)");
+                ui.horizontal(|ui| {
+                    ui.label("Bundled creature:");
+                    for creature in LENIA_CREATURES.iter() {
+                        if ui.button(creature.name).clicked() {
+                            self.place_lenia_creature(creature);
+                        }
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let is_lenia = matches!(self.continuous_rule, ContinuousRule::Lenia(_));
+                    if ui.radio(is_lenia, "Lenia").clicked() && !is_lenia {
+                        self.continuous_rule = ContinuousRule::Lenia(LeniaParameters::default());
+                        self.ring_heights_text = String::from("1");
+                    }
+                    if ui.radio(!is_lenia, "SmoothLife").clicked() && is_lenia {
+                        self.continuous_rule = ContinuousRule::SmoothLife(SmoothLifeParameters::default());
+                    }
+                });
+                match &mut self.continuous_rule {
+                    ContinuousRule::Lenia(parameters) => {
+                        ui.add(egui::Slider::new(&mut parameters.radius, 2..=50).text("Kernel radius (R)"));
+                        ui.add(egui::Slider::new(&mut parameters.time_resolution, 1.0..=50.0).text("Time resolution (T)"));
+                        ui.add(egui::Slider::new(&mut parameters.growth_center, 0.0..=0.5).text("Growth center (μ)"));
+                        ui.add(egui::Slider::new(&mut parameters.growth_width, 0.001..=0.1).text("Growth width (σ)"));
+                        ui.horizontal(|ui| {
+                            ui.label("Ring heights (β):");
+                            ui.text_edit_singleline(&mut self.ring_heights_text)
+                                .on_hover_text("Relative heights of the kernel's rings, innermost first, e.g. 1,0.5");
+                        });
+                    }
+                    ContinuousRule::SmoothLife(parameters) => {
+                        ui.add(egui::Slider::new(&mut parameters.inner_radius, 1.0..=30.0).text("Inner radius"));
+                        ui.add(egui::Slider::new(&mut parameters.outer_radius, 2.0..=60.0).text("Outer radius"));
+                        ui.add(egui::Slider::new(&mut parameters.birth_min, 0.0..=1.0).text("Birth min"));
+                        ui.add(egui::Slider::new(&mut parameters.birth_max, 0.0..=1.0).text("Birth max"));
+                        ui.add(egui::Slider::new(&mut parameters.death_min, 0.0..=1.0).text("Survival min"));
+                        ui.add(egui::Slider::new(&mut parameters.death_max, 0.0..=1.0).text("Survival max"));
+                        ui.add(egui::Slider::new(&mut parameters.alpha_n, 0.001..=0.5).text("Ring smoothness (αn)"));
+                        ui.add(egui::Slider::new(&mut parameters.alpha_m, 0.001..=0.5).text("Disk smoothness (αm)"));
+                        ui.add(egui::Slider::new(&mut parameters.time_step, 0.01..=1.0).text("Time step"));
+                    }
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        self.apply_continuous_rule();
+                    }
+                    if self.game.get_continuous_rule().is_some() && ui.button("Back to B/S rules").clicked() {
+                        self.game.set_continuous_rule(None);
+                    }
+                });
+                if !self.continuous_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.continuous_error_message);
+                }
+                if self.game.get_continuous_rule().is_some() {
+                    ui.label("Left click fills a cell, right click empties it; the color map is chosen in the color controls");
+                }
+            });
+    }
+
+    fn apply_continuous_rule(&mut self) {
+        if let ContinuousRule::Lenia(parameters) = &mut self.continuous_rule {
+            let mut ring_heights: Vec<f32> = Vec::new();
+            for ring_height in self.ring_heights_text.split(',') {
+                let ring_height = ring_height.trim();
+                match ring_height.parse::<f32>() {
+                    Ok(ring_height) if ring_height >= 0.0 => ring_heights.push(ring_height),
+                    _ => {
+                        self.continuous_error_message = String::from("\"") + ring_height + "\" is not a ring height";
+                        return;
+                    }
+                }
+            }
+            parameters.ring_heights = ring_heights;
+        }
+        self.game.set_continuous_rule(Some(self.continuous_rule.clone()));
+        self.continuous_error_message.clear();
+    }
+
+    /// Switches to the creature's parameters and places it alone in the middle of the board
+    fn place_lenia_creature(&mut self, creature: &LeniaCreature) {
+        let pattern = match ContinuousField::from_hex_rows(creature.cells) {
+            Ok(pattern) => pattern,
+            Err(error_message) => {
+                self.continuous_error_message = error_message;
+                return;
+            }
+        };
+        let parameters = creature.get_parameters();
+        let ring_heights: Vec<String> = parameters.ring_heights.iter().map(|ring_height| ring_height.to_string()).collect();
+        self.ring_heights_text = ring_heights.join(",");
+        self.continuous_rule = ContinuousRule::Lenia(parameters);
+        self.game.set_continuous_rule(Some(self.continuous_rule.clone()));
+        self.game.clear();
+        let continuous_field = self.game.get_continuous_field_mut();
+        let center_x = continuous_field.get_width() / 2;
+        let center_y = continuous_field.get_height() / 2;
+        continuous_field.paste(&pattern, center_x, center_y);
+        self.continuous_error_message.clear();
+    }
+}
+
+impl TemplateApp {
+    fn show_three_dimensional_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Three-dimensional view")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 550.0))
+            .show(ctx, |ui| {
+                if self.game.get_grid_type() != GridType::ThreeDimensional {
+                    ui.label("Start a new three-dimensional game to use this view");
+                    return;
+                }
+
+                let voxel_field = self.game.get_voxel_field();
+                let depth = voxel_field.get_depth();
+                let living_cells_text = String::from("Universe of ") + &voxel_field.get_width().to_string() + "x" + &voxel_field.get_height().to_string() + "x" + &depth.to_string()
+                    + " cells, " + &voxel_field.get_number_of_living_cells().to_string() + " alive";
+                ui.label(living_cells_text);
+                ui.horizontal(|ui| {
+                    ui.label("View:");
+                    ui.radio_value(&mut self.three_dimensional_view, ThreeDimensionalView::Slice, "Slice")
+                        .on_hover_text("Shows (and lets you edit) a single Z layer");
+                    ui.radio_value(&mut self.three_dimensional_view, ThreeDimensionalView::Isometric, "Isometric")
+                        .on_hover_text("Shows the layers up to the Z slice as cubes");
+                });
+                ui.add(egui::Slider::new(&mut self.shown_z_slice, 0..=depth.saturating_sub(1)).text("Z slice"));
+
+                let mut voxel_neighbourhood = self.game.get_voxel_neighbourhood();
+                ui.horizontal(|ui| {
+                    ui.label("Neighbourhood:");
+                    ui.radio_value(&mut voxel_neighbourhood, VoxelNeighbourhood::Moore, "Moore (26 cells)");
+                    ui.radio_value(&mut voxel_neighbourhood, VoxelNeighbourhood::VonNeumann, "Von Neumann (6 cells)");
+                });
+                self.game.set_voxel_neighbourhood(voxel_neighbourhood);
+                ui.horizontal(|ui| {
+                    ui.label("Rules:");
+                    for (name, rule_string) in [("Life 4555", BAYS_4555), ("Life 5766", BAYS_5766)] {
+                        if ui.button(name).on_hover_text(rule_string).clicked() {
+                            if let Ok(rule) = Rule::parse(rule_string) {
+                                self.rule_text = rule.to_rule_string();
+                                self.game.set_rule(rule);
+                                self.game.set_voxel_neighbourhood(VoxelNeighbourhood::Moore);
+                            }
+                        }
+                    }
+                });
+                ui.label("The B/S rule counts living neighbours in three dimensions; stepping and randomizing use the usual controls");
+            });
+    }
+}
+
+impl TemplateApp {
+    fn show_update_mode_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Update mode")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 600.0))
+            .show(ctx, |ui| {
+                ui.label("How B/S rules update the cells of square and triangular grids");
+                let mut update_settings = self.game.get_update_settings().clone();
+                ui.radio_value(&mut update_settings.mode, UpdateMode::Synchronous, "Synchronous")
+                    .on_hover_text("Every cell is updated at once from the previous generation");
+                ui.radio_value(&mut update_settings.mode, UpdateMode::RandomOrder, "Asynchronous, random order")
+                    .on_hover_text("Cells are updated one at a time in a new random order every generation, each seeing the updates made before it");
+                ui.radio_value(&mut update_settings.mode, UpdateMode::AlphaAsynchronous, "α-asynchronous")
+                    .on_hover_text("Every cell is updated at once, but each only with probability α; the others keep their state");
+                if update_settings.mode == UpdateMode::AlphaAsynchronous {
+                    ui.add(egui::Slider::new(&mut update_settings.synchrony_rate, 0.0..=1.0).text("Synchrony rate (α)"));
+                }
+                ui.add(egui::Slider::new(&mut update_settings.birth_probability, 0.0..=1.0).text("Birth probability"))
+                    .on_hover_text("Probability of a birth allowed by the rule actually happening");
+                ui.add(egui::Slider::new(&mut update_settings.survival_probability, 0.0..=1.0).text("Survival probability"))
+                    .on_hover_text("Probability of a survival allowed by the rule actually happening; otherwise the cell dies");
+                self.game.set_update_settings(update_settings);
+
+                ui.separator();
+                let seed_text = String::from("Random numbers come from seed ") + &self.game.get_update_seed().to_string() + "; runs from the same seed, settings and cells are identical";
+                ui.label(seed_text);
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    ui.add(egui::DragValue::new(&mut self.update_seed));
+                    if ui.button("Restart from seed").clicked() {
+                        self.game.set_update_seed(self.update_seed);
+                    }
+                });
+            });
+    }
+}
+
+impl TemplateApp {
+    fn show_soup_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Soup options")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 650.0))
+            .show(ctx, |ui| {
+                ui.label("Options of the random soups placed by \"Randomize cells\", as in apgsearch");
+                ui.horizontal(|ui| {
+                    ui.label("Symmetry:");
+                    egui::ComboBox::from_id_salt("soup_symmetry")
+                        .selected_text(self.soup_settings.symmetry.get_name())
+                        .show_ui(ui, |ui| {
+                            for symmetry in SOUP_SYMMETRIES {
+                                ui.selectable_value(&mut self.soup_settings.symmetry, symmetry, symmetry.get_name());
+                            }
+                        });
+                }).response.on_hover_text("_1 centres the symmetry on a cell, _2 on the midpoint of an edge and _4 on a vertex; + reflects across orthogonal axes and x across diagonal ones");
+
+                let mut has_region = self.soup_settings.region_size.is_some();
+                ui.checkbox(&mut has_region, "Only fill a centred square region");
+                if has_region {
+                    let mut region_size = self.soup_settings.region_size.unwrap_or(16);
+                    ui.add(egui::Slider::new(&mut region_size, 1..=1000).text("Region size"));
+                    self.soup_settings.region_size = Some(region_size);
+                }
+                else {
+                    self.soup_settings.region_size = None;
+                }
+
+                ui.add(egui::Slider::new(&mut self.soup_settings.probability_living_cell, 0.0..=100.0).text("Probability of living cell (percentage)"));
+                ui.horizontal(|ui| {
+                    ui.label("Gradient:");
+                    ui.radio_value(&mut self.soup_settings.gradient, SoupGradient::Uniform, "None");
+                    ui.radio_value(&mut self.soup_settings.gradient, SoupGradient::LeftToRight, "Left to right");
+                    ui.radio_value(&mut self.soup_settings.gradient, SoupGradient::CentreToEdges, "Centre to corners");
+                });
+                if self.soup_settings.gradient != SoupGradient::Uniform {
+                    ui.add(egui::Slider::new(&mut self.soup_settings.gradient_end_probability_living_cell, 0.0..=100.0).text("Probability at the end of the gradient"));
+                }
+                ui.label(self.soup_settings.to_description_string());
+                if ui.button("Randomize cells").clicked() {
+                    self.randomize_game();
+                }
+            });
+    }
+}
+
+impl TemplateApp {
+    fn show_soup_search_window(&mut self, ctx: &egui::Context) {
+        self.soup_search_runner.run_pending_soups();
+        if self.soup_search_runner.is_running() {
+            ctx.request_repaint();
+        }
+        egui::Window::new("Soup search")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 700.0))
+            .show(ctx, |ui| {
+                ui.label("Runs seeded soups (with the soup options, 16x16 unless a region is set) on an unbounded plane until they become periodic, then censuses the objects left");
+                ui.horizontal(|ui| {
+                    ui.label("First seed:");
+                    ui.add(egui::DragValue::new(&mut self.soup_search_first_seed));
+                });
+                ui.add(egui::Slider::new(&mut self.soup_search_max_generations, 100..=100_000).logarithmic(true).text("Give up on soups after this many generations"));
+                ui.horizontal(|ui| {
+                    if self.soup_search_runner.is_running() {
+                        if ui.button("Stop").clicked() {
+                            self.soup_search_runner.stop();
+                        }
+                    }
+                    else if ui.button("Start searching").clicked() {
+                        let settings = SoupSearchSettings{
+                            rule: self.game.get_rule().clone(),
+                            soup_settings: self.soup_settings.clone(),
+                            first_seed: self.soup_search_first_seed,
+                            max_generations: self.soup_search_max_generations
+                        };
+                        match self.soup_search_runner.start(settings) {
+                            Ok(()) => self.soup_search_error_message.clear(),
+                            Err(error_message) => self.soup_search_error_message = error_message,
+                        }
+                    }
+                });
+                if !self.soup_search_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.soup_search_error_message);
+                }
+
+                let Some(settings) = self.soup_search_runner.get_settings().cloned() else {
+                    return;
+                };
+                let results = self.soup_search_runner.get_results();
+                let summary_text = String::from("Searched ") + &results.n_soups_searched.to_string() + " soups of " + &settings.rule.to_rule_string()
+                    + " (" + &settings.soup_settings.to_description_string() + "), " + &results.unstabilised_seeds.len().to_string() + " of which didn't stabilise";
+                ui.label(summary_text);
+
+                let unknown_objects = results.get_unknown_objects();
+                if !unknown_objects.is_empty() {
+                    ui.separator();
+                    ui.label("Objects not in the known objects list:");
+                    egui::ScrollArea::vertical().id_salt("unknown_objects").max_height(150.0).show(ui, |ui| {
+                        for (code, tally) in unknown_objects {
+                            ui.horizontal(|ui| {
+                                let object_text = code.clone() + " (" + &tally.kind.to_description_string() + "), first in soup " + &tally.first_seed.to_string();
+                                ui.label(object_text);
+                                if ui.button("Show soup").clicked() {
+                                    self.show_searched_soup(&settings, tally.first_seed);
+                                }
+                            });
+                        }
+                    });
+                }
+
+                ui.separator();
+                ui.label("Tally:");
+                egui::ScrollArea::vertical().id_salt("object_tally").max_height(250.0).show(ui, |ui| {
+                    egui::Grid::new("object_tally_grid").striped(true).show(ui, |ui| {
+                        for (code, tally) in results.get_sorted_tallies() {
+                            ui.label(tally.count.to_string());
+                            ui.label(code);
+                            ui.label(tally.name.clone().unwrap_or_default());
+                            ui.label(tally.kind.to_description_string());
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+
+    /// Places the searched soup with the given seed on the board, under the rule it was searched with
+    fn show_searched_soup(&mut self, settings: &SoupSearchSettings, seed: u64) {
+        self.soup_settings = settings.soup_settings.clone();
+        self.soup_settings.region_size = Some(self.soup_settings.region_size.unwrap_or(16));
+        self.soup_seed = seed;
+        self.new_soup_seed_every_time = false;
+        self.rule_text = settings.rule.to_rule_string();
+        self.game.set_rule(settings.rule.clone());
+        self.game.randomize(&self.soup_settings, seed);
+    }
+}
+
+impl TemplateApp {
+    fn show_predecessor_window(&mut self, ctx: &egui::Context) {
+        if let Some(predecessor_search) = &mut self.predecessor_search {
+            if predecessor_search.get_result().is_none() {
+                predecessor_search.step(PREDECESSOR_SEARCH_CONFLICTS_PER_FRAME);
+                ctx.request_repaint();
+            }
+        }
+        egui::Window::new("Predecessor search")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(600.0, 700.0))
+            .show(ctx, |ui| {
+                ui.label("Looks for parents of the selected cells (one generation back, ignoring the wrapping of the board) with a SAT solver, or proves that none exists");
+                ui.checkbox(&mut self.selecting_cells, "Dragging on the board selects cells (right click clears the selection)");
+                let selection_text = match self.selection {
+                    Some(selection) => String::from("Selection: ") + &selection.width.to_string() + "x" + &selection.height.to_string()
+                        + " cells from (" + &selection.x.to_string() + ", " + &selection.y.to_string() + ")",
+                    None => String::from("Nothing selected"),
+                };
+                ui.label(selection_text);
+                ui.checkbox(&mut self.predecessor_surroundings_must_die, "The cells around the selection must be dead too")
+                    .on_hover_text("Finds parents that work on an otherwise empty board, instead of parents of the selected cells alone");
+                ui.add(egui::Slider::new(&mut self.predecessor_max_parents, 1..=20).text("Parents to find"));
+                ui.add(egui::Slider::new(&mut self.predecessor_conflict_limit, 1000..=10_000_000).logarithmic(true).text("Give up after this many conflicts"));
+
+                if !self.is_binary_square_game() {
+                    ui.label("Predecessor search works with B/S rules on square boards");
+                    return;
+                }
+                let is_searching = self.predecessor_search.as_ref().is_some_and(|predecessor_search| predecessor_search.get_result().is_none());
+                if is_searching {
+                    if ui.button("Stop").clicked() {
+                        if let Some(predecessor_search) = &mut self.predecessor_search {
+                            predecessor_search.stop();
+                        }
+                    }
+                }
+                else if let Some(selection) = self.selection {
+                    if ui.button("Search for parents").clicked() {
+                        let rule = self.game.get_rule().clone();
+                        self.predecessor_search = Some(PredecessorSearch::new(self.game.get_field(), selection, &rule,
+                            self.predecessor_surroundings_must_die, self.predecessor_max_parents, self.predecessor_conflict_limit));
+                    }
+                }
+
+                let Some(predecessor_search) = &self.predecessor_search else {
+                    return;
+                };
+                ui.separator();
+                let parents = match predecessor_search.get_result() {
+                    None => {
+                        ui.label(String::from("Searching... ") + &predecessor_search.get_parents().len().to_string() + " parent(s) found so far");
+                        predecessor_search.get_parents()
+                    }
+                    Some(PredecessorSearchResult::GardenOfEden) => {
+                        ui.label("No parent exists: the selected cells are an orphan, so any pattern containing them is a Garden of Eden");
+                        &[]
+                    }
+                    Some(PredecessorSearchResult::NoIsolatedParent) => {
+                        ui.label("No parent within two cells of the selection leaves the cells around it dead, so the selected cells can't appear on an otherwise empty board from such a parent; they may still have other parents");
+                        &[]
+                    }
+                    Some(PredecessorSearchResult::GaveUp) => {
+                        ui.label("Gave up before finding a parent or proving there is none; try a higher conflict limit");
+                        &[]
+                    }
+                    Some(PredecessorSearchResult::Parents(parents)) => {
+                        ui.label(String::from("Found ") + &parents.len().to_string() + " parent(s):");
+                        parents.as_slice()
+                    }
+                };
+                let mut parent_to_place: Option<Field> = None;
+                if !parents.is_empty() {
+                    egui::ScrollArea::vertical().id_salt("parents").max_height(400.0).show(ui, |ui| {
+                        for parent in parents {
+                            ui.horizontal(|ui| {
+                                ui.monospace(get_pattern_text(parent));
+                                if ui.button("Place on the board").clicked() {
+                                    parent_to_place = Some(parent.clone());
+                                }
+                            });
+                        }
+                    });
+                }
+                if let Some(parent) = parent_to_place {
+                    predecessor_search.place_parent(&parent, self.game.get_field_mut());
+                }
+            });
+    }
+}
+
+impl TemplateApp {
+    fn show_ship_search_window(&mut self, ctx: &egui::Context) {
+        if self.is_ship_search_running {
+            if let Some(ship_search) = &mut self.ship_search {
+                ship_search.step(SHIP_SEARCH_CANDIDATE_ROWS_PER_FRAME);
+                self.is_ship_search_running = !ship_search.is_finished();
+            }
+            ctx.request_repaint();
+        }
+        egui::Window::new("Spaceship search")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(600.0, 500.0))
+            .show(ctx, |ui| {
+                ui.label("Searches row by row (like gfind) for spaceships moving orthogonally, or oscillators (displacement 0), under the current rule");
+                let settings = &mut self.ship_search_settings;
+                ui.add(egui::Slider::new(&mut settings.period, 1..=12).text("Period"));
+                ui.add(egui::Slider::new(&mut settings.displacement, 0..=11).text("Displacement (cells per period)"));
+                ui.add(egui::Slider::new(&mut settings.width, 1..=MAX_SEARCH_WIDTH).text("Width (searched columns)"));
+                ui.horizontal(|ui| {
+                    ui.label("Symmetry:");
+                    for (symmetry, name) in SEARCH_SYMMETRIES {
+                        ui.radio_value(&mut settings.symmetry, symmetry, name);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Order:");
+                    ui.radio_value(&mut settings.order, SearchOrder::BreadthFirst, "Breadth-first");
+                    ui.radio_value(&mut settings.order, SearchOrder::DepthFirst, "Depth-first");
+                });
+                ui.add(egui::Slider::new(&mut settings.max_length, 1..=200).text("Maximum length (rows)"));
+                ui.add(egui::Slider::new(&mut settings.max_nodes, 10_000..=MAX_SEARCH_NODES).logarithmic(true).text("Give up after this many partials"));
+                ui.add(egui::Slider::new(&mut settings.max_results, 1..=100).text("Stop after this many results"));
+
+                if !self.is_binary_square_game() {
+                    ui.label("Spaceship search works with B/S rules on square boards");
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Start searching").clicked() {
+                        match ShipSearch::new(self.ship_search_settings.clone(), self.game.get_rule().clone()) {
+                            Ok(ship_search) => {
+                                self.ship_search = Some(ship_search);
+                                self.is_ship_search_running = true;
+                                self.ship_search_error_message.clear();
+                            }
+                            Err(error_message) => self.ship_search_error_message = error_message,
+                        }
+                    }
+                    let can_resume = self.ship_search.as_ref().is_some_and(|ship_search| !ship_search.is_finished());
+                    if self.is_ship_search_running {
+                        if ui.button("Pause").clicked() {
+                            self.is_ship_search_running = false;
+                        }
+                    }
+                    else if can_resume && ui.button("Resume").clicked() {
+                        self.is_ship_search_running = true;
+                    }
+                });
+                if !self.ship_search_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.ship_search_error_message);
+                }
+
+                let Some(ship_search) = &self.ship_search else {
+                    return;
+                };
+                let searched = ship_search.get_settings();
+                let status_text = if !ship_search.is_finished() {
+                    "in progress"
+                }
+                else if ship_search.has_given_up() {
+                    "gave up"
+                }
+                else {
+                    "finished"
+                };
+                let summary_text = String::from("Period ") + &searched.period.to_string() + ", displacement " + &searched.displacement.to_string()
+                    + ": " + &ship_search.get_n_nodes().to_string() + " partials (" + status_text + "), " + &ship_search.get_results().len().to_string() + " result(s)";
+                ui.label(summary_text);
+
+                let mut pattern_to_place: Option<Field> = None;
+                egui::ScrollArea::vertical().id_salt("ship_search_results").max_height(400.0).show(ui, |ui| {
+                    for result in ship_search.get_results() {
+                        ui.horizontal(|ui| {
+                            ui.monospace(get_pattern_text(&result.pattern));
+                            ui.vertical(|ui| {
+                                ui.label(result.code.clone() + " (" + &result.kind.to_description_string() + ")");
+                                if ui.button("Place on the board").clicked() {
+                                    pattern_to_place = Some(result.pattern.clone());
+                                }
+                            });
+                        });
+                        ui.separator();
+                    }
+                    if let Some(partial) = ship_search.get_deepest_partial() {
+                        ui.horizontal(|ui| {
+                            ui.monospace(get_pattern_text(&partial));
+                            ui.vertical(|ui| {
+                                ui.label("Longest partial (phase 0)");
+                                if ui.button("Place on the board").clicked() {
+                                    pattern_to_place = Some(partial.clone());
+                                }
+                            });
+                        });
+                    }
+                });
+                if let Some(pattern) = pattern_to_place {
+                    self.place_pattern(&pattern);
+                }
+            });
+    }
+
+    /// Copies the pattern (dead cells included) to the middle of the board
+    fn place_pattern(&mut self, pattern: &Field) {
+        let field = self.game.get_field_mut();
+        let left = field.get_width().saturating_sub(pattern.get_width()) / 2;
+        let top = field.get_height().saturating_sub(pattern.get_height()) / 2;
+        for y in 0..pattern.get_height() {
+            for x in 0..pattern.get_width() {
+                field.set_cell_state(left + x, top + y, pattern.get_cell_state(x, y));
+            }
+        }
+    }
+}
+
+impl TemplateApp {
+    fn show_methuselah_search_window(&mut self, ctx: &egui::Context) {
+        if self.is_methuselah_search_running {
+            if let Some(methuselah_search) = &mut self.methuselah_search {
+                methuselah_search.step(METHUSELAH_SEARCH_GENERATIONS_PER_FRAME);
+            }
+            ctx.request_repaint();
+        }
+        egui::Window::new("Methuselah search")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(600.0, 300.0))
+            .show(ctx, |ui| {
+                ui.label("Evolves small seed patterns with a genetic algorithm, running each on an unbounded plane under the current rule until it stabilises");
+                let settings = &mut self.methuselah_search_settings;
+                ui.add(egui::Slider::new(&mut settings.population_size, 2..=200).text("Population size"));
+                ui.add(egui::Slider::new(&mut settings.mutation_rate, 0.0..=0.5).text("Mutation rate (per cell)"));
+                ui.add(egui::Slider::new(&mut settings.box_size, 1..=16).text("Box size"));
+                ui.add(egui::Slider::new(&mut settings.max_generations, 100..=100_000).logarithmic(true).text("Disqualify patterns unstable after this many generations"));
+                ui.horizontal(|ui| {
+                    ui.label("Fitness:");
+                    for (fitness, name) in METHUSELAH_FITNESSES {
+                        ui.radio_value(&mut settings.fitness, fitness, name);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    ui.add(egui::DragValue::new(&mut settings.seed));
+                });
+
+                if !self.is_binary_square_game() {
+                    ui.label("Methuselah search works with B/S rules on square boards");
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Start searching").clicked() {
+                        match MethuselahSearch::new(self.methuselah_search_settings.clone(), self.game.get_rule().clone()) {
+                            Ok(methuselah_search) => {
+                                self.methuselah_search = Some(methuselah_search);
+                                self.is_methuselah_search_running = true;
+                                self.methuselah_search_error_message.clear();
+                            }
+                            Err(error_message) => self.methuselah_search_error_message = error_message,
+                        }
+                    }
+                    if self.is_methuselah_search_running {
+                        if ui.button("Pause").clicked() {
+                            self.is_methuselah_search_running = false;
+                        }
+                    }
+                    else if self.methuselah_search.is_some() && ui.button("Resume").clicked() {
+                        self.is_methuselah_search_running = true;
+                    }
+                });
+                if !self.methuselah_search_error_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.methuselah_search_error_message);
+                }
+
+                let Some(methuselah_search) = &self.methuselah_search else {
+                    return;
+                };
+                ui.label(String::from("Generation ") + &methuselah_search.get_generation().to_string() + " under " + &methuselah_search.get_rule().to_rule_string());
+                ui.separator();
+                ui.label("Leaderboard:");
+                let mut pattern_to_load: Option<Field> = None;
+                let box_size = methuselah_search.get_settings().box_size;
+                egui::ScrollArea::vertical().id_salt("methuselah_leaderboard").max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("methuselah_leaderboard_grid").striped(true).show(ui, |ui| {
+                        ui.label("Fitness");
+                        ui.label("Lifespan");
+                        ui.label("Final population");
+                        ui.label("Escaping spaceships");
+                        ui.label("Pattern");
+                        ui.end_row();
+                        for candidate in methuselah_search.get_leaderboard() {
+                            let pattern = candidate.get_pattern(box_size);
+                            ui.label(candidate.fitness.to_string());
+                            ui.label(candidate.score.lifespan.to_string());
+                            ui.label(candidate.score.final_population.to_string());
+                            ui.label(candidate.score.escaping_spaceships.to_string());
+                            ui.monospace(get_pattern_text(&pattern));
+                            if ui.button("Load").clicked() {
+                                pattern_to_load = Some(pattern);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+                if let Some(pattern) = pattern_to_load {
+                    let rule = methuselah_search.get_rule().clone();
+                    self.rule_text = rule.to_rule_string();
+                    self.game.set_rule(rule);
+                    self.game.clear();
+                    self.place_pattern(&pattern);
+                }
+            });
+    }
+}
+
+impl TemplateApp {
+    fn show_activity_map_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Activity heat map")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(200.0, 300.0))
+            .show(ctx, |ui| {
+                ui.label("Counts, for each cell, the generations in which it changed state or was alive, to show where the pattern is doing work");
+                if !self.game.is_running_field() {
+                    ui.label("Only available for two-state rules on square or triangular grids");
+                }
+                if ui.checkbox(&mut self.show_activity_map, "Show the heat map over the board").changed() {
+                    self.game.set_activity_tracking(self.show_activity_map);
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Count:");
+                    for (activity_measure, name) in ACTIVITY_MEASURES {
+                        ui.radio_value(&mut self.activity_measure, activity_measure, name);
+                    }
+                });
+                let mut window = self.game.get_activity_map().get_window();
+                if ui.add(egui::Slider::new(&mut window, 0..=10_000).logarithmic(true).text("Window in generations (0 for all)")).changed() {
+                    self.game.set_activity_window(window);
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Color map:");
+                    for (color_map, name) in COLOR_MAPS {
+                        ui.radio_value(&mut self.activity_color_map, color_map, name);
+                    }
+                });
+                ui.add(egui::Slider::new(&mut self.activity_map_opacity, 0.0..=1.0).text("Opacity"));
+                if ui.button("Reset the counts").clicked() {
+                    self.game.reset_activity_map();
+                }
+
+                ui.separator();
+                let activity_map = self.game.get_activity_map();
+                let max_count = activity_map.get_max_count(self.activity_measure);
+                ui.label(String::from("Legend, over the last ") + &activity_map.get_n_generations().to_string() + " generations:");
+                ui.horizontal(|ui| {
+                    ui.label("0");
+                    draw_gradient(ui, |value| self.activity_color_map.get_color(value, self.dead_cell_color, self.living_cell_color));
+                    ui.label(max_count.max(1).to_string());
+                });
+                ui.label("Cells with a count of 0 are left uncoloured");
+            });
+    }
+}
 
-    probability_living_cell: f64,
+impl TemplateApp {
+    fn show_animation_export_window(&mut self, ctx: &egui::Context) {
+        if self.animation_recorder.is_some() {
+            self.animation_export_message = match self.step_animation_recording(ANIMATION_EXPORT_GENERATIONS_PER_FRAME) {
+                Ok(Some(bytes)) => file_saver::save_file(&self.animation_export_path, &bytes).unwrap_or_else(|error| error),
+                Ok(None) => String::new(),
+                Err(error) => error,
+            };
+            ctx.request_repaint();
+        }
+        egui::Window::new("Animation export")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(400.0, 300.0))
+            .show(ctx, |ui| {
+                ui.label("Runs the game forward and records it, with the current colors, cell size and borders, as an animated GIF or PNG; the board is left at the last generation");
+                let settings = &mut self.animation_export_settings;
+                ui.horizontal(|ui| {
+                    ui.label("Format:");
+                    for (format, name) in ANIMATION_FORMATS {
+                        if ui.radio_value(&mut settings.format, format, name).changed() {
+                            let stem = self.animation_export_path.rsplit_once('.').map_or(self.animation_export_path.as_str(), |(stem, _)| stem);
+                            self.animation_export_path = String::from(stem) + "." + format.get_extension();
+                        }
+                    }
+                });
+                ui.add(egui::Slider::new(&mut settings.n_generations, 1..=10_000).logarithmic(true).text("Generations"));
+                ui.add(egui::Slider::new(&mut settings.frame_skip, 1..=100).text("Generations per frame"));
+                ui.add(egui::Slider::new(&mut settings.frame_delay_ms, 10..=2000).logarithmic(true).text("Frame delay (ms)"));
+                ui.checkbox(&mut settings.crop_to_bounding_box, "Crop to the bounding box of the cells that aren't dead");
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.animation_export_path);
+                });
+                if let Some(animation_recorder) = &self.animation_recorder {
+                    let progress = String::from("Recording frame ") + &animation_recorder.get_n_frames_added().to_string()
+                        + " of " + &animation_recorder.get_n_frames().to_string() + "...";
+                    ui.horizontal(|ui| {
+                        ui.label(progress);
+                        if ui.button("Stop").clicked() {
+                            self.animation_recorder = None;
+                            self.animation_export_message = String::from("The recording was stopped");
+                        }
+                    });
+                } else if ui.button("Export").clicked() {
+                    let settings = self.animation_export_settings.clone();
+                    self.animation_export_message = match self.start_animation_recording(&settings) {
+                        Ok(()) => String::new(),
+                        Err(error) => error,
+                    };
+                }
+                if !self.animation_export_message.is_empty() {
+                    ui.label(&self.animation_export_message);
+                }
+            });
+    }
 
-    consider_extremes_adjacent: bool,
+    fn get_frame_style(&self) -> FrameStyle {
+        FrameStyle {
+            cell_size: self.cell_size,
+            cell_border_size: self.cell_border_size,
+            border_color: self.border_color,
+            dead_cell_color: self.dead_cell_color
+        }
+    }
 
-    #[serde(skip)] // This how you opt-out of serialization of a field
-    debug_message: String,
+    /// Rule, generation and soup seed of the board, recorded in exported files so they can be reproduced
+    fn get_export_description(&self) -> String {
+        let mut description = String::from("Rule ") + &self.game.get_rule().to_rule_string() + ", generation " + &self.game.get_generation().to_string();
+        if let Some(soup) = self.game.get_soup() {
+            description = description + ". " + &soup.to_comment_string();
+        }
+        description
+    }
 
-    game: Game,
-}
+    /// Colours of the cells as drawn by `draw_square_board`
+    fn get_cell_colors(&mut self) -> CellColors {
+        let width = self.game.get_field().get_width();
+        let height = self.game.get_field().get_height();
+        let mut colors: Vec<egui::Color32> = Vec::with_capacity(width * height);
+        for j in 0..height {
+            for i in 0..width {
+                colors.push(self.get_square_cell_color(i, j));
+            }
+        }
+        CellColors{width, height, colors}
+    }
 
-impl Default for TemplateApp {
-    fn default() -> Self {
-        let default_probability_living_cell: f64 = 30.0;
-        let default_game_width = 20;
-        let default_game_height = 20;
-        let mut game: Game = Game::new(default_game_width, default_game_height);
-        game.randomize(default_probability_living_cell);
-        Self {
-            cell_size: 15,
-            cell_border_size: 1,
-            living_cell_color: egui::Color32::GREEN,
-            dead_cell_color: egui::Color32::GRAY,
-            border_color: egui::Color32::BLACK,
-            n_generations_to_advance: 100,
-            new_game_width: default_game_width,
-            new_game_height: default_game_height,
-            probability_living_cell: default_probability_living_cell,
-            consider_extremes_adjacent: true,
-            debug_message: String::from("<NO DEBUG MESSAGE>"),
-            game
+    /// Starts recording an animation from the current generation; the game is then advanced by `step_animation_recording`
+    fn start_animation_recording(&mut self, settings: &AnimationExportSettings) -> Result<(), String> {
+        if self.game.get_grid_type() == GridType::Triangular {
+            return Err(String::from("Only boards of square cells can be exported"));
+        }
+        let mut animation_recorder = AnimationRecorder::new(settings, self.get_frame_style(), &self.get_export_description())?;
+        animation_recorder.add_frame(self.get_cell_colors())?;
+        self.animation_recorder = Some(animation_recorder);
+        Ok(())
+    }
+
+    /// Advances the game by up to `n_generations` generations, encoding every `frame_skip`-th one as it is produced.
+    /// Returns the encoded file once the last frame has been recorded
+    fn step_animation_recording(&mut self, n_generations: usize) -> Result<Option<Vec<u8>>, String> {
+        let Some(mut animation_recorder) = self.animation_recorder.take() else {
+            return Ok(None);
+        };
+        for _ in 0..n_generations {
+            if animation_recorder.is_complete() {
+                break;
+            }
+            self.game.advance_generation(self.consider_extremes_adjacent);
+            if animation_recorder.next_generation() {
+                animation_recorder.add_frame(self.get_cell_colors())?;
+            }
         }
+        if animation_recorder.is_complete() {
+            return animation_recorder.finish().map(Some);
+        }
+        self.animation_recorder = Some(animation_recorder);
+        Ok(None)
+    }
+
+    /// Records the current generation, then every `frame_skip`-th generation while advancing the game, all at once
+    fn record_animation(&mut self, settings: &AnimationExportSettings) -> Result<Vec<u8>, String> {
+        self.start_animation_recording(settings)?;
+        self.step_animation_recording(usize::MAX)?.ok_or_else(|| String::from("The recording didn't finish"))
     }
 }
 
 impl TemplateApp {
-    /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        // This is also where you can customize the look and feel of egui using
-        // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
+    fn show_image_window(&mut self, ctx: &egui::Context) {
+        let dropped_files = ctx.input(|input| input.raw.dropped_files.clone());
+        for dropped_file in dropped_files {
+            let is_image = [".png", ".bmp"].iter().any(|extension| dropped_file.name.to_lowercase().ends_with(extension)
+                || dropped_file.path.as_ref().is_some_and(|path| path.to_string_lossy().to_lowercase().ends_with(extension)));
+            if !is_image {
+                continue;
+            }
+            let bytes = match (&dropped_file.bytes, &dropped_file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => file_saver::read_file(&path.to_string_lossy()),
+                (None, None) => Err(String::from("Couldn't read the dropped file")),
+            };
+            self.image_message = bytes.and_then(|bytes| self.import_image_bytes(&bytes)).unwrap_or_else(|error| error);
+        }
 
-        // Load previous app state (if any).
-        // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        egui::Window::new("Images")
+            .resizable(true)
+            .collapsible(true)
+            .default_open(false)
+            .default_pos(egui::pos2(400.0, 350.0))
+            .show(ctx, |ui| {
+                ui.label("PNG snapshot of the board:");
+                ui.horizontal(|ui| {
+                    for (snapshot_scale, name) in SNAPSHOT_SCALES {
+                        ui.radio_value(&mut self.snapshot_scale, snapshot_scale, name);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.snapshot_path);
+                    if ui.button("Export").clicked() {
+                        self.image_message = self.export_snapshot().unwrap_or_else(|error| error);
+                    }
+                });
+
+                ui.separator();
+                ui.label("SVG image of the board, for figures:");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.svg_export_settings.grid_lines, "Grid lines");
+                    ui.checkbox(&mut self.svg_export_settings.caption, "Generation and rule caption");
+                    ui.add_enabled(self.selection.is_some(), egui::Checkbox::new(&mut self.svg_export_settings.selection_only, "Only the selection"))
+                        .on_disabled_hover_text("Select cells by dragging on the board with the selection tool of the predecessor search");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.svg_path);
+                    if ui.button("Export").clicked() {
+                        self.image_message = self.export_svg().unwrap_or_else(|error| error);
+                    }
+                });
+
+                ui.separator();
+                ui.label("Pattern from a PNG or BMP image, dark pixels becoming living cells (drop an image onto the window to import it):");
+                let settings = &mut self.image_import_settings;
+                ui.add(egui::Slider::new(&mut settings.threshold, 0..=255).text("Luminance threshold"));
+                ui.add(egui::Slider::new(&mut settings.pixels_per_cell, 1..=32).text("Pixels per cell"));
+                ui.checkbox(&mut settings.dithering, "Dithering (grey areas become patterns of matching density)");
+                ui.checkbox(&mut settings.invert, "Invert (light pixels become living cells)");
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.image_import_path);
+                    if ui.button("Import").clicked() {
+                        self.image_message = file_saver::read_file(&self.image_import_path)
+                            .and_then(|bytes| self.import_image_bytes(&bytes))
+                            .unwrap_or_else(|error| error);
+                    }
+                });
+                if !self.image_message.is_empty() {
+                    ui.label(&self.image_message);
+                }
+            });
+    }
+
+    fn export_snapshot(&mut self) -> Result<String, String> {
+        if self.game.get_grid_type() == GridType::Triangular {
+            return Err(String::from("Only boards of square cells can be exported"));
         }
+        let cell_colors = self.get_cell_colors();
+        let bytes = encode_snapshot(&cell_colors, &self.get_frame_style(), self.snapshot_scale, &self.get_export_description())?;
+        file_saver::save_file(&self.snapshot_path, &bytes)
+    }
 
-        Default::default()
+    fn export_svg(&mut self) -> Result<String, String> {
+        if self.game.get_grid_type() == GridType::Triangular {
+            return Err(String::from("Only boards of square cells can be exported"));
+        }
+        let mut cell_colors = self.get_cell_colors();
+        if let Some(selection) = self.selection.filter(|_| self.svg_export_settings.selection_only) {
+            let width = selection.width.min(cell_colors.width.saturating_sub(selection.x));
+            let height = selection.height.min(cell_colors.height.saturating_sub(selection.y));
+            cell_colors = cell_colors.get_region(selection.x, selection.y, width, height);
+        }
+        if cell_colors.width == 0 || cell_colors.height == 0 {
+            return Err(String::from("There are no cells to export"));
+        }
+        let caption = String::from("Generation ") + &self.game.get_generation().to_string() + ", rule " + &self.game.get_rule().to_rule_string();
+        let svg = encode_svg(&cell_colors, &self.get_frame_style(), &self.svg_export_settings, &caption, &self.get_export_description());
+        file_saver::save_file(&self.svg_path, svg.as_bytes())
     }
 
-    fn draw_board(&mut self, ui: &mut egui::Ui, response: &egui::Response, painter: &egui::Painter) {
-        let cell_width = self.cell_size;
-        let cell_height = self.cell_size;
-        let cell_border_size = self.cell_border_size;
-        for i in 0..self.game.get_field().get_width() {
-            for j in 0..self.game.get_field().get_height() {
-                let cell_state: game::field::CellState = self.game.get_field().get_cell_state(i, j);
-                let cell_color: egui::Color32 = if cell_state == game::field::CellState::Alive {self.living_cell_color} else {self.dead_cell_color};
-                draw_rectangle(ui, &response, &painter, cell_width * i, cell_height * j, cell_width, cell_height, self.border_color);
-                draw_rectangle(ui, &response, &painter, cell_width * i + cell_border_size, cell_height * j + cell_border_size, cell_width - 2 * cell_border_size, cell_height - 2 * cell_border_size, cell_color);
-            }
+    /// Clears the board and places the pattern of the image in its middle
+    fn import_image_bytes(&mut self, bytes: &[u8]) -> Result<String, String> {
+        if !self.is_binary_square_game() {
+            return Err(String::from("Images can only be imported onto square boards of living and dead cells"));
         }
+        let pattern = import_image(bytes, &self.image_import_settings)?;
+        self.game.clear();
+        self.place_pattern(&pattern);
+        let size = pattern.get_width().to_string() + "x" + &pattern.get_height().to_string();
+        let field = self.game.get_field();
+        if pattern.get_width() > field.get_width() || pattern.get_height() > field.get_height() {
+            return Ok(String::from("Imported a ") + &size + " pattern, cropped to fit the board (start a bigger game to fit all of it)");
+        }
+        Ok(String::from("Imported a ") + &size + " pattern")
     }
+}
 
-    fn on_mouse_press_on_game_window(&mut self, pos : egui::Pos2, was_right_click: bool) {
-        let x_pressed = pos.x as usize;
-        let y_pressed = pos.y as usize;
-        let x_within_square_representation = x_pressed % self.cell_size;
-        let y_within_square_representation = y_pressed % self.cell_size;
-        let x_was_within_square : bool = self.cell_border_size < x_within_square_representation && x_within_square_representation + self.cell_border_size < self.cell_size;
-        let y_was_within_square : bool = self.cell_border_size < y_within_square_representation && y_within_square_representation + self.cell_border_size < self.cell_size;
-        if x_was_within_square && y_was_within_square {
-            let x_clicked_cell = x_pressed / self.cell_size;
-            let y_clicked_cell = y_pressed / self.cell_size;
-            self.cell_clicked(x_clicked_cell, y_clicked_cell, was_right_click);
+impl TemplateApp {
+    /// The board, if it is one of living and dead cells on a square or triangular grid, under a B/S rule
+    fn get_shared_board(&mut self) -> Result<SharedBoard, String> {
+        let grid_type = self.game.get_grid_type();
+        let is_field_shareable = self.game.is_running_field() && self.game.get_margolus_rule().is_none();
+        if !is_field_shareable {
+            return Err(String::from("Only boards of living and dead cells, on square or triangular grids, under a B/S rule can be shared"));
         }
-        //self.debug_message = "Mouse button at: ".to_owned() + &x_pressed.to_string() + "-" + &y_pressed.to_string();
+        Ok(SharedBoard {
+            rule: self.game.get_rule().clone(),
+            grid_type,
+            triangular_neighbourhood: self.game.get_triangular_neighbourhood(),
+            consider_extremes_adjacent: self.consider_extremes_adjacent,
+            field: self.game.get_field().clone()
+        })
     }
 
-    fn cell_clicked(&mut self, x: usize, y: usize, was_right_click: bool) {
-        let new_cell_state: CellState = if was_right_click {CellState::Dead} else {CellState::Alive};
-        self.game.get_field().set_cell_state(x, y, new_cell_state);
-        //self.debug_message = "Clicked cell: ".to_owned() + &x.to_string() + "-" + &y.to_string();
+    /// Starts a new game on the shared board
+    fn open_shared_board(&mut self, shared_board: SharedBoard) {
+        let width = shared_board.field.get_width();
+        let height = shared_board.field.get_height();
+        self.game = Game::new_with_grid_type(width, height, shared_board.grid_type);
+        self.rule_text = shared_board.rule.to_rule_string();
+        self.game.set_rule(shared_board.rule);
+        self.game.set_triangular_neighbourhood(shared_board.triangular_neighbourhood);
+        self.game.set_field(shared_board.field).ok(); // the game was made to its size
+        self.apply_game_settings();
+        self.consider_extremes_adjacent = shared_board.consider_extremes_adjacent;
+        self.new_game_width = width;
+        self.new_game_height = height;
+        self.new_game_grid_type = shared_board.grid_type;
+        self.selection = None;
     }
 
-    fn show_color_controls_window(&mut self, ctx: &egui::Context) {
-        egui::Window::new("Color controls")
+    /// Copies a link to the board to the clipboard
+    fn copy_share_link(&mut self, ui: &mut egui::Ui) -> Result<String, String> {
+        let fragment = self.get_shared_board()?.to_fragment();
+        let link = share_link::get_share_link(&fragment);
+        ui.output_mut(|output| output.copied_text = link.clone());
+        Ok(String::from("Copied a link of ") + &link.len().to_string() + " characters")
+    }
+}
+
+impl TemplateApp {
+    fn show_load_errors_window(&mut self, ctx: &egui::Context) {
+        if self.load_errors.is_empty() {
+            return;
+        }
+        let mut is_open = true;
+        egui::Window::new("Couldn't load the saved state")
+            .open(&mut is_open)
+            .collapsible(false)
+            .default_pos(egui::pos2(300.0, 200.0))
+            .show(ctx, |ui| {
+                for error in &self.load_errors {
+                    ui.label(error);
+                }
+                ui.label("Defaults replace what couldn't be read. The unreadable saves are kept in storage under their key followed by \"_unreadable\", and can be exported:");
+                ui.horizontal(|ui| {
+                    if ui.button("Export the unreadable saves").clicked() {
+                        for (key, text) in &self.unreadable_saves {
+                            let path = String::from("rusty-life-unreadable-") + key + ".ron";
+                            let message = file_saver::save_file(&path, text.as_bytes()).unwrap_or_else(|error| error);
+                            self.load_errors.push(message);
+                        }
+                    }
+                });
+            });
+        if !is_open {
+            self.load_errors.clear();
+        }
+    }
+}
+
+impl TemplateApp {
+    fn show_save_slots_window(&mut self, ctx: &egui::Context) {
+        let dropped_files = ctx.input(|input| input.raw.dropped_files.clone());
+        for dropped_file in dropped_files {
+            let is_save_slots_file = dropped_file.name.to_lowercase().ends_with(".ron")
+                || dropped_file.path.as_ref().is_some_and(|path| path.to_string_lossy().to_lowercase().ends_with(".ron"));
+            if !is_save_slots_file {
+                continue;
+            }
+            let bytes = match (&dropped_file.bytes, &dropped_file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => file_saver::read_file(&path.to_string_lossy()),
+                (None, None) => Err(String::from("Couldn't read the dropped file")),
+            };
+            self.save_slots_message = bytes.and_then(|bytes| self.import_save_slots(&bytes)).unwrap_or_else(|error| error);
+        }
+
+        egui::Window::new("Saved boards")
             .resizable(true)
             .collapsible(true)
             .default_open(false)
-            .default_pos(egui::pos2(200.0, 0.0))
+            .default_pos(egui::pos2(600.0, 350.0))
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
-                    ui.vertical(|ui| {
-                        ui.label("Living cell color:");
-                        egui::widgets::color_picker::color_picker_color32(ui, &mut self.living_cell_color, Alpha::Opaque);
-                    });
-                    ui.separator();
-                    ui.vertical(|ui| {
-                        ui.label("Dead cell color:");
-                        egui::widgets::color_picker::color_picker_color32(ui, &mut self.dead_cell_color, Alpha::Opaque);
-                    });
-                    ui.separator();
-                    ui.vertical(|ui| {
-                        ui.label("Border color:");
-                        egui::widgets::color_picker::color_picker_color32(ui, &mut self.border_color, Alpha::Opaque);
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut self.save_slot_name);
+                    if ui.button("Save the board").on_hover_text("Saves the game with its display settings; a board of the same name is replaced").clicked() {
+                        self.save_slots_message = self.save_current_board().unwrap_or_else(|error| error);
+                    }
+                });
+
+                let mut loaded_slot: Option<usize> = None;
+                let mut deleted_slot: Option<usize> = None;
+                let mut finished_renaming = false;
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("save_slots").striped(true).show(ui, |ui| {
+                        for (index, slot) in self.save_slots.get_slots().iter().enumerate() {
+                            let thumbnail_key = slot.get_thumbnail_key();
+                            let thumbnail = self.save_slot_thumbnails.entry(thumbnail_key.clone())
+                                .or_insert_with(|| ctx.load_texture(thumbnail_key, slot.thumbnail.get_image(), egui::TextureOptions::NEAREST));
+                            let thumbnail_size = thumbnail.size_vec2() * (64.0 / thumbnail.size_vec2().max_elem());
+                            ui.image((thumbnail.id(), thumbnail_size));
+                            ui.vertical(|ui| {
+                                match &mut self.renamed_slot {
+                                    Some((renamed_index, new_name)) if *renamed_index == index => {
+                                        let response = ui.text_edit_singleline(new_name);
+                                        if response.lost_focus() || ui.button("OK").clicked() {
+                                            finished_renaming = true;
+                                        }
+                                    }
+                                    _ => {
+                                        ui.strong(&slot.name);
+                                    }
+                                }
+                                ui.label(get_timestamp_text(slot.timestamp));
+                            });
+                            ui.horizontal(|ui| {
+                                if ui.button("Load").clicked() {
+                                    loaded_slot = Some(index);
+                                }
+                                if ui.button("Rename").clicked() {
+                                    self.renamed_slot = Some((index, slot.name.clone()));
+                                }
+                                if ui.button("Delete").clicked() {
+                                    deleted_slot = Some(index);
+                                }
+                            });
+                            ui.end_row();
+                        }
                     });
                 });
+                if finished_renaming {
+                    if let Some((index, new_name)) = self.renamed_slot.take() {
+                        if let Err(error) = self.save_slots.rename(index, &new_name) {
+                            self.save_slots_message = error;
+                        }
+                        self.forget_stale_thumbnails();
+                    }
+                }
+                if let Some(index) = loaded_slot {
+                    self.save_slots_message = self.load_save_slot(index).unwrap_or_else(|error| error);
+                }
+                if let Some(index) = deleted_slot {
+                    self.save_slots.delete(index);
+                    self.renamed_slot = None;
+                    self.forget_stale_thumbnails();
+                }
+                if self.save_slots.get_slots().is_empty() {
+                    ui.label("No saved boards yet");
+                }
+
+                ui.separator();
+                ui.label("All the saved boards, as a file (drop one onto the window to import its boards):");
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.save_slots_path);
+                    if ui.button("Export").clicked() {
+                        self.save_slots_message = self.save_slots.to_file_text()
+                            .and_then(|text| file_saver::save_file(&self.save_slots_path, text.as_bytes()))
+                            .unwrap_or_else(|error| error);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Import").clicked() {
+                        self.save_slots_message = file_saver::read_file(&self.save_slots_path)
+                            .and_then(|bytes| self.import_save_slots(&bytes))
+                            .unwrap_or_else(|error| error);
+                    }
+                });
+                if !self.save_slots_message.is_empty() {
+                    ui.label(&self.save_slots_message);
+                }
             });
     }
+
+    fn save_current_board(&mut self) -> Result<String, String> {
+        let name = self.save_slot_name.trim().to_string();
+        if name.is_empty() {
+            return Err(String::from("Give the board a name first"));
+        }
+        let display_settings = SlotDisplaySettings {
+            cell_size: self.cell_size,
+            cell_border_size: self.cell_border_size,
+            living_cell_color: self.living_cell_color,
+            dead_cell_color: self.dead_cell_color,
+            border_color: self.border_color,
+            color_map: self.color_map,
+            consider_extremes_adjacent: self.consider_extremes_adjacent
+        };
+        let thumbnail = Thumbnail::new(&self.get_cell_colors());
+        self.save_slots.save(SaveSlot::new(&name, &self.game, display_settings, thumbnail)?);
+        self.renamed_slot = None;
+        self.forget_stale_thumbnails();
+        Ok(String::from("Saved ") + &name)
+    }
+
+    /// Frees the thumbnail textures of slots that were deleted, renamed or saved over (their keys no longer match a slot)
+    fn forget_stale_thumbnails(&mut self) {
+        let thumbnail_keys: HashSet<String> = self.save_slots.get_slots().iter().map(|slot| slot.get_thumbnail_key()).collect();
+        self.save_slot_thumbnails.retain(|thumbnail_key, _| thumbnail_keys.contains(thumbnail_key));
+    }
+
+    fn load_save_slot(&mut self, index: usize) -> Result<String, String> {
+        let slot = self.save_slots.get_slots().get(index).ok_or_else(|| String::from("There is no such board"))?;
+        let game = slot.load_game()?;
+        let display_settings = slot.display_settings.clone();
+        let name = slot.name.clone();
+        self.game = game;
+        self.apply_game_settings();
+        self.cell_size = display_settings.cell_size;
+        self.cell_border_size = display_settings.cell_border_size;
+        self.living_cell_color = display_settings.living_cell_color;
+        self.dead_cell_color = display_settings.dead_cell_color;
+        self.border_color = display_settings.border_color;
+        self.color_map = display_settings.color_map;
+        self.consider_extremes_adjacent = display_settings.consider_extremes_adjacent;
+        self.rule_text = self.game.get_rule().to_rule_string();
+        self.selection = None;
+        self.save_slot_name = name.clone();
+        Ok(String::from("Loaded ") + &name)
+    }
+
+    fn import_save_slots(&mut self, bytes: &[u8]) -> Result<String, String> {
+        let text = std::str::from_utf8(bytes).map_err(|_| String::from("The file isn't a file of saved boards"))?;
+        let n_imported = self.save_slots.import_file_text(text)?;
+        Ok(String::from("Imported ") + &n_imported.to_string() + " boards")
+    }
+}
+
+/// Runs `rusty-life export-animation <file.gif|file.png> [options]` without opening a window: a soup of the rule is recorded
+/// with the default colours, unless the options say otherwise. Returns a message saying where the file went.
+pub fn run_animation_export_command(args: &[String]) -> Result<String, String> {
+    let usage = "Usage: rusty-life export-animation <file.gif|file.png> [--rule B3/S23] [--width 64] [--height 64] [--density 50] [--seed 0] \
+        [--generations 100] [--delay 100] [--skip 1] [--crop] [--wrap] [--cell-size 8] [--border 1] [--living-color 00FF00] [--dead-color 808080] [--border-color 000000]";
+    let Some(path) = args.first() else {
+        return Err(String::from(usage));
+    };
+    let mut app = TemplateApp {
+        cell_size: 8,
+        consider_extremes_adjacent: false,
+        ..TemplateApp::default()
+    };
+    let mut settings = AnimationExportSettings {
+        format: AnimationFormat::from_file_name(path).ok_or_else(|| String::from("The file must end in .gif or .png\n") + usage)?,
+        ..AnimationExportSettings::default()
+    };
+    let mut rule = Rule::default();
+    let (mut width, mut height) = (64, 64);
+    let mut soup_settings = SoupSettings::uniform(50.0);
+    let mut seed: u64 = 0;
+
+    let mut index = 1;
+    while index < args.len() {
+        let option = args[index].as_str();
+        if option == "--crop" || option == "--wrap" {
+            match option {
+                "--crop" => settings.crop_to_bounding_box = true,
+                _ => app.consider_extremes_adjacent = true,
+            }
+            index += 1;
+            continue;
+        }
+        let value = args.get(index + 1).ok_or_else(|| String::from("Missing value after ") + option)?;
+        let invalid_value = || String::from("Invalid value for ") + option + ": " + value;
+        match option {
+            "--rule" => rule = Rule::parse(value)?,
+            "--width" => width = value.parse().map_err(|_| invalid_value())?,
+            "--height" => height = value.parse().map_err(|_| invalid_value())?,
+            "--density" => soup_settings.probability_living_cell = value.parse().map_err(|_| invalid_value())?,
+            "--seed" => seed = value.parse().map_err(|_| invalid_value())?,
+            "--generations" => settings.n_generations = value.parse().map_err(|_| invalid_value())?,
+            "--delay" => settings.frame_delay_ms = value.parse().map_err(|_| invalid_value())?,
+            "--skip" => settings.frame_skip = value.parse().map_err(|_| invalid_value())?,
+            "--cell-size" => app.cell_size = value.parse().map_err(|_| invalid_value())?,
+            "--border" => app.cell_border_size = value.parse().map_err(|_| invalid_value())?,
+            "--living-color" => app.living_cell_color = parse_hex_color(value).ok_or_else(invalid_value)?,
+            "--dead-color" => app.dead_cell_color = parse_hex_color(value).ok_or_else(invalid_value)?,
+            "--border-color" => app.border_color = parse_hex_color(value).ok_or_else(invalid_value)?,
+            _ => return Err(String::from("Unknown option ") + option + "\n" + usage),
+        }
+        index += 2;
+    }
+    if app.cell_border_size * 2 >= app.cell_size {
+        app.cell_border_size = 0;
+    }
+
+    app.game = Game::new(width, height);
+    app.apply_game_settings();
+    app.game.set_rule(rule);
+    app.game.randomize(&soup_settings, seed);
+    let bytes = app.record_animation(&settings)?;
+    file_saver::save_file(path, &bytes)
+}
+
+/// Solver conflicts the predecessor search spends per frame, so the UI stays responsive (also on the web)
+const PREDECESSOR_SEARCH_CONFLICTS_PER_FRAME: usize = 2000;
+
+/// Generations the methuselah search runs its patterns for per frame, so the UI stays responsive (also on the web)
+const METHUSELAH_SEARCH_GENERATIONS_PER_FRAME: usize = 2000;
+
+/// Generations an animation export records per frame, so the UI stays responsive (also on the web)
+const ANIMATION_EXPORT_GENERATIONS_PER_FRAME: usize = 20;
+
+/// Candidate rows the spaceship search tries per frame, so the UI stays responsive (also on the web)
+const SHIP_SEARCH_CANDIDATE_ROWS_PER_FRAME: usize = 200_000;
+
+/// Seeds are kept below 2^32 so they can be edited exactly in a DragValue (which works with f64)
+fn get_new_soup_seed() -> u64 {
+    rand::random::<u32>() as u64
+}
+
+/// Rows of 'o' (alive) and '.' (dead)
+fn get_pattern_text(field: &Field) -> String {
+    let mut text = String::new();
+    for y in 0..field.get_height() {
+        for x in 0..field.get_width() {
+            text.push(if field.get_cell_state(x, y) == CellState::Alive {'o'} else {'.'});
+        }
+        text.push('\n');
+    }
+    text.trim_end().to_string()
+}
+
+/// Reads colours written as RRGGBB, with or without a leading #
+fn parse_hex_color(text: &str) -> Option<egui::Color32> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let component = |start: usize| u8::from_str_radix(&hex[start..start + 2], 16).ok();
+    Some(egui::Color32::from_rgb(component(0)?, component(2)?, component(4)?))
+}
+
+fn get_contrasting_text_color(r: u8, g: u8, b: u8) -> egui::Color32 {
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luminance > 128.0 {egui::Color32::BLACK} else {egui::Color32::WHITE}
+}
+
+/// Draws a horizontal bar going through the colours of the gradient from 0.0 (left) to 1.0 (right)
+fn draw_gradient(ui: &mut egui::Ui, get_color: impl Fn(f32) -> egui::Color32) {
+    let (gradient_rect, _) = ui.allocate_exact_size(egui::vec2(200.0, 12.0), egui::Sense::hover());
+    let n_samples = 50;
+    for sample in 0..n_samples {
+        let left = gradient_rect.left() + gradient_rect.width() * sample as f32 / n_samples as f32;
+        let right = gradient_rect.left() + gradient_rect.width() * (sample + 1) as f32 / n_samples as f32;
+        let sample_rect = egui::Rect::from_x_y_ranges(left..=right, gradient_rect.y_range());
+        ui.painter().rect_filled(sample_rect, 0.0, get_color(sample as f32 / (n_samples - 1) as f32));
+    }
 }
 
-fn draw_rectangle(ui :&mut egui::Ui, response: &egui::Response, painter: &egui::Painter, starting_x: usize, starting_y: usize, width: usize, height: usize, color : egui::Color32)
+fn draw_rectangle(response: &egui::Response, painter: &egui::Painter, starting_x: usize, starting_y: usize, width: usize, height: usize, color : egui::Color32)
 {
     let absolute_position = egui::Pos2 {
         x: response.rect.min.x,
@@ -150,6 +2586,36 @@ fn draw_rectangle(ui :&mut egui::Ui, response: &egui::Response, painter: &egui::
     painter.rect_filled(my_rect, 0.0, color);
 }
 
+fn get_triangle_row_height(cell_size: usize) -> f32 {
+    cell_size as f32 * 3.0_f32.sqrt() / 2.0
+}
+
+/// Vertices of the (equilateral) triangle representing the cell at (x, y), relative to the game window;
+/// consecutive triangles in a row alternate between pointing up and down and overlap by half a side.
+fn get_triangle_vertices(x: usize, y: usize, cell_size: usize) -> [egui::Pos2; 3] {
+    let side = cell_size as f32;
+    let row_height = get_triangle_row_height(cell_size);
+    let left = x as f32 * side / 2.0;
+    let top = y as f32 * row_height;
+    let bottom = top + row_height;
+    if Field::is_triangle_pointing_up(x, y) {
+        [egui::pos2(left, bottom), egui::pos2(left + side / 2.0, top), egui::pos2(left + side, bottom)]
+    }
+    else {
+        [egui::pos2(left, top), egui::pos2(left + side, top), egui::pos2(left + side / 2.0, bottom)]
+    }
+}
+
+fn is_point_within_triangle(point: egui::Pos2, a: egui::Pos2, b: egui::Pos2, c: egui::Pos2) -> bool {
+    let sign = |p1: egui::Pos2, p2: egui::Pos2, p3: egui::Pos2| (p1.x - p3.x) * (p2.y - p3.y) - (p2.x - p3.x) * (p1.y - p3.y);
+    let d1 = sign(point, a, b);
+    let d2 = sign(point, b, c);
+    let d3 = sign(point, c, a);
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
 fn check_pressed_button_within_game_window(response: &egui::Response, ui: &egui::Ui, pointer_button: egui::PointerButton) -> Option<egui::Pos2> {
     if ui.input(|input| input.pointer.button_pressed(pointer_button)) {
         if let Some(pos) = ui.input(|input| input.pointer.press_origin()) {
@@ -171,12 +2637,37 @@ impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
+        match game::save_format::save_game(&self.game) {
+            Ok(text) => storage.set_string(GAME_KEY, text),
+            Err(error) => log::error!("{error}"),
+        }
+        eframe::set_value(storage, SAVE_SLOTS_KEY, &self.save_slots);
+        for (key, text) in &self.unreadable_saves {
+            storage.set_string(&(key.clone() + "_unreadable"), text.clone());
+        }
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.show_color_controls_window(ctx);
+        self.show_rule_table_window(ctx);
+        self.show_ants_window(ctx);
+        self.show_one_dimensional_window(ctx);
+        self.show_margolus_window(ctx);
+        self.show_continuous_window(ctx);
+        self.show_three_dimensional_window(ctx);
+        self.show_update_mode_window(ctx);
+        self.show_soup_window(ctx);
+        self.show_soup_search_window(ctx);
+        self.show_predecessor_window(ctx);
+        self.show_ship_search_window(ctx);
+        self.show_methuselah_search_window(ctx);
+        self.show_activity_map_window(ctx);
+        self.show_animation_export_window(ctx);
+        self.show_image_window(ctx);
+        self.show_save_slots_window(ctx);
+        self.show_load_errors_window(ctx);
+        self.load_dropped_rule_tables(ctx);
 
         egui::Window::new("Game window")
             .resizable(true)
@@ -188,24 +2679,25 @@ impl eframe::App for TemplateApp {
                     egui::Sense::hover(),
                 );
 
-                let left_pressed_position: Option<egui::Pos2> = check_pressed_button_within_game_window(&response, &ui, egui::PointerButton::Primary);
+                let left_pressed_position: Option<egui::Pos2> = check_pressed_button_within_game_window(&response, ui, egui::PointerButton::Primary);
                 if let Some(left_pressed_position) = left_pressed_position {
                     self.on_mouse_press_on_game_window(left_pressed_position, false /*was_right_click*/);
                 }
 
-                let right_pressed_position: Option<egui::Pos2> = check_pressed_button_within_game_window(&response, &ui, egui::PointerButton::Secondary);
+                let right_pressed_position: Option<egui::Pos2> = check_pressed_button_within_game_window(&response, ui, egui::PointerButton::Secondary);
                 if let Some(right_pressed_position) = right_pressed_position {
                     self.on_mouse_press_on_game_window(right_pressed_position, true /*was_right_click*/);
                 }
+                self.drag_selection(&response, ui);
 
-                self.draw_board(ui, &response, &painter);
+                self.draw_board(&response, &painter);
             });
             
 
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
 
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+        egui::TopBottomPanel::top("top_panel").show(ctx, |_ui| {
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -222,29 +2714,70 @@ impl eframe::App for TemplateApp {
             let game_dimensions_text = String::from("Currently simulating a ") + &self.game.get_field().get_width().to_string() + &String::from("x") + &self.game.get_field().get_width().to_string() + &String::from(" game");
             ui.label(game_dimensions_text);
 
-            ui.add(egui::Slider::new(&mut self.cell_size, 1 as usize..=100 as usize).text("Cell size"));
-            ui.add(egui::Slider::new(&mut self.cell_border_size, 1 as usize..=100 as usize).text("Cell border size"));
+            ui.add(egui::Slider::new(&mut self.cell_size, 1..=100).text("Cell size"));
+            ui.add(egui::Slider::new(&mut self.cell_border_size, 1..=100).text("Cell border size"));
             if self.cell_border_size * 2 >= self.cell_size {
                 self.cell_border_size = self.cell_size / 2;
                 if self.cell_size % 2 == 0 {
-                    self.cell_border_size = self.cell_border_size - 1;
+                    self.cell_border_size -= 1;
                 }
             }
 
             ui.separator();
             ui.label("Game control options");
 
-            ui.add(egui::Slider::new(&mut self.new_game_width, 2 as usize..=1000 as usize).text("New game width"));
-            ui.add(egui::Slider::new(&mut self.new_game_height, 2 as usize..=1000 as usize).text("New game height"));
-            ui.add(egui::Slider::new(&mut self.probability_living_cell, 0.0..=100.0).text("Probability of living cell (percentage)"));
+            ui.add(egui::Slider::new(&mut self.new_game_width, 2..=1000).text("New game width"));
+            ui.add(egui::Slider::new(&mut self.new_game_height, 2..=1000).text("New game height"));
+            ui.horizontal(|ui| {
+                ui.label("New game grid:");
+                ui.radio_value(&mut self.new_game_grid_type, GridType::Square, "Square");
+                ui.radio_value(&mut self.new_game_grid_type, GridType::Triangular, "Triangular")
+                    .on_hover_text("Cells are triangles alternately pointing up and down; use an even width and height so the pattern stays consistent when the extremes are adjacent");
+                ui.radio_value(&mut self.new_game_grid_type, GridType::OneDimensional, "One-dimensional")
+                    .on_hover_text("A single row of cells, whose successive generations are drawn top to bottom as a spacetime diagram");
+                ui.radio_value(&mut self.new_game_grid_type, GridType::ThreeDimensional, "Three-dimensional")
+                    .on_hover_text("Layers of square cells stacked along the Z axis, shown and edited one slice at a time or in isometric perspective");
+            });
+            if self.new_game_grid_type == GridType::ThreeDimensional {
+                ui.add(egui::Slider::new(&mut self.new_game_depth, 2..=100).text("New game depth"));
+            }
+            ui.add(egui::Slider::new(&mut self.soup_settings.probability_living_cell, 0.0..=100.0).text("Probability of living cell (percentage)"))
+                .on_hover_text("Symmetries, soup regions and gradients can be set in the soup options");
             let start_new_game_text = String::from("Start new ") + &self.new_game_width.to_string() + &String::from("x") + &self.new_game_height.to_string() + &String::from(" game");
             if ui.button(start_new_game_text).clicked() {
-                self.game = Game::new(self.new_game_width, self.new_game_height);
-                self.game.randomize(self.probability_living_cell);
+                let rule = self.game.get_rule().clone();
+                let triangular_neighbourhood = self.game.get_triangular_neighbourhood();
+                let voxel_neighbourhood = self.game.get_voxel_neighbourhood();
+                let update_settings = self.game.get_update_settings().clone();
+                let update_seed = self.game.get_update_seed();
+                // the rule being edited may be invalid until it is applied, so the new game keeps the applied one
+                let one_dimensional_rule = self.game.get_one_dimensional().get_rule();
+                self.game = match self.new_game_grid_type {
+                    GridType::ThreeDimensional => Game::new_three_dimensional(self.new_game_width, self.new_game_height, self.new_game_depth),
+                    grid_type => Game::new_with_grid_type(self.new_game_width, self.new_game_height, grid_type),
+                };
+                self.game.set_voxel_neighbourhood(voxel_neighbourhood);
+                self.game.set_update_settings(update_settings);
+                self.game.set_update_seed(update_seed);
+                self.shown_z_slice = self.shown_z_slice.min(self.new_game_depth - 1);
+                self.game.set_rule(rule);
+                self.game.set_triangular_neighbourhood(triangular_neighbourhood);
+                self.game.set_one_dimensional_rule(one_dimensional_rule);
+                self.apply_game_settings();
+                self.randomize_game();
             }
 
-            if ui.button("Randomize cells").clicked() {
-                self.game.randomize(self.probability_living_cell);
+            ui.horizontal(|ui| {
+                if ui.button("Randomize cells").clicked() {
+                    self.randomize_game();
+                }
+                ui.label("Seed:");
+                ui.add(egui::DragValue::new(&mut self.soup_seed))
+                    .on_hover_text("The same seed and probability give the same soup, on the desktop and on the web alike");
+                ui.checkbox(&mut self.new_soup_seed_every_time, "New seed every time");
+            });
+            if let Some(soup) = self.game.get_soup() {
+                ui.label(soup.to_comment_string());
             }
 
             ui.label("Left click a cell to bring it to life");
@@ -262,17 +2795,79 @@ impl eframe::App for TemplateApp {
 
             ui.checkbox(&mut self.consider_extremes_adjacent, "Consider cells at the extremes adjacent to one another").on_hover_text("Checking this will make it so that the cells at the extremes of the board will be considered neighbours to their opposite cells in any direction; this makes it possible for gliders to propel indefinitely across the board");
 
+            ui.horizontal(|ui| {
+                ui.label("Rule:");
+                ui.text_edit_singleline(&mut self.rule_text)
+                    .on_hover_text("Totalistic rule in B/S notation, e.g. B3/S23 for Conway's Game of Life; counts above 9 can be given comma-separated, e.g. B4,5/S3,4,10");
+                if ui.button("Apply rule").clicked() {
+                    match Rule::parse(&self.rule_text) {
+                        Ok(rule) => {
+                            self.rule_text = rule.to_rule_string();
+                            self.game.set_rule(rule);
+                            self.rule_error_message.clear();
+                        }
+                        Err(error_message) => self.rule_error_message = error_message,
+                    }
+                }
+            });
+            if !self.rule_error_message.is_empty() {
+                ui.colored_label(egui::Color32::RED, &self.rule_error_message);
+            }
+            let current_rule_text = String::from("Current rule: ") + &self.game.get_rule().to_rule_string();
+            ui.label(current_rule_text);
+
+            if self.game.get_grid_type() == GridType::Triangular {
+                let mut triangular_neighbourhood = self.game.get_triangular_neighbourhood();
+                ui.horizontal(|ui| {
+                    ui.label("Triangular neighbourhood:");
+                    ui.radio_value(&mut triangular_neighbourhood, TriangularNeighbourhood::Edge, "Edge (3 cells)");
+                    ui.radio_value(&mut triangular_neighbourhood, TriangularNeighbourhood::Vertex, "Vertex (12 cells)");
+                });
+                self.game.set_triangular_neighbourhood(triangular_neighbourhood);
+            }
+
             if ui.button("Advance generation").clicked() {
                 self.game.advance_generation(self.consider_extremes_adjacent);
             }
 
-            ui.add(egui::Slider::new(&mut self.n_generations_to_advance, 2 as usize..=1000 as usize).text("Number of generations to advance"));
+            ui.add(egui::Slider::new(&mut self.n_generations_to_advance, 2..=1000).text("Number of generations to advance"));
 
             let advance_n_generations_text = String::from("Advance ") + &self.n_generations_to_advance.to_string() + &String::from(" generations");
             if ui.button(advance_n_generations_text).clicked() {
-                for i in 0..self.n_generations_to_advance {
-                    self.game.advance_generation(self.consider_extremes_adjacent);
+                self.game.advance_generations(self.n_generations_to_advance, self.consider_extremes_adjacent);
+            }
+
+            // Only synchronous B/S rules on square and triangular grids are stepped in parallel, and there are no threads on the web
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let max_n_threads = 2 * game::get_available_threads();
+                if ui.add(egui::Slider::new(&mut self.n_threads, 0..=max_n_threads).text("Threads (0 for one per core)")).changed() {
+                    self.game.set_n_threads(self.n_threads);
+                }
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Copy share link").on_hover_text("Link to the web version of the app opening this board: rule, grid, topology, size and cells").clicked() {
+                    self.share_link_message = self.copy_share_link(ui).unwrap_or_else(|error| error);
                 }
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.text_edit_singleline(&mut self.share_link_to_open).on_hover_text("Paste a share link here to open its board");
+                    if ui.button("Open share link").clicked() {
+                        self.share_link_message = match SharedBoard::from_fragment(&self.share_link_to_open) {
+                            Ok(shared_board) => {
+                                self.open_shared_board(shared_board);
+                                String::from("Opened the shared board")
+                            }
+                            Err(error) => error,
+                        };
+                    }
+                }
+            });
+            if !self.share_link_message.is_empty() {
+                ui.label(&self.share_link_message);
             }
 
             ui.separator();
@@ -300,3 +2895,60 @@ fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
         ui.label(".");
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::Storage as _;
+
+    /// Storage of the texts of a few keys, as eframe keeps them between runs
+    #[derive(Default)]
+    struct MemoryStorage {
+        texts: HashMap<String, String>
+    }
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.texts.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.texts.insert(String::from(key), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn games_saved_with_the_settings_are_migrated() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(eframe::APP_KEY, String::from("(cell_size: 9, game: (field: (width: 3, height: 1, cells: [Dead, Alive, Dead]), generation: 4))"));
+        let app = TemplateApp::load(&storage);
+        assert!(app.load_errors.is_empty(), "{:?}", app.load_errors);
+        assert_eq!(app.cell_size, 9);
+        assert_eq!(app.game.get_generation(), 4);
+        assert_eq!(app.game.get_field().get_width(), 3);
+        assert_eq!(app.game.get_field().get_cell_state(1, 0), CellState::Alive);
+    }
+
+    #[test]
+    fn games_saved_apart_take_precedence() {
+        let mut game = Game::new(2, 2);
+        game.get_field_mut().set_cell_state(1, 1, CellState::Alive);
+        let mut storage = MemoryStorage::default();
+        storage.set_string(eframe::APP_KEY, String::from("(game: (field: (width: 3, height: 1, cells: [Dead, Alive, Dead])))"));
+        storage.set_string(GAME_KEY, game::save_format::save_game(&game).unwrap());
+        let app = TemplateApp::load(&storage);
+        assert_eq!(app.game.get_field().get_width(), 2);
+        assert_eq!(app.game.get_field().get_cell_state(1, 1), CellState::Alive);
+    }
+
+    #[test]
+    fn unreadable_games_are_kept() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(GAME_KEY, String::from("(version: 1, game: (field: 3))"));
+        let app = TemplateApp::load(&storage);
+        assert_eq!(app.load_errors.len(), 1);
+        assert_eq!(app.unreadable_saves, [(String::from(GAME_KEY), String::from("(version: 1, game: (field: 3))"))]);
+    }
+}
@@ -0,0 +1,341 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    /// Animated PNG: lossless colours, without GIF's 256-colour palette
+    Apng
+}
+
+pub const ANIMATION_FORMATS: [(AnimationFormat, &str); 2] = [
+    (AnimationFormat::Gif, "GIF"),
+    (AnimationFormat::Apng, "APNG")
+];
+
+impl AnimationFormat {
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+        }
+    }
+
+    /// Guesses the format from the extension of a file name (.gif, or .png/.apng)
+    pub fn from_file_name(file_name: &str) -> Option<AnimationFormat> {
+        let extension = file_name.rsplit('.').next()?.to_lowercase();
+        match extension.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "png" | "apng" => Some(AnimationFormat::Apng),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct AnimationExportSettings {
+    pub format: AnimationFormat,
+    /// Generations the game is run forward for; the first frame is the current generation
+    pub n_generations: usize,
+    /// Time each frame is shown for, in milliseconds (rounded to hundredths of a second in GIFs)
+    pub frame_delay_ms: u16,
+    /// Generations between consecutive frames (1 to record every generation)
+    pub frame_skip: usize,
+    /// Whether frames are cropped to the bounding box of the non-dead cells over the whole run
+    pub crop_to_bounding_box: bool
+}
+
+impl Default for AnimationExportSettings {
+    fn default() -> Self {
+        AnimationExportSettings {
+            format: AnimationFormat::Gif,
+            n_generations: 100,
+            frame_delay_ms: 100,
+            frame_skip: 1,
+            crop_to_bounding_box: false
+        }
+    }
+}
+
+/// How cells are turned into pixels, as on the board
+pub struct FrameStyle {
+    pub cell_size: usize,
+    pub cell_border_size: usize,
+    pub border_color: egui::Color32,
+    /// Colour of dead cells, which cropping leaves out
+    pub dead_cell_color: egui::Color32
+}
+
+/// Colours of the cells of one generation, row by row
+pub struct CellColors {
+    pub width: usize,
+    pub height: usize,
+    pub colors: Vec<egui::Color32>
+}
+
+impl CellColors {
+    /// Colours of the cells of the rectangle, which must lie within these cells
+    pub fn get_region(&self, left: usize, top: usize, width: usize, height: usize) -> CellColors {
+        let mut colors: Vec<egui::Color32> = Vec::with_capacity(width * height);
+        for y in top..top + height {
+            colors.extend_from_slice(&self.colors[left + y * self.width..left + width + y * self.width]);
+        }
+        CellColors{width, height, colors}
+    }
+}
+
+/// An animation being recorded frame by frame: each frame is rendered and fed to the encoder as soon as it is added, so
+/// only the file written so far is kept. When cropping, the bounding box of the whole run is needed first, so only the
+/// cell colours of the frames (not their pixels) are kept until `finish`.
+pub struct AnimationRecorder {
+    settings: AnimationExportSettings,
+    style: FrameStyle,
+    description: String,
+    encoder: Option<AnimationEncoder>,
+    /// Frames waiting for the bounding box, when cropping
+    cropped_frames: Vec<CellColors>,
+    n_frames_added: usize,
+    /// Generations the game has been run forward for since the first frame
+    generation: usize
+}
+
+impl AnimationRecorder {
+    pub fn new(settings: &AnimationExportSettings, style: FrameStyle, description: &str) -> Result<AnimationRecorder, String> {
+        if style.cell_size == 0 {
+            return Err(String::from("The cell size must be at least 1 pixel"));
+        }
+        Ok(AnimationRecorder {
+            settings: settings.clone(),
+            style,
+            description: String::from(description),
+            encoder: None,
+            cropped_frames: Vec::new(),
+            n_frames_added: 0,
+            generation: 0
+        })
+    }
+
+    /// Frames the animation will have: the first generation, then every `frame_skip`-th generation of the run
+    pub fn get_n_frames(&self) -> usize {
+        1 + self.settings.n_generations / self.settings.frame_skip.max(1)
+    }
+
+    pub fn get_n_frames_added(&self) -> usize {
+        self.n_frames_added
+    }
+
+    /// Whether every frame has been added, so the animation can be finished
+    pub fn is_complete(&self) -> bool {
+        self.n_frames_added >= self.get_n_frames()
+    }
+
+    /// Counts a generation the game was advanced by, returning whether it is recorded as a frame
+    pub fn next_generation(&mut self) -> bool {
+        self.generation += 1;
+        self.generation % self.settings.frame_skip.max(1) == 0
+    }
+
+    pub fn add_frame(&mut self, frame: CellColors) -> Result<(), String> {
+        if self.is_complete() {
+            return Err(String::from("The animation already has all its frames"));
+        }
+        self.n_frames_added += 1;
+        if self.settings.crop_to_bounding_box {
+            if self.cropped_frames.first().is_some_and(|first_frame| frame.width != first_frame.width || frame.height != first_frame.height) {
+                return Err(String::from("The board was resized during the run"));
+            }
+            self.cropped_frames.push(frame);
+            return Ok(());
+        }
+        if self.encoder.is_none() {
+            let n_frames = self.get_n_frames();
+            self.encoder = Some(AnimationEncoder::new(&self.settings, &self.style, &self.description, n_frames, (0, 0, frame.width, frame.height))?);
+        }
+        match &mut self.encoder {
+            Some(encoder) => encoder.add_frame(&frame, &self.style),
+            None => Ok(()),
+        }
+    }
+
+    /// The encoded file, once every frame has been added
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        if !self.is_complete() {
+            return Err(String::from("The recording was stopped before its last frame"));
+        }
+        if self.settings.crop_to_bounding_box {
+            let Some(first_frame) = self.cropped_frames.first() else {
+                return Err(String::from("There are no frames to export"));
+            };
+            let bounding_box = get_bounding_box(&self.cropped_frames, self.style.dead_cell_color)
+                .unwrap_or((0, 0, first_frame.width, first_frame.height));
+            let mut encoder = AnimationEncoder::new(&self.settings, &self.style, &self.description, self.cropped_frames.len(), bounding_box)?;
+            for frame in std::mem::take(&mut self.cropped_frames) {
+                encoder.add_frame(&frame, &self.style)?;
+            }
+            self.encoder = Some(encoder);
+        }
+        self.encoder.ok_or_else(|| String::from("There are no frames to export"))?.finish()
+    }
+}
+
+/// Buffer the encoders write to, that can still be read once they are done with it (the PNG writer doesn't give it back)
+#[derive(Clone)]
+#[derive(Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+enum FrameWriter {
+    Gif(gif::Encoder<SharedBuffer>),
+    Apng(png::Writer<SharedBuffer>)
+}
+
+/// Renders frames of a rectangle of the board and encodes them one at a time, in the format of the settings
+struct AnimationEncoder {
+    writer: FrameWriter,
+    output: SharedBuffer,
+    /// Rectangle (left, top, width, height) of the cells rendered
+    region: (usize, usize, usize, usize),
+    frame_delay_ms: u16
+}
+
+impl AnimationEncoder {
+    /// Writes the header of a file of `n_frames` frames of the region, with the description as a comment
+    fn new(settings: &AnimationExportSettings, style: &FrameStyle, description: &str, n_frames: usize, region: (usize, usize, usize, usize)) -> Result<AnimationEncoder, String> {
+        let (_, _, width, height) = region;
+        let image_width = width * style.cell_size;
+        let image_height = height * style.cell_size;
+        if image_width == 0 || image_height == 0 || image_width > u16::MAX as usize || image_height > u16::MAX as usize {
+            return Err(String::from("Frames of ") + &image_width.to_string() + "x" + &image_height.to_string() + " pixels can't be exported");
+        }
+        let output = SharedBuffer::default();
+        let writer = match settings.format {
+            AnimationFormat::Gif => {
+                // each frame brings its own palette, since the colours of the frames to come aren't known yet
+                let mut encoder = gif::Encoder::new(output.clone(), image_width as u16, image_height as u16, &[]).map_err(|error| error.to_string())?;
+                encoder.set_repeat(gif::Repeat::Infinite).map_err(|error| error.to_string())?;
+                encoder.write_raw_extension(gif::AnyExtension(GIF_COMMENT_LABEL), &[description.as_bytes()]).map_err(|error| error.to_string())?;
+                FrameWriter::Gif(encoder)
+            }
+            AnimationFormat::Apng => {
+                let mut encoder = png::Encoder::new(output.clone(), image_width as u32, image_height as u32);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(n_frames as u32, 0).map_err(|error| error.to_string())?;
+                encoder.set_frame_delay(settings.frame_delay_ms, 1000).map_err(|error| error.to_string())?;
+                encoder.add_itxt_chunk(String::from("Comment"), String::from(description)).map_err(|error| error.to_string())?;
+                FrameWriter::Apng(encoder.write_header().map_err(|error| error.to_string())?)
+            }
+        };
+        Ok(AnimationEncoder{writer, output, region, frame_delay_ms: settings.frame_delay_ms})
+    }
+
+    fn add_frame(&mut self, frame: &CellColors, style: &FrameStyle) -> Result<(), String> {
+        let (left, top, width, height) = self.region;
+        if left + width > frame.width || top + height > frame.height {
+            return Err(String::from("The board was resized during the run"));
+        }
+        let image = render_frame(frame, style, left, top, width, height);
+        let (image_width, image_height) = ((width * style.cell_size) as u16, (height * style.cell_size) as u16);
+        match &mut self.writer {
+            FrameWriter::Gif(encoder) => {
+                let mut frame = get_gif_frame(&image, image_width, image_height);
+                frame.delay = (self.frame_delay_ms as f32 / 10.0).round() as u16;
+                encoder.write_frame(&frame).map_err(|error| error.to_string())
+            }
+            FrameWriter::Apng(writer) => {
+                let rgb: Vec<u8> = image.iter().flat_map(|color| [color.r(), color.g(), color.b()]).collect();
+                writer.write_image_data(&rgb).map_err(|error| error.to_string())
+            }
+        }
+    }
+
+    fn finish(self) -> Result<Vec<u8>, String> {
+        match self.writer {
+            FrameWriter::Gif(encoder) => drop(encoder.into_inner().map_err(|error| error.to_string())?),
+            FrameWriter::Apng(writer) => writer.finish().map_err(|error| error.to_string())?,
+        }
+        Ok(self.output.0.take())
+    }
+}
+
+/// Smallest rectangle (left, top, width, height) holding every cell of every frame that isn't dead, if any
+fn get_bounding_box(frames: &[CellColors], dead_cell_color: egui::Color32) -> Option<(usize, usize, usize, usize)> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for frame in frames {
+        for (index, color) in frame.colors.iter().enumerate() {
+            if *color == dead_cell_color {
+                continue;
+            }
+            let (x, y) = (index % frame.width, index / frame.width);
+            bounds = Some(match bounds {
+                Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+    }
+    bounds.map(|(min_x, min_y, max_x, max_y)| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// Pixels of the cells in the rectangle, row by row, each cell being a square of its colour within a border
+pub fn render_frame(frame: &CellColors, style: &FrameStyle, left: usize, top: usize, width: usize, height: usize) -> Vec<egui::Color32> {
+    let image_width = width * style.cell_size;
+    let mut pixels = vec![style.border_color; image_width * height * style.cell_size];
+    let interior = style.cell_border_size..style.cell_size.saturating_sub(style.cell_border_size);
+    for y in 0..height {
+        for x in 0..width {
+            let color = frame.colors[left + x + (top + y) * frame.width];
+            for pixel_y in interior.clone() {
+                let row_start = (y * style.cell_size + pixel_y) * image_width + x * style.cell_size;
+                for pixel_x in interior.clone() {
+                    pixels[row_start + pixel_x] = color;
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// Extension label of GIF comments
+const GIF_COMMENT_LABEL: u8 = 0xFE;
+
+/// A GIF frame of the image: boards seldom have more than a handful of colours, and when they fit in a palette it is exact;
+/// otherwise (e.g. continuous states) the frame gets a quantized palette
+fn get_gif_frame(image: &[egui::Color32], width: u16, height: u16) -> gif::Frame<'static> {
+    let mut palette_indices: HashMap<egui::Color32, u8> = HashMap::new();
+    for color in image {
+        if !palette_indices.contains_key(color) {
+            if palette_indices.len() == 256 {
+                let rgb: Vec<u8> = image.iter().flat_map(|color| [color.r(), color.g(), color.b()]).collect();
+                return gif::Frame::from_rgb_speed(width, height, &rgb, 10);
+            }
+            palette_indices.insert(*color, palette_indices.len() as u8);
+        }
+    }
+    let mut palette: Vec<u8> = vec![0; 3 * palette_indices.len()];
+    for (color, index) in &palette_indices {
+        palette[3 * *index as usize..3 * *index as usize + 3].copy_from_slice(&[color.r(), color.g(), color.b()]);
+    }
+    let indices: Vec<u8> = image.iter().map(|color| palette_indices[color]).collect();
+    gif::Frame::from_palette_pixels(width, height, indices, palette, None)
+}
@@ -0,0 +1,90 @@
+/// Gradients used to display continuous cell states, from 0.0 to 1.0.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum ColorMap {
+    /// From the dead cell color to the living cell color
+    #[default]
+    CellColors,
+    Grayscale,
+    Viridis,
+    Inferno,
+    Jet
+}
+
+pub const COLOR_MAPS: [(ColorMap, &str); 5] = [
+    (ColorMap::CellColors, "Cell colors"),
+    (ColorMap::Grayscale, "Grayscale"),
+    (ColorMap::Viridis, "Viridis"),
+    (ColorMap::Inferno, "Inferno"),
+    (ColorMap::Jet, "Jet")
+];
+
+// Evenly spaced samples of the gradients, interpolated linearly in between
+const VIRIDIS: [[u8; 3]; 5] = [[68, 1, 84], [59, 82, 139], [33, 145, 140], [94, 201, 98], [253, 231, 37]];
+const INFERNO: [[u8; 3]; 5] = [[0, 0, 4], [87, 16, 110], [188, 55, 84], [249, 142, 9], [252, 255, 164]];
+const JET: [[u8; 3]; 5] = [[0, 0, 143], [0, 128, 255], [128, 255, 128], [255, 128, 0], [128, 0, 0]];
+
+impl ColorMap {
+    pub fn get_color(&self, value: f32, dead_cell_color: egui::Color32, living_cell_color: egui::Color32) -> egui::Color32 {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            ColorMap::CellColors => dead_cell_color.lerp_to_gamma(living_cell_color, value),
+            ColorMap::Grayscale => {
+                let level = (value * 255.0).round() as u8;
+                egui::Color32::from_rgb(level, level, level)
+            }
+            ColorMap::Viridis => interpolate_samples(&VIRIDIS, value),
+            ColorMap::Inferno => interpolate_samples(&INFERNO, value),
+            ColorMap::Jet => interpolate_samples(&JET, value),
+        }
+    }
+}
+
+fn interpolate_samples(samples: &[[u8; 3]], value: f32) -> egui::Color32 {
+    let position = value * (samples.len() - 1) as f32;
+    let index = (position.floor() as usize).min(samples.len() - 2);
+    let t = position - index as f32;
+    let [r1, g1, b1] = samples[index];
+    let [r2, g2, b2] = samples[index + 1];
+    let component = |first: u8, second: u8| (first as f32 + (second as f32 - first as f32) * t).round() as u8;
+    egui::Color32::from_rgb(component(r1, r2), component(g1, g2), component(b1, b2))
+}
+
+/// A user-defined gradient: colours at evenly spaced positions from 0.0 to 1.0, interpolated in between
+#[derive(PartialEq)]
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct ColorRamp {
+    pub stops: Vec<egui::Color32>
+}
+
+impl Default for ColorRamp {
+    /// Newborn cells in yellow, turning red then purple as they age
+    fn default() -> Self {
+        ColorRamp{stops: vec![
+            egui::Color32::from_rgb(255, 255, 0),
+            egui::Color32::from_rgb(255, 128, 0),
+            egui::Color32::from_rgb(200, 0, 0),
+            egui::Color32::from_rgb(90, 0, 120)
+        ]}
+    }
+}
+
+impl ColorRamp {
+    pub fn get_color(&self, value: f32) -> egui::Color32 {
+        match self.stops.len() {
+            0 => egui::Color32::BLACK,
+            1 => self.stops[0],
+            _ => {
+                let samples: Vec<[u8; 3]> = self.stops.iter().map(|color| [color.r(), color.g(), color.b()]).collect();
+                interpolate_samples(&samples, value.clamp(0.0, 1.0))
+            }
+        }
+    }
+}
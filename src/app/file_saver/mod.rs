@@ -0,0 +1,39 @@
+/// Writes the file on native, or has the browser download it under the file name (without its directories) on the web.
+/// Returns a message telling the user where the file went.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(path: &str, bytes: &[u8]) -> Result<String, String> {
+    std::fs::write(path, bytes).map_err(|error| String::from("Couldn't write ") + path + ": " + &error.to_string())?;
+    Ok(String::from("Saved ") + path)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(path: &str, bytes: &[u8]) -> Result<String, String> {
+    use eframe::wasm_bindgen::JsCast as _;
+
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let parts = js_sys::Array::new();
+    parts.push(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).map_err(|_| String::from("Couldn't create the file"))?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(|_| String::from("Couldn't create a link to the file"))?;
+    let anchor = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("a").ok())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+        .ok_or_else(|| String::from("Couldn't start the download"))?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url).ok();
+    Ok(String::from("Downloaded ") + file_name)
+}
+
+/// Reads a file on native; the web has no file system, files reach it by being dropped onto the window
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|error| String::from("Couldn't read ") + path + ": " + &error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    Err(String::from("Couldn't read ") + path + ": files can only be dropped onto the window on the web")
+}
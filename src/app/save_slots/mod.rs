@@ -0,0 +1,200 @@
+use super::animation_export::CellColors;
+use super::color_map::ColorMap;
+use crate::game::Game;
+use crate::game::save_format::{load_game, save_game};
+
+/// Storage key of the saved boards, kept apart from the app state so that they survive resetting it
+pub const SAVE_SLOTS_KEY: &str = "save_slots";
+
+/// Largest side of thumbnails, in pixels; boards are shrunk to fit
+const MAX_THUMBNAIL_SIDE: usize = 64;
+
+/// Display settings saved along with a board
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct SlotDisplaySettings {
+    pub cell_size: usize,
+    pub cell_border_size: usize,
+    pub living_cell_color: egui::Color32,
+    pub dead_cell_color: egui::Color32,
+    pub border_color: egui::Color32,
+    pub color_map: ColorMap,
+    pub consider_extremes_adjacent: bool
+}
+
+impl Default for SlotDisplaySettings {
+    fn default() -> Self {
+        SlotDisplaySettings {
+            cell_size: 15,
+            cell_border_size: 1,
+            living_cell_color: egui::Color32::GREEN,
+            dead_cell_color: egui::Color32::GRAY,
+            border_color: egui::Color32::BLACK,
+            color_map: ColorMap::default(),
+            consider_extremes_adjacent: true
+        }
+    }
+}
+
+/// Small picture of a board, one pixel per cell (or per block of cells on big boards), row by row
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct Thumbnail {
+    pub width: usize,
+    pub height: usize,
+    /// Red, green and blue of each pixel
+    pub pixels: Vec<u8>
+}
+
+impl Thumbnail {
+    /// Samples the cells so that the thumbnail fits in `MAX_THUMBNAIL_SIDE`
+    pub fn new(cell_colors: &CellColors) -> Thumbnail {
+        let cells_per_pixel = cell_colors.width.max(cell_colors.height).div_ceil(MAX_THUMBNAIL_SIDE).max(1);
+        let width = cell_colors.width.div_ceil(cells_per_pixel);
+        let height = cell_colors.height.div_ceil(cells_per_pixel);
+        let mut pixels: Vec<u8> = Vec::with_capacity(3 * width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = cell_colors.colors[x * cells_per_pixel + y * cells_per_pixel * cell_colors.width];
+                pixels.extend_from_slice(&[color.r(), color.g(), color.b()]);
+            }
+        }
+        Thumbnail{width, height, pixels}
+    }
+
+    pub fn get_image(&self) -> egui::ColorImage {
+        if self.pixels.len() != 3 * self.width * self.height || self.pixels.is_empty() {
+            return egui::ColorImage::new([1, 1], egui::Color32::BLACK);
+        }
+        egui::ColorImage::from_rgb([self.width, self.height], &self.pixels)
+    }
+}
+
+/// A named board saved by the user
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct SaveSlot {
+    pub name: String,
+    /// When the board was saved, in seconds since the Unix epoch
+    pub timestamp: f64,
+    pub thumbnail: Thumbnail,
+    pub display_settings: SlotDisplaySettings,
+    /// The game, in the versioned save format
+    game: String
+}
+
+impl SaveSlot {
+    pub fn new(name: &str, game: &Game, display_settings: SlotDisplaySettings, thumbnail: Thumbnail) -> Result<SaveSlot, String> {
+        let game = save_game(game)?;
+        Ok(SaveSlot{name: String::from(name), timestamp: get_current_time(), thumbnail, display_settings, game})
+    }
+
+    pub fn load_game(&self) -> Result<Game, String> {
+        load_game(&self.game).map_err(|error| String::from("Couldn't load ") + &self.name + ": " + &error)
+    }
+
+    /// Identifies the slot's thumbnail texture, which changes whenever the slot is saved again
+    pub fn get_thumbnail_key(&self) -> String {
+        self.name.clone() + "@" + &self.timestamp.to_string()
+    }
+}
+
+/// The saved boards, most recent first; names are unique
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct SaveSlots {
+    slots: Vec<SaveSlot>
+}
+
+impl SaveSlots {
+    pub fn get_slots(&self) -> &[SaveSlot] {
+        &self.slots
+    }
+
+    /// Saves the slot, replacing the slot of the same name if there is one
+    pub fn save(&mut self, slot: SaveSlot) {
+        self.slots.retain(|existing_slot| existing_slot.name != slot.name);
+        self.slots.insert(0, slot);
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(String::from("Names can't be empty"));
+        }
+        if self.slots.iter().enumerate().any(|(other_index, slot)| other_index != index && slot.name == name) {
+            return Err(String::from("There already is a board named ") + name);
+        }
+        let slot = self.slots.get_mut(index).ok_or_else(|| String::from("There is no such board"))?;
+        slot.name = String::from(name);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, index: usize) {
+        if index < self.slots.len() {
+            self.slots.remove(index);
+        }
+    }
+
+    /// All the slots, written in RON to be saved to a file
+    pub fn to_file_text(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|error| error.to_string())
+    }
+
+    /// Adds the slots of a file written by `to_file_text`, renaming those whose names are taken; returns how many there were
+    pub fn import_file_text(&mut self, text: &str) -> Result<usize, String> {
+        let imported: SaveSlots = ron::from_str(text).map_err(|error| String::from("Couldn't read the saved boards: ") + &error.to_string())?;
+        let n_imported = imported.slots.len();
+        for mut slot in imported.slots {
+            let base_name = slot.name.clone();
+            let mut copy_number = 2;
+            while self.slots.iter().any(|existing_slot| existing_slot.name == slot.name) {
+                slot.name = base_name.clone() + " (" + &copy_number.to_string() + ")";
+                copy_number += 1;
+            }
+            self.slots.push(slot);
+        }
+        Ok(n_imported)
+    }
+}
+
+/// Seconds since the Unix epoch
+fn get_current_time() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now() / 1000.0
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0.0, |duration| duration.as_secs_f64())
+    }
+}
+
+/// Date and time of a timestamp, as YYYY-MM-DD HH:MM UTC
+pub fn get_timestamp_text(timestamp: f64) -> String {
+    let seconds = timestamp.max(0.0) as i64;
+    let (days, seconds_of_day) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let shifted_days = days + 719_468;
+    let era = shifted_days.div_euclid(146_097);
+    let day_of_era = shifted_days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {shifted_month + 3} else {shifted_month - 9};
+    let year = year_of_era + era * 400 + if month <= 2 {1} else {0};
+    let two_digits = |value: i64| if value < 10 {String::from("0") + &value.to_string()} else {value.to_string()};
+    year.to_string() + "-" + &two_digits(month) + "-" + &two_digits(day) + " "
+        + &two_digits(seconds_of_day / 3600) + ":" + &two_digits(seconds_of_day % 3600 / 60) + " UTC"
+}
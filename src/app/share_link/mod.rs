@@ -0,0 +1,132 @@
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use crate::game::GridType;
+use crate::game::field::{Field, TriangularNeighbourhood};
+use crate::game::rle::{decode_rle, encode_rle};
+use crate::game::rule::Rule;
+
+/// Address of the web build, which links made on the desktop point to
+#[cfg(not(target_arch = "wasm32"))]
+const WEB_APP_URL: &str = "https://rustylife.netlify.app/";
+
+/// Prefix of the fragments of this version of the encoding, so that later versions can still read older links
+const FRAGMENT_PREFIX: &str = "v1.";
+
+/// Largest side of shared boards, as for new games
+const MAX_SHARED_SIDE: usize = 1000;
+
+/// Largest decompressed size of a fragment, so that a forged link can't exhaust the memory
+const MAX_PAYLOAD_SIZE: usize = 1 << 22;
+
+/// Everything needed to reproduce a board of living and dead cells elsewhere
+pub struct SharedBoard {
+    pub rule: Rule,
+    /// Square or triangular
+    pub grid_type: GridType,
+    pub triangular_neighbourhood: TriangularNeighbourhood,
+    pub consider_extremes_adjacent: bool,
+    pub field: Field
+}
+
+impl SharedBoard {
+    /// URL fragment (without the #): the version prefix, then `rule=...;grid=...;wrap=...;size=WxH;cells=RLE`,
+    /// compressed with deflate and encoded in base64url
+    pub fn to_fragment(&self) -> String {
+        let grid = match (self.grid_type, self.triangular_neighbourhood) {
+            (GridType::Triangular, TriangularNeighbourhood::Edge) => "triangular-edge",
+            (GridType::Triangular, TriangularNeighbourhood::Vertex) => "triangular-vertex",
+            _ => "square",
+        };
+        let payload = String::from("rule=") + &self.rule.to_rule_string()
+            + ";grid=" + grid
+            + ";wrap=" + if self.consider_extremes_adjacent {"1"} else {"0"}
+            + ";size=" + &self.field.get_width().to_string() + "x" + &self.field.get_height().to_string()
+            + ";cells=" + &encode_rle(&self.field);
+        let compressed = miniz_oxide::deflate::compress_to_vec(payload.as_bytes(), 9);
+        String::from(FRAGMENT_PREFIX) + &URL_SAFE_NO_PAD.encode(compressed)
+    }
+
+    /// Reads a fragment made by `to_fragment`, with or without the #, or a whole link containing one
+    pub fn from_fragment(text: &str) -> Result<SharedBoard, String> {
+        let fragment = text.trim().rsplit('#').next().unwrap_or_default();
+        let encoded = fragment.strip_prefix(FRAGMENT_PREFIX).ok_or_else(|| String::from("Not a share link of this version of the app"))?;
+        let compressed = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| String::from("The share link is damaged (invalid base64url)"))?;
+        let payload = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_PAYLOAD_SIZE)
+            .map_err(|_| String::from("The share link is damaged (invalid compressed data)"))?;
+        let payload = String::from_utf8(payload).map_err(|_| String::from("The share link is damaged (invalid text)"))?;
+
+        let mut rule: Option<Rule> = None;
+        let mut grid: Option<(GridType, TriangularNeighbourhood)> = None;
+        let mut consider_extremes_adjacent = false;
+        let mut size: Option<(usize, usize)> = None;
+        let mut cells: Option<&str> = None;
+        for entry in payload.split(';') {
+            let (key, value) = entry.split_once('=').ok_or_else(|| String::from("Invalid entry in the share link: ") + entry)?;
+            match key {
+                "rule" => rule = Some(Rule::parse(value)?),
+                "grid" => grid = Some(match value {
+                    "square" => (GridType::Square, TriangularNeighbourhood::default()),
+                    "triangular-edge" => (GridType::Triangular, TriangularNeighbourhood::Edge),
+                    "triangular-vertex" => (GridType::Triangular, TriangularNeighbourhood::Vertex),
+                    _ => return Err(String::from("Unknown grid in the share link: ") + value),
+                }),
+                "wrap" => consider_extremes_adjacent = value == "1",
+                "size" => {
+                    let (width, height) = value.split_once('x').ok_or_else(|| String::from("Invalid size in the share link: ") + value)?;
+                    let parse_side = |side: &str| side.parse::<usize>().ok().filter(|side| (1..=MAX_SHARED_SIDE).contains(side));
+                    size = Some((
+                        parse_side(width).ok_or_else(|| String::from("Invalid width in the share link: ") + width)?,
+                        parse_side(height).ok_or_else(|| String::from("Invalid height in the share link: ") + height)?
+                    ));
+                }
+                "cells" => cells = Some(value),
+                _ => (), // entries added by later versions
+            }
+        }
+        let (width, height) = size.ok_or_else(|| String::from("The share link has no size"))?;
+        let (grid_type, triangular_neighbourhood) = grid.unwrap_or((GridType::Square, TriangularNeighbourhood::default()));
+        Ok(SharedBoard {
+            rule: rule.ok_or_else(|| String::from("The share link has no rule"))?,
+            grid_type,
+            triangular_neighbourhood,
+            consider_extremes_adjacent,
+            field: decode_rle(cells.ok_or_else(|| String::from("The share link has no cells"))?, width, height)?
+        })
+    }
+}
+
+/// Link opening the web build on the board of the fragment: the current page on the web, the published web build on the desktop
+pub fn get_share_link(fragment: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let href = web_sys::window().and_then(|window| window.location().href().ok()).unwrap_or_default();
+        let page = href.split('#').next().unwrap_or_default();
+        String::from(page) + "#" + fragment
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        String::from(WEB_APP_URL) + "#" + fragment
+    }
+}
+
+/// Fragment of the page's address (without the #), if it has one
+#[cfg(target_arch = "wasm32")]
+pub fn get_page_fragment() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let fragment = hash.trim_start_matches('#');
+    if fragment.is_empty() {None} else {Some(String::from(fragment))}
+}
+
+/// Removes the fragment from the page's address, without reloading the page or adding a history entry,
+/// so reloading the page later doesn't open the shared board again over the saved one
+#[cfg(target_arch = "wasm32")]
+pub fn clear_page_fragment() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    let url = location.pathname().unwrap_or_default() + &location.search().unwrap_or_default();
+    if let Ok(history) = window.history() {
+        history.replace_state_with_url(&eframe::wasm_bindgen::JsValue::NULL, "", Some(&url)).ok();
+    }
+}
@@ -0,0 +1,153 @@
+use super::animation_export::{CellColors, FrameStyle, render_frame};
+use crate::game::field::{CellState, Field};
+use std::io::Cursor;
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum SnapshotScale {
+    /// One pixel per cell, without borders
+    #[default]
+    OnePixelPerCell,
+    /// As on the board, with its cell size and borders
+    DisplayedCellSize
+}
+
+pub const SNAPSHOT_SCALES: [(SnapshotScale, &str); 2] = [
+    (SnapshotScale::OnePixelPerCell, "One pixel per cell"),
+    (SnapshotScale::DisplayedCellSize, "Displayed cell size")
+];
+
+/// Encodes the cells as a PNG image, at one pixel per cell or drawn with the style, with the description as a comment
+pub fn encode_snapshot(cells: &CellColors, style: &FrameStyle, scale: SnapshotScale, description: &str) -> Result<Vec<u8>, String> {
+    let (cell_size, cell_border_size) = match scale {
+        SnapshotScale::OnePixelPerCell => (1, 0),
+        SnapshotScale::DisplayedCellSize => (style.cell_size, style.cell_border_size),
+    };
+    if cell_size == 0 || cells.width == 0 || cells.height == 0 {
+        return Err(String::from("The snapshot would be empty"));
+    }
+    let (image_width, image_height) = (cells.width.saturating_mul(cell_size), cells.height.saturating_mul(cell_size));
+    if image_width > MAX_SNAPSHOT_SIDE || image_height > MAX_SNAPSHOT_SIDE {
+        return Err(String::from("Snapshots can be at most ") + &MAX_SNAPSHOT_SIDE.to_string() + " pixels wide and high, this one would be "
+            + &image_width.to_string() + "x" + &image_height.to_string() + " (try one pixel per cell)");
+    }
+    let scaled_style = FrameStyle{cell_size, cell_border_size, border_color: style.border_color, dead_cell_color: style.dead_cell_color};
+    let pixels = render_frame(cells, &scaled_style, 0, 0, cells.width, cells.height);
+    let rgb: Vec<u8> = pixels.iter().flat_map(|color| [color.r(), color.g(), color.b()]).collect();
+
+    let mut bytes: Vec<u8> = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, image_width as u32, image_height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.add_itxt_chunk(String::from("Comment"), String::from(description)).map_err(|error| error.to_string())?;
+        let mut writer = encoder.write_header().map_err(|error| error.to_string())?;
+        writer.write_image_data(&rgb).map_err(|error| error.to_string())?;
+        writer.finish().map_err(|error| error.to_string())?;
+    }
+    Ok(bytes)
+}
+
+/// Largest side, in pixels, of snapshots taken at the displayed cell size
+const MAX_SNAPSHOT_SIDE: usize = 16384;
+
+/// Largest side, in cells, of imported patterns
+const MAX_IMPORTED_SIDE: usize = 2000;
+
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct ImageImportSettings {
+    /// Pixels darker than this luminance (0 to 255) become living cells
+    pub threshold: u8,
+    /// Whether the thresholding error is diffused to the neighbouring pixels (Floyd-Steinberg), so grey areas become patterns of the matching density
+    pub dithering: bool,
+    /// Whether light pixels become living cells instead of dark ones
+    pub invert: bool,
+    /// Side of the square of pixels averaged into each cell (e.g. the cell size of a snapshot taken at the displayed size)
+    pub pixels_per_cell: usize
+}
+
+impl Default for ImageImportSettings {
+    fn default() -> Self {
+        ImageImportSettings {
+            threshold: 128,
+            dithering: false,
+            invert: false,
+            pixels_per_cell: 1
+        }
+    }
+}
+
+/// Turns a PNG or BMP image into a pattern by thresholding the luminance of its pixels; transparent pixels count as white
+pub fn import_image(bytes: &[u8], settings: &ImageImportSettings) -> Result<Field, String> {
+    let get_reader = || image::ImageReader::new(Cursor::new(bytes)).with_guessed_format()
+        .map_err(|error| String::from("Couldn't read the image: ") + &error.to_string());
+    // the size is checked from the header, before decoding anything
+    let (image_width, image_height) = get_reader()?.into_dimensions().map_err(|error| String::from("Couldn't read the image: ") + &error.to_string())?;
+    let pixels_per_cell = settings.pixels_per_cell.max(1);
+    let width = (image_width as usize).div_ceil(pixels_per_cell);
+    let height = (image_height as usize).div_ceil(pixels_per_cell);
+    if width == 0 || height == 0 {
+        return Err(String::from("The image is empty"));
+    }
+    if width > MAX_IMPORTED_SIDE || height > MAX_IMPORTED_SIDE {
+        return Err(String::from("Patterns can be at most ") + &MAX_IMPORTED_SIDE.to_string() + " cells wide and high, this one would be "
+            + &width.to_string() + "x" + &height.to_string() + " (try more pixels per cell)");
+    }
+    let mut reader = get_reader()?;
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(image_width);
+    limits.max_image_height = Some(image_height);
+    reader.limits(limits);
+    let image = reader.decode().map_err(|error| String::from("Couldn't read the image: ") + &error.to_string())?.to_rgba8();
+
+    // Average luminance of the pixels of each cell
+    let mut luminances: Vec<f32> = vec![0.0; width * height];
+    let mut n_pixels: Vec<usize> = vec![0; width * height];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        let opacity = a as f32 / 255.0;
+        let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        let index = x as usize / pixels_per_cell + y as usize / pixels_per_cell * width;
+        luminances[index] += luminance * opacity + 255.0 * (1.0 - opacity);
+        n_pixels[index] += 1;
+    }
+    for (luminance, n_pixels) in luminances.iter_mut().zip(n_pixels.iter()) {
+        *luminance /= *n_pixels as f32;
+        if settings.invert {
+            *luminance = 255.0 - *luminance;
+        }
+    }
+
+    let mut pattern = Field::new(width, height);
+    let threshold = settings.threshold as f32;
+    for y in 0..height {
+        for x in 0..width {
+            let luminance = luminances[x + y * width];
+            let is_alive = luminance < threshold;
+            if is_alive {
+                pattern.set_cell_state(x, y, CellState::Alive);
+            }
+            if settings.dithering {
+                let error = luminance - if is_alive {0.0} else {255.0};
+                let mut diffuse = |dx: isize, dy: usize, weight: f32| {
+                    let neighbour_x = x as isize + dx;
+                    if neighbour_x >= 0 && (neighbour_x as usize) < width && y + dy < height {
+                        luminances[neighbour_x as usize + (y + dy) * width] += error * weight;
+                    }
+                };
+                diffuse(1, 0, 7.0 / 16.0);
+                diffuse(-1, 1, 3.0 / 16.0);
+                diffuse(0, 1, 5.0 / 16.0);
+                diffuse(1, 1, 1.0 / 16.0);
+            }
+        }
+    }
+    Ok(pattern)
+}
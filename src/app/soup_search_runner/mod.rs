@@ -0,0 +1,93 @@
+#[cfg(target_arch = "wasm32")]
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::game::field::CellState;
+use crate::game::soup_search::{SoupSearchResults, SoupSearchSettings, get_known_objects, search_soup};
+
+/// Soups searched per frame on the web, where there are no threads to search in the background
+#[cfg(target_arch = "wasm32")]
+const SOUPS_PER_FRAME: usize = 2;
+
+/// Runs a soup search in a background thread on native, or a few soups per frame (see `run_pending_soups`) on the web.
+#[derive(Default)]
+pub struct SoupSearchRunner {
+    settings: Option<SoupSearchSettings>,
+    results: Arc<Mutex<SoupSearchResults>>,
+    /// Each search gets its own flag, so that a stopped thread can't be revived by the next search
+    is_running: Arc<AtomicBool>,
+    #[cfg(target_arch = "wasm32")]
+    known_objects: HashMap<String, String>
+}
+
+impl SoupSearchRunner {
+    /// Stops any search in progress and starts a new one, from scratch
+    pub fn start(&mut self, settings: SoupSearchSettings) -> Result<(), String> {
+        if settings.rule.get_next_cell_state(CellState::Dead, 0) == CellState::Alive {
+            return Err(String::from("Rules with B0 can't be run on an unbounded plane"));
+        }
+        self.stop();
+        self.results = Arc::new(Mutex::new(SoupSearchResults::default()));
+        self.is_running = Arc::new(AtomicBool::new(true));
+        self.settings = Some(settings.clone());
+        let known_objects = get_known_objects(&settings.rule);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let results = self.results.clone();
+            let is_running = self.is_running.clone();
+            std::thread::spawn(move || {
+                let mut seed = settings.first_seed;
+                while is_running.load(Ordering::Relaxed) {
+                    let Some(census) = search_soup(&settings, seed, &is_running) else {
+                        break;
+                    };
+                    if let Ok(mut results) = results.lock() {
+                        results.add_census(census, seed, &known_objects);
+                    }
+                    seed = seed.wrapping_add(1);
+                }
+            });
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.known_objects = known_objects;
+        }
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::Relaxed)
+    }
+
+    /// Settings of the current (or last) search
+    pub fn get_settings(&self) -> Option<&SoupSearchSettings> {
+        self.settings.as_ref()
+    }
+
+    pub fn get_results(&self) -> SoupSearchResults {
+        self.results.lock().map(|results| results.clone()).unwrap_or_default()
+    }
+
+    /// Searches the next few soups on the web; the background thread does it on native
+    pub fn run_pending_soups(&mut self) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let (Some(settings), true) = (&self.settings, self.is_running()) else {
+                return;
+            };
+            if let Ok(mut results) = self.results.lock() {
+                for _ in 0..SOUPS_PER_FRAME {
+                    let seed = settings.first_seed.wrapping_add(results.n_soups_searched as u64);
+                    if let Some(census) = search_soup(settings, seed, &self.is_running) {
+                        results.add_census(census, seed, &self.known_objects);
+                    }
+                }
+            }
+        }
+    }
+}
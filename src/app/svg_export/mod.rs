@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use super::animation_export::{CellColors, FrameStyle};
+
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct SvgExportSettings {
+    /// Whether the cell borders are drawn, as lines between every cell, dead ones included (without them, runs of cells
+    /// of the same colour are seamless)
+    pub grid_lines: bool,
+    /// Whether the generation and rule are written under the board
+    pub caption: bool,
+    /// Whether only the selected cells are exported, when there is a selection
+    pub selection_only: bool
+}
+
+/// Height of the caption line, in pixels
+const CAPTION_HEIGHT: usize = 24;
+
+/// Writes the cells as an SVG image drawn with the style: a background of the dead cell colour, then, row by row,
+/// one rectangle per run of adjacent cells of the same colour, so files stay small, and the borders on top as grid lines
+/// (so the borders inside runs are drawn too). The description goes in the <desc> element.
+pub fn encode_svg(cells: &CellColors, style: &FrameStyle, settings: &SvgExportSettings, caption: &str, description: &str) -> String {
+    let cell_size = style.cell_size;
+    let border = style.cell_border_size.min(cell_size.saturating_sub(1) / 2);
+    let board_width = cells.width * cell_size;
+    let board_height = cells.height * cell_size;
+    let image_height = board_height + if settings.caption {CAPTION_HEIGHT} else {0};
+
+    // Runs grouped by colour, so each colour is written once
+    let mut runs_by_color: BTreeMap<[u8; 3], Vec<(usize, usize, usize)>> = BTreeMap::new();
+    for y in 0..cells.height {
+        let mut x = 0;
+        while x < cells.width {
+            let color = cells.colors[x + y * cells.width];
+            let run_start = x;
+            while x < cells.width && cells.colors[x + y * cells.width] == color {
+                x += 1;
+            }
+            if color != style.dead_cell_color {
+                runs_by_color.entry([color.r(), color.g(), color.b()]).or_default().push((run_start, y, x - run_start));
+            }
+        }
+    }
+
+    let mut svg = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    svg += &(String::from("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"") + &board_width.to_string() + "\" height=\"" + &image_height.to_string()
+        + "\" viewBox=\"0 0 " + &board_width.to_string() + " " + &image_height.to_string() + "\" shape-rendering=\"crispEdges\">\n");
+    svg += &(String::from("<desc>") + &escape_xml(description) + "</desc>\n");
+    svg += &(String::from("<rect width=\"") + &board_width.to_string() + "\" height=\"" + &board_height.to_string() + "\" fill=\"" + &get_hex_color(style.dead_cell_color) + "\"/>\n");
+    for ([r, g, b], runs) in &runs_by_color {
+        svg += &(String::from("<g fill=\"") + &get_hex_color(egui::Color32::from_rgb(*r, *g, *b)) + "\">\n");
+        for (x, y, length) in runs {
+            svg += &(String::from("<rect x=\"") + &(x * cell_size).to_string() + "\" y=\"" + &(y * cell_size).to_string()
+                + "\" width=\"" + &(length * cell_size).to_string() + "\" height=\"" + &cell_size.to_string() + "\"/>\n");
+        }
+        svg += "</g>\n";
+    }
+    if settings.grid_lines && border > 0 {
+        // Each cell has a border on both sides, so the lines between cells are twice as wide (and half of the outer ones is cut off)
+        let mut path = String::new();
+        for x in 0..=cells.width {
+            path = path + "M" + &(x * cell_size).to_string() + " 0V" + &board_height.to_string();
+        }
+        for y in 0..=cells.height {
+            path = path + "M0 " + &(y * cell_size).to_string() + "H" + &board_width.to_string();
+        }
+        svg += &(String::from("<path d=\"") + &path + "\" stroke=\"" + &get_hex_color(style.border_color) + "\" stroke-width=\"" + &(2 * border).to_string() + "\"/>\n");
+    }
+    if settings.caption {
+        svg += &(String::from("<text x=\"4\" y=\"") + &(board_height + CAPTION_HEIGHT - 7).to_string() + "\" font-family=\"sans-serif\" font-size=\"14\">"
+            + &escape_xml(caption) + "</text>\n");
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+/// Colour written as #rrggbb
+fn get_hex_color(color: egui::Color32) -> String {
+    let hex_digits = b"0123456789abcdef";
+    let mut hex = String::from("#");
+    for component in [color.r(), color.g(), color.b()] {
+        hex.push(hex_digits[(component >> 4) as usize] as char);
+        hex.push(hex_digits[(component & 0xF) as usize] as char);
+    }
+    hex
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_row_of_living_cells(border: usize) -> (CellColors, FrameStyle) {
+        let cells = CellColors{width: 3, height: 1, colors: vec![egui::Color32::GREEN; 3]};
+        let style = FrameStyle{cell_size: 10, cell_border_size: border, border_color: egui::Color32::BLACK, dead_cell_color: egui::Color32::GRAY};
+        (cells, style)
+    }
+
+    #[test]
+    fn runs_are_merged_under_the_grid_lines() {
+        let (cells, style) = get_row_of_living_cells(2);
+        let settings = SvgExportSettings{grid_lines: true, ..SvgExportSettings::default()};
+        let svg = encode_svg(&cells, &style, &settings, "", "");
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"30\" height=\"10\"/>"));
+        // the borders between the cells of the run are drawn on top of it
+        assert!(svg.contains("M10 0V10M20 0V10"));
+        assert!(svg.contains("stroke-width=\"4\""));
+    }
+
+    #[test]
+    fn there_are_no_grid_lines_without_borders() {
+        let (cells, style) = get_row_of_living_cells(0);
+        let settings = SvgExportSettings{grid_lines: true, ..SvgExportSettings::default()};
+        assert!(!encode_svg(&cells, &style, &settings, "", "").contains("<path"));
+    }
+}
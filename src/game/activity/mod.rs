@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use super::field::{CellState, Field};
+
+/// What the activity of a cell counts
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum ActivityMeasure {
+    /// Generations in which the cell was born or died
+    #[default]
+    StateChanges,
+    /// Generations in which the cell was alive
+    LiveGenerations
+}
+
+pub const ACTIVITY_MEASURES: [(ActivityMeasure, &str); 2] = [
+    (ActivityMeasure::StateChanges, "State changes"),
+    (ActivityMeasure::LiveGenerations, "Live generations")
+];
+
+/// Per-cell counts of state changes and live generations over the last `window` generations (all of them if 0).
+/// Only the window is saved: the counts start over with each session, and are only kept while the map is enabled.
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct ActivityMap {
+    window: usize,
+    #[serde(skip)]
+    is_enabled: bool,
+    #[serde(skip)]
+    width: usize,
+    #[serde(skip)]
+    height: usize,
+    #[serde(skip)]
+    change_counts: Vec<u32>,
+    #[serde(skip)]
+    live_counts: Vec<u32>,
+    /// Cells that changed and cells alive in each generation of the window, oldest first, as bitsets of 64 cells
+    /// per word, so they can be taken off the counts as they leave it
+    #[serde(skip)]
+    history: VecDeque<[Vec<u64>; 2]>,
+    #[serde(skip)]
+    n_generations: usize
+}
+
+impl Default for ActivityMap {
+    fn default() -> Self {
+        ActivityMap {
+            window: 100,
+            is_enabled: false,
+            width: 0,
+            height: 0,
+            change_counts: Vec::new(),
+            live_counts: Vec::new(),
+            history: VecDeque::new(),
+            n_generations: 0
+        }
+    }
+}
+
+impl ActivityMap {
+    pub fn get_window(&self) -> usize {
+        self.window
+    }
+
+    /// Changing the window starts the counts over, since the generations it would take in were never recorded
+    pub fn set_window(&mut self, window: usize) {
+        if window != self.window {
+            self.window = window;
+            self.reset(self.width, self.height);
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled
+    }
+
+    /// Generations are only counted while the map is enabled; enabling it starts the counts over, and disabling it frees them
+    pub fn set_enabled(&mut self, is_enabled: bool) {
+        if is_enabled != self.is_enabled {
+            self.is_enabled = is_enabled;
+            let (width, height) = if is_enabled {(self.width, self.height)} else {(0, 0)};
+            self.reset(width, height);
+        }
+    }
+
+    pub fn reset(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.change_counts = vec![0; width * height];
+        self.live_counts = vec![0; width * height];
+        self.history.clear();
+        self.n_generations = 0;
+    }
+
+    /// Counts the generation that turned `previous_field` into `field`; fields of other dimensions start the counts over
+    pub fn update(&mut self, previous_field: &Field, field: &Field) {
+        if !self.is_enabled {
+            return;
+        }
+        if field.get_width() != self.width || field.get_height() != self.height {
+            self.reset(field.get_width(), field.get_height());
+        }
+        let n_cells = field.get_cells().len();
+        if previous_field.get_cells().len() != n_cells {
+            return;
+        }
+        // the bitsets leaving the window are reused for the new generation
+        let mut bitsets: [Vec<u64>; 2] = Default::default();
+        if self.window > 0 && self.history.len() >= self.window {
+            if let Some(oldest_bitsets) = self.history.pop_front() {
+                for (counts, bitset) in [&mut self.change_counts, &mut self.live_counts].into_iter().zip(&oldest_bitsets) {
+                    for_each_set_bit(bitset, |index| counts[index] -= 1);
+                }
+                self.n_generations -= 1;
+                bitsets = oldest_bitsets;
+            }
+        }
+        for bitset in bitsets.iter_mut() {
+            bitset.clear();
+            bitset.resize(n_cells.div_ceil(64), 0);
+        }
+        let [changed_cells, live_cells] = &mut bitsets;
+        for (index, (previous_cell_state, cell_state)) in previous_field.get_cells().iter().zip(field.get_cells()).enumerate() {
+            if previous_cell_state != cell_state {
+                changed_cells[index / 64] |= 1 << (index % 64);
+                self.change_counts[index] += 1;
+            }
+            if *cell_state == CellState::Alive {
+                live_cells[index / 64] |= 1 << (index % 64);
+                self.live_counts[index] += 1;
+            }
+        }
+        self.n_generations += 1;
+        if self.window > 0 {
+            self.history.push_back(bitsets);
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Generations the counts cover
+    pub fn get_n_generations(&self) -> usize {
+        self.n_generations
+    }
+
+    pub fn get_count(&self, measure: ActivityMeasure, x: usize, y: usize) -> u32 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        self.get_counts(measure)[x + y * self.width]
+    }
+
+    pub fn get_max_count(&self, measure: ActivityMeasure) -> u32 {
+        self.get_counts(measure).iter().copied().max().unwrap_or(0)
+    }
+
+    fn get_counts(&self, measure: ActivityMeasure) -> &[u32] {
+        match measure {
+            ActivityMeasure::StateChanges => &self.change_counts,
+            ActivityMeasure::LiveGenerations => &self.live_counts,
+        }
+    }
+}
+
+/// Calls `f` with the index of each bit set in the bitset
+fn for_each_set_bit(bitset: &[u64], mut f: impl FnMut(usize)) {
+    for (word_index, word) in bitset.iter().enumerate() {
+        let mut word = *word;
+        while word != 0 {
+            f(64 * word_index + word.trailing_zeros() as usize);
+            word &= word - 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_over_the_window() {
+        let mut activity_map = ActivityMap::default();
+        activity_map.set_window(2);
+        let mut fields = vec![Field::new(70, 2)];
+        for generation in 0..4 {
+            let mut field = Field::new(70, 2);
+            // cell (69, 1) blinks, cell (0, 0) comes alive once and stays so
+            if generation % 2 == 0 {
+                field.set_cell_state(69, 1, CellState::Alive);
+            }
+            if generation >= 1 {
+                field.set_cell_state(0, 0, CellState::Alive);
+            }
+            fields.push(field);
+        }
+
+        activity_map.update(&fields[0], &fields[1]);
+        assert_eq!(activity_map.get_n_generations(), 0, "generations aren't counted while the map is disabled");
+        activity_map.set_enabled(true);
+        for generation in 0..4 {
+            activity_map.update(&fields[generation], &fields[generation + 1]);
+        }
+        assert_eq!(activity_map.get_n_generations(), 2);
+        assert_eq!(activity_map.get_count(ActivityMeasure::StateChanges, 69, 1), 2);
+        assert_eq!(activity_map.get_count(ActivityMeasure::LiveGenerations, 69, 1), 1);
+        assert_eq!(activity_map.get_count(ActivityMeasure::StateChanges, 0, 0), 0);
+        assert_eq!(activity_map.get_count(ActivityMeasure::LiveGenerations, 0, 0), 2);
+        assert_eq!(activity_map.get_max_count(ActivityMeasure::StateChanges), 2);
+    }
+}
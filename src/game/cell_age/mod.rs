@@ -0,0 +1,91 @@
+use super::field::{CellState, Field};
+use super::field_error::FieldError;
+
+/// Generations since death of cells that have never died
+const NEVER_DIED: u32 = u32::MAX;
+
+/// How long each cell of a field has been alive, or dead since it last lived
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct CellAges {
+    width: usize,
+    height: usize,
+    /// Consecutive generations each cell has been alive for, 0 for dead cells and cells not yet seen alive by `update`
+    #[serde(with = "super::save_format::packed_counts")]
+    ages: Vec<u32>,
+    /// Generations since each dead cell last died
+    #[serde(with = "super::save_format::packed_counts")]
+    generations_since_death: Vec<u32>
+}
+
+impl CellAges {
+    pub fn new(width: usize, height: usize) -> CellAges {
+        CellAges {
+            width,
+            height,
+            ages: vec![0; width * height],
+            generations_since_death: vec![NEVER_DIED; width * height]
+        }
+    }
+
+    /// Forgets the history of every cell, e.g. once the board has been cleared or randomized
+    pub fn reset(&mut self, width: usize, height: usize) {
+        *self = CellAges::new(width, height);
+    }
+
+    /// Records a new generation of the field; a field of other dimensions (or a damaged save) starts the history over
+    pub fn update(&mut self, field: &Field) {
+        let n_cells = field.get_width() * field.get_height();
+        if field.get_width() != self.width || field.get_height() != self.height
+            || self.ages.len() != n_cells || self.generations_since_death.len() != n_cells {
+            self.reset(field.get_width(), field.get_height());
+        }
+        for (index, cell_state) in field.get_cells().iter().enumerate() {
+            if *cell_state == CellState::Alive {
+                self.ages[index] = self.ages[index].saturating_add(1);
+                self.generations_since_death[index] = NEVER_DIED;
+            }
+            else if self.ages[index] > 0 {
+                self.ages[index] = 0;
+                self.generations_since_death[index] = 1;
+            }
+            else if self.generations_since_death[index] != NEVER_DIED {
+                self.generations_since_death[index] = self.generations_since_death[index].saturating_add(1).min(NEVER_DIED - 1);
+            }
+        }
+    }
+
+    /// Whether the counts match their dimensions and those of the field of `width` x `height` cells, which a damaged save
+    /// may not; ages of saves from before they were saved are empty
+    pub(crate) fn check_dimensions(&self, width: usize, height: usize) -> Result<(), FieldError> {
+        let expected_n_cells = self.width * self.height;
+        for counts in [&self.ages, &self.generations_since_death] {
+            if counts.len() != expected_n_cells {
+                return Err(FieldError::CellCountMismatch{n_cells: counts.len(), expected_n_cells});
+            }
+        }
+        if expected_n_cells > 0 && (self.width != width || self.height != height) {
+            return Err(FieldError::DimensionMismatch{width: self.width, height: self.height, expected_width: width, expected_height: height});
+        }
+        Ok(())
+    }
+
+    /// Generations the cell has been alive for (0 if it was born, or drawn, since the last update)
+    pub fn get_age(&self, x: usize, y: usize) -> u32 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        self.ages.get(x + y * self.width).copied().unwrap_or(0)
+    }
+
+    /// Generations since the cell died, None if it is alive or hasn't died since the history started
+    pub fn get_generations_since_death(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let generations_since_death = self.generations_since_death.get(x + y * self.width).copied().unwrap_or(NEVER_DIED);
+        if generations_since_death == NEVER_DIED {None} else {Some(generations_since_death)}
+    }
+}
@@ -0,0 +1,112 @@
+use super::field_error::FieldError;
+
+/// Like `Field`, but each cell holds a continuous state between 0.0 (empty) and 1.0 (full).
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct ContinuousField {
+    width: usize,
+    height: usize,
+    #[serde(with = "super::save_format::packed_levels")]
+    cells: Vec<f32>
+}
+
+impl ContinuousField {
+    pub fn new(width: usize, height: usize) -> ContinuousField {
+        ContinuousField{width, height, cells: vec![0.0; width * height]}
+    }
+
+    /// Reads a pattern written as rows of two-digit hexadecimal values (00 for 0.0 to ff for 1.0), one row per line
+    pub fn from_hex_rows(text: &str) -> Result<ContinuousField, String> {
+        let rows: Vec<&str> = text.lines().map(|row| row.trim()).filter(|row| !row.is_empty()).collect();
+        let width = rows.iter().map(|row| row.len() / 2).max().unwrap_or(0);
+        let mut field = ContinuousField::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0..row.len() / 2 {
+                let hex_value = row.get(2 * x..2 * x + 2).unwrap_or("");
+                let value = u8::from_str_radix(hex_value, 16)
+                    .map_err(|_| String::from("Invalid value \"") + hex_value + "\" on row " + &(y + 1).to_string())?;
+                field.set_cell_state(x, y, value as f32 / 255.0);
+            }
+        }
+        Ok(field)
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// The levels row by row: the cell (x, y) is at x + y * width
+    pub fn get_cells(&self) -> &[f32] {
+        &self.cells
+    }
+
+    pub(crate) fn get_cells_mut(&mut self) -> &mut [f32] {
+        &mut self.cells
+    }
+
+    pub fn is_within_boundaries(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Cells outside of the board are empty
+    pub fn get_cell_state(&self, x: usize, y: usize) -> f32 {
+        if self.is_within_boundaries(x, y) {
+            return self.cells[x + y * self.width]
+        }
+        0.0
+    }
+
+    pub fn set_cell_state(&mut self, x: usize, y: usize, cell_state: f32) {
+        if self.is_within_boundaries(x, y) {
+            self.cells[x + y * self.width] = cell_state.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Like `get_cell_state`, but with an error rather than an empty cell outside of the board
+    pub fn try_get_cell_state(&self, x: usize, y: usize) -> Result<f32, FieldError> {
+        self.check_boundaries(x, y)?;
+        Ok(self.cells[x + y * self.width])
+    }
+
+    /// Like `set_cell_state`, but with an error rather than nothing happening outside of the board
+    pub fn try_set_cell_state(&mut self, x: usize, y: usize, cell_state: f32) -> Result<(), FieldError> {
+        self.check_boundaries(x, y)?;
+        self.cells[x + y * self.width] = cell_state.clamp(0.0, 1.0);
+        Ok(())
+    }
+
+    fn check_boundaries(&self, x: usize, y: usize) -> Result<(), FieldError> {
+        if self.is_within_boundaries(x, y) {
+            return Ok(());
+        }
+        Err(FieldError::OutOfBounds{x, y, width: self.width, height: self.height})
+    }
+
+    /// Whether the cells match the dimensions, which a damaged save may not
+    pub(crate) fn check_cell_count(&self) -> Result<(), FieldError> {
+        if self.cells.len() == self.width * self.height {
+            return Ok(());
+        }
+        Err(FieldError::CellCountMismatch{n_cells: self.cells.len(), expected_n_cells: self.width * self.height})
+    }
+
+    /// Copies the pattern so that its center lands on (center_x, center_y); parts falling outside of the board are dropped
+    pub fn paste(&mut self, pattern: &ContinuousField, center_x: usize, center_y: usize) {
+        for y in 0..pattern.get_height() {
+            for x in 0..pattern.get_width() {
+                let target_x = (center_x + x).checked_sub(pattern.get_width() / 2);
+                let target_y = (center_y + y).checked_sub(pattern.get_height() / 2);
+                if let (Some(target_x), Some(target_y)) = (target_x, target_y) {
+                    self.set_cell_state(target_x, target_y, pattern.get_cell_state(x, y));
+                }
+            }
+        }
+    }
+}
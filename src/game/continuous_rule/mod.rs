@@ -0,0 +1,335 @@
+use std::sync::Arc;
+use rustfft::{Fft, FftPlanner};
+use rustfft::num_complex::Complex;
+use super::continuous_field::ContinuousField;
+
+/// Parameters of Bert Chan's Lenia: the field is convolved with a kernel made of concentric rings,
+/// and each cell grows or shrinks by `growth(potential) / time_resolution` per generation.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct LeniaParameters {
+    /// Kernel radius (R), in cells
+    pub radius: usize,
+    /// Number of generations per unit of time (T)
+    pub time_resolution: f32,
+    /// Potential at which growth peaks (μ)
+    pub growth_center: f32,
+    /// Width of the growth peak (σ)
+    pub growth_width: f32,
+    /// Relative height of each of the kernel's rings, innermost first (β)
+    pub ring_heights: Vec<f32>
+}
+
+impl Default for LeniaParameters {
+    /// Parameters of Orbium unicaudatus, the best known Lenia glider
+    fn default() -> Self {
+        LeniaParameters{radius: 13, time_resolution: 10.0, growth_center: 0.15, growth_width: 0.015, ring_heights: vec![1.0]}
+    }
+}
+
+/// A Lenia pattern, stored as rows of two-digit hexadecimal values (see `ContinuousField::from_hex_rows`),
+/// along with the parameters it lives under.
+pub struct LeniaCreature {
+    pub name: &'static str,
+    pub radius: usize,
+    pub time_resolution: f32,
+    pub growth_center: f32,
+    pub growth_width: f32,
+    pub ring_heights: &'static [f32],
+    pub cells: &'static str
+}
+
+impl LeniaCreature {
+    pub fn get_parameters(&self) -> LeniaParameters {
+        LeniaParameters{
+            radius: self.radius,
+            time_resolution: self.time_resolution,
+            growth_center: self.growth_center,
+            growth_width: self.growth_width,
+            ring_heights: self.ring_heights.to_vec()
+        }
+    }
+}
+
+/// Creatures from Bert Chan's Lenia catalogue. Only Orbium is bundled for now: the other creatures only live under
+/// their exact parameters and cells, which have to be taken from the catalogue itself.
+pub const LENIA_CREATURES: [LeniaCreature; 1] = [
+    LeniaCreature{
+        name: "Orbium unicaudatus",
+        radius: 13,
+        time_resolution: 10.0,
+        growth_center: 0.15,
+        growth_width: 0.015,
+        ring_heights: &[1.0],
+        cells: include_str!("../../../assets/lenia/Orbium.txt")
+    }
+];
+
+/// Parameters of Stephan Rafler's SmoothLife: cells are born or survive according to the filling
+/// of the disk around them (m) and of the ring around that disk (n).
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct SmoothLifeParameters {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    /// Birth interval of the ring filling, for an empty cell
+    pub birth_min: f32,
+    pub birth_max: f32,
+    /// Survival interval of the ring filling, for a full cell
+    pub death_min: f32,
+    pub death_max: f32,
+    /// Smoothness of the transitions in the ring filling (αn) and in the disk filling (αm)
+    pub alpha_n: f32,
+    pub alpha_m: f32,
+    /// 1.0 replaces each cell with its transition value; smaller values move towards it gradually
+    pub time_step: f32
+}
+
+impl Default for SmoothLifeParameters {
+    /// The gliders-friendly parameters from Rafler's paper
+    fn default() -> Self {
+        SmoothLifeParameters{
+            inner_radius: 7.0,
+            outer_radius: 21.0,
+            birth_min: 0.278,
+            birth_max: 0.365,
+            death_min: 0.267,
+            death_max: 0.445,
+            alpha_n: 0.028,
+            alpha_m: 0.147,
+            time_step: 1.0
+        }
+    }
+}
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum ContinuousRule {
+    Lenia(LeniaParameters),
+    SmoothLife(SmoothLifeParameters)
+}
+
+/// A rule for continuous fields, along with the convolution kernels prepared for the last field it advanced.
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct ContinuousEngine {
+    rule: ContinuousRule,
+    #[serde(skip)]
+    convolvers: Option<Vec<FftConvolver>>
+}
+
+impl ContinuousEngine {
+    pub fn new(rule: ContinuousRule) -> ContinuousEngine {
+        ContinuousEngine{rule, convolvers: None}
+    }
+
+    pub fn get_rule(&self) -> &ContinuousRule {
+        &self.rule
+    }
+
+    pub fn advance_generation(&mut self, field: &ContinuousField, consider_extremes_adjacent: bool) -> ContinuousField {
+        let width = field.get_width();
+        let height = field.get_height();
+        let is_prepared = self.convolvers.as_ref()
+            .and_then(|convolvers| convolvers.first())
+            .is_some_and(|convolver| convolver.is_prepared_for(width, height, consider_extremes_adjacent));
+        if !is_prepared {
+            let kernels = match &self.rule {
+                ContinuousRule::Lenia(parameters) => vec![get_lenia_kernel(parameters)],
+                ContinuousRule::SmoothLife(parameters) => Vec::from(get_smooth_life_kernels(parameters)),
+            };
+            self.convolvers = Some(kernels.iter().map(|kernel| FftConvolver::new(kernel, width, height, consider_extremes_adjacent)).collect());
+        }
+        let convolvers = self.convolvers.as_ref().map_or(&[][..], |convolvers| convolvers.as_slice());
+
+        let mut next_field = field.clone();
+        match &self.rule {
+            ContinuousRule::Lenia(parameters) => {
+                let potentials = convolvers[0].convolve(field.get_cells());
+                for (cell, potential) in next_field.get_cells_mut().iter_mut().zip(potentials) {
+                    let growth = get_lenia_growth(potential, parameters.growth_center, parameters.growth_width);
+                    *cell = (*cell + growth / parameters.time_resolution).clamp(0.0, 1.0);
+                }
+            }
+            ContinuousRule::SmoothLife(parameters) => {
+                let inner_fillings = convolvers[0].convolve(field.get_cells());
+                let outer_fillings = convolvers[1].convolve(field.get_cells());
+                for ((cell, inner_filling), outer_filling) in next_field.get_cells_mut().iter_mut().zip(inner_fillings).zip(outer_fillings) {
+                    let transition = get_smooth_life_transition(parameters, outer_filling, inner_filling);
+                    *cell = if parameters.time_step >= 1.0 {
+                        transition
+                    }
+                    else {
+                        (*cell + parameters.time_step * (2.0 * transition - 1.0)).clamp(0.0, 1.0)
+                    };
+                }
+            }
+        }
+        next_field
+    }
+}
+
+/// A kernel as the weights of the cells at the given offsets, normalized to add up to 1
+struct Kernel {
+    weights: Vec<(isize, isize, f32)>,
+    radius: usize
+}
+
+impl Kernel {
+    fn new(radius: usize, weight_at_distance: impl Fn(f32) -> f32) -> Kernel {
+        let mut weights: Vec<(isize, isize, f32)> = Vec::new();
+        let reach = radius as isize;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let weight = weight_at_distance(((dx * dx + dy * dy) as f32).sqrt());
+                if weight > 0.0 {
+                    weights.push((dx, dy, weight));
+                }
+            }
+        }
+        let total_weight: f32 = weights.iter().map(|(_, _, weight)| weight).sum();
+        if total_weight > 0.0 {
+            for (_, _, weight) in weights.iter_mut() {
+                *weight /= total_weight;
+            }
+        }
+        Kernel{weights, radius}
+    }
+}
+
+fn get_lenia_kernel(parameters: &LeniaParameters) -> Kernel {
+    let n_rings = parameters.ring_heights.len().max(1);
+    Kernel::new(parameters.radius, |distance| {
+        let relative_distance = distance / parameters.radius as f32 * n_rings as f32;
+        let ring = relative_distance.floor() as usize;
+        let position_in_ring = relative_distance.fract();
+        match parameters.ring_heights.get(ring) {
+            // smooth bump, peaking in the middle of the ring and vanishing at its edges
+            Some(ring_height) if position_in_ring > 0.0 => ring_height * (4.0 - 1.0 / (position_in_ring * (1.0 - position_in_ring))).exp(),
+            _ => 0.0,
+        }
+    })
+}
+
+/// The disk within the inner radius, and the ring between the inner and outer radii, anti-aliased over one cell
+fn get_smooth_life_kernels(parameters: &SmoothLifeParameters) -> [Kernel; 2] {
+    let inner_weight = |distance: f32| (parameters.inner_radius + 0.5 - distance).clamp(0.0, 1.0);
+    let outer_weight = |distance: f32| (parameters.outer_radius + 0.5 - distance).clamp(0.0, 1.0) - inner_weight(distance);
+    let radius = parameters.outer_radius.ceil() as usize + 1;
+    [Kernel::new(radius, inner_weight), Kernel::new(radius, outer_weight)]
+}
+
+fn get_lenia_growth(potential: f32, growth_center: f32, growth_width: f32) -> f32 {
+    let deviation = (potential - growth_center) / growth_width;
+    2.0 * (-deviation * deviation / 2.0).exp() - 1.0
+}
+
+fn get_smooth_life_transition(parameters: &SmoothLifeParameters, outer_filling: f32, inner_filling: f32) -> f32 {
+    let sigmoid = |x: f32, a: f32, alpha: f32| 1.0 / (1.0 + (-(x - a) * 4.0 / alpha).exp());
+    let interval = |x: f32, a: f32, b: f32| sigmoid(x, a, parameters.alpha_n) * (1.0 - sigmoid(x, b, parameters.alpha_n));
+    let aliveness = sigmoid(inner_filling, 0.5, parameters.alpha_m);
+    let mix = |dead_value: f32, alive_value: f32| dead_value * (1.0 - aliveness) + alive_value * aliveness;
+    interval(outer_filling, mix(parameters.birth_min, parameters.death_min), mix(parameters.birth_max, parameters.death_max))
+}
+
+/// Convolves fields of a given size with a kernel through the FFT. Convolutions through the FFT wrap around;
+/// when the extremes of the board aren't adjacent, the field is padded with empty cells so that they don't.
+#[derive(Clone)]
+struct FftConvolver {
+    width: usize,
+    height: usize,
+    consider_extremes_adjacent: bool,
+    padded_width: usize,
+    padded_height: usize,
+    kernel_spectrum: Vec<Complex<f32>>,
+    row_fft: Arc<dyn Fft<f32>>,
+    row_inverse_fft: Arc<dyn Fft<f32>>,
+    column_fft: Arc<dyn Fft<f32>>,
+    column_inverse_fft: Arc<dyn Fft<f32>>
+}
+
+impl FftConvolver {
+    fn new(kernel: &Kernel, width: usize, height: usize, consider_extremes_adjacent: bool) -> FftConvolver {
+        let padding = if consider_extremes_adjacent {0} else {kernel.radius};
+        let padded_width = width + padding;
+        let padded_height = height + padding;
+        let mut planner: FftPlanner<f32> = FftPlanner::new();
+        let mut convolver = FftConvolver{
+            width,
+            height,
+            consider_extremes_adjacent,
+            padded_width,
+            padded_height,
+            kernel_spectrum: Vec::new(),
+            row_fft: planner.plan_fft_forward(padded_width),
+            row_inverse_fft: planner.plan_fft_inverse(padded_width),
+            column_fft: planner.plan_fft_forward(padded_height),
+            column_inverse_fft: planner.plan_fft_inverse(padded_height)
+        };
+
+        let mut kernel_values = vec![Complex::new(0.0, 0.0); padded_width * padded_height];
+        for (dx, dy, weight) in &kernel.weights {
+            let x = dx.rem_euclid(padded_width as isize) as usize;
+            let y = dy.rem_euclid(padded_height as isize) as usize;
+            kernel_values[x + y * padded_width].re += weight;
+        }
+        convolver.transform(&mut kernel_values, false);
+        convolver.kernel_spectrum = kernel_values;
+        convolver
+    }
+
+    fn is_prepared_for(&self, width: usize, height: usize, consider_extremes_adjacent: bool) -> bool {
+        self.width == width && self.height == height && self.consider_extremes_adjacent == consider_extremes_adjacent
+    }
+
+    fn convolve(&self, cells: &[f32]) -> Vec<f32> {
+        let mut values = vec![Complex::new(0.0, 0.0); self.padded_width * self.padded_height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                values[x + y * self.padded_width].re = cells[x + y * self.width];
+            }
+        }
+        self.transform(&mut values, false);
+        for (value, kernel_value) in values.iter_mut().zip(&self.kernel_spectrum) {
+            *value *= kernel_value;
+        }
+        self.transform(&mut values, true);
+
+        let scale = 1.0 / (self.padded_width * self.padded_height) as f32;
+        let mut result = vec![0.0; self.width * self.height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                result[x + y * self.width] = values[x + y * self.padded_width].re * scale;
+            }
+        }
+        result
+    }
+
+    /// 2D FFT (or its unnormalized inverse) of row-major values, as the FFT of every row and then of every column
+    fn transform(&self, values: &mut [Complex<f32>], inverse: bool) {
+        let (row_fft, column_fft) = if inverse {(&self.row_inverse_fft, &self.column_inverse_fft)} else {(&self.row_fft, &self.column_fft)};
+        row_fft.process(values);
+        let mut columns = vec![Complex::new(0.0, 0.0); values.len()];
+        transpose(values, &mut columns, self.padded_width, self.padded_height);
+        column_fft.process(&mut columns);
+        transpose(&columns, values, self.padded_height, self.padded_width);
+    }
+}
+
+fn transpose(source: &[Complex<f32>], destination: &mut [Complex<f32>], source_width: usize, source_height: usize) {
+    for y in 0..source_height {
+        for x in 0..source_width {
+            destination[y + x * source_height] = source[x + y * source_width];
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use super::field_error::FieldError;
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum CellState {
+    Dead,
+    Alive,
+    Unknown
+}
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+pub struct CellPosition {
+    pub x: usize,
+    pub y: usize
+}
+
+/// A rectangle of cells, such as the selection on the board
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct CellRegion {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize
+}
+
+impl CellRegion {
+    /// The smallest region containing both corner cells, given in any order
+    pub fn from_corners(x1: usize, y1: usize, x2: usize, y2: usize) -> CellRegion {
+        CellRegion{x: x1.min(x2), y: y1.min(y2), width: x1.abs_diff(x2) + 1, height: y1.abs_diff(y2) + 1}
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Which cells count as neighbours on a triangular grid: the 3 cells sharing an edge,
+/// or the 12 cells sharing at least a vertex.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum TriangularNeighbourhood {
+    Edge,
+    #[default]
+    Vertex
+}
+
+// Offsets (dx, dy) of the neighbours of an upwards-pointing triangle; the ones of a
+// downwards-pointing triangle are the same reflected vertically (dy negated).
+const UP_TRIANGLE_EDGE_NEIGHBOUR_OFFSETS: [(isize, isize); 3] = [(-1, 0), (1, 0), (0, 1)];
+const UP_TRIANGLE_VERTEX_NEIGHBOUR_OFFSETS: [(isize, isize); 12] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-2, 0), (-1, 0), (1, 0), (2, 0),
+    (-2, 1), (-1, 1), (0, 1), (1, 1), (2, 1)
+];
+
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct Field {
+    width: usize,
+    height: usize,
+    #[serde(with = "super::save_format::packed_cells")]
+    cells: Vec<CellState>
+}
+
+impl Field {
+    pub fn new(width: usize, height: usize) -> Field {
+        let n_cells: usize = width * height;
+        let cells: Vec<CellState> = vec![CellState::Dead; n_cells];
+        Field{width, height, cells}
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// The cells row by row: the cell (x, y) is at x + y * width
+    pub fn get_cells(&self) -> &[CellState] {
+        &self.cells
+    }
+
+    pub(crate) fn get_cells_mut(&mut self) -> &mut [CellState] {
+        &mut self.cells
+    }
+
+    pub fn get_cell_neighbour_positions(&self, x: usize, y: usize, consider_extremes_adjacent: bool) -> Vec<CellPosition> {
+        let mut neighbour_positions: Vec<CellPosition> = Vec::new();
+        
+        let mut possible_x_coordinates: Vec<usize> = Vec::new();
+        let mut possible_y_coordinates: Vec<usize> = Vec::new();
+
+        // todo: logic could be reused somehow
+        // obs: this is obviously not close to the most efficient way of checking neighbours
+        if x > 0 {
+            possible_x_coordinates.push(x - 1);
+        }
+        else if consider_extremes_adjacent { // x == 0
+            possible_x_coordinates.push(self.get_width() - 1);
+        }
+        possible_x_coordinates.push(x);
+        if x + 1 < self.get_width() {
+            possible_x_coordinates.push(x + 1);
+        }
+        else if x + 1 == self.get_width() && consider_extremes_adjacent {
+            possible_x_coordinates.push(0);
+        }
+
+        if y > 0 {
+            possible_y_coordinates.push(y - 1);
+        }
+        else if consider_extremes_adjacent { // y == 0
+            possible_y_coordinates.push(self.get_height() - 1);
+        }
+        possible_y_coordinates.push(y);
+        if y + 1 < self.get_height() {
+            possible_y_coordinates.push(y + 1);
+        }
+        else if y + 1 == self.get_height() && consider_extremes_adjacent {
+            possible_y_coordinates.push(0);
+        }
+
+        for i in &possible_x_coordinates {
+            for j in &possible_y_coordinates {
+                let is_the_cell_position_itself = *i == x && *j == y;
+                if !is_the_cell_position_itself {
+                    neighbour_positions.push(CellPosition{x: *i, y: *j});
+                }
+            }
+        }
+
+        neighbour_positions
+    }
+
+    /// On a triangular grid, cells alternate between pointing up and pointing down along both axes.
+    /// For the pattern to be consistent when the extremes are adjacent, width and height should be even.
+    pub fn is_triangle_pointing_up(x: usize, y: usize) -> bool {
+        (x + y) % 2 == 0
+    }
+
+    pub fn get_triangular_cell_neighbour_positions(&self, x: usize, y: usize, neighbourhood: TriangularNeighbourhood, consider_extremes_adjacent: bool) -> Vec<CellPosition> {
+        let offsets: &[(isize, isize)] = match neighbourhood {
+            TriangularNeighbourhood::Edge => &UP_TRIANGLE_EDGE_NEIGHBOUR_OFFSETS,
+            TriangularNeighbourhood::Vertex => &UP_TRIANGLE_VERTEX_NEIGHBOUR_OFFSETS,
+        };
+        let vertical_direction: isize = if Field::is_triangle_pointing_up(x, y) {1} else {-1};
+        offsets.iter()
+            .filter_map(|(dx, dy)| self.get_offset_position(x, y, *dx, dy * vertical_direction, consider_extremes_adjacent))
+            .collect()
+    }
+
+    pub fn get_offset_position(&self, x: usize, y: usize, dx: isize, dy: isize, consider_extremes_adjacent: bool) -> Option<CellPosition> {
+        get_offset_position(self.width, self.height, x, y, dx, dy, consider_extremes_adjacent)
+    }
+
+    pub fn is_within_boundaries(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get_cell_state(&self, x: usize, y: usize) -> CellState {
+        if self.is_within_boundaries(x, y) {
+            return self.cells[x + y * self.width]
+        }
+        CellState::Unknown
+    }
+
+    pub fn set_cell_state(&mut self, x: usize, y: usize, cell_state: CellState) {
+        if self.is_within_boundaries(x, y) {
+            self.cells[x + y * self.width] = cell_state
+        }
+    }
+
+    /// Like `get_cell_state`, but with an error rather than `CellState::Unknown` outside of the field
+    pub fn try_get_cell_state(&self, x: usize, y: usize) -> Result<CellState, FieldError> {
+        self.check_boundaries(x, y)?;
+        Ok(self.cells[x + y * self.width])
+    }
+
+    /// Like `set_cell_state`, but with an error rather than nothing happening outside of the field
+    pub fn try_set_cell_state(&mut self, x: usize, y: usize, cell_state: CellState) -> Result<(), FieldError> {
+        self.check_boundaries(x, y)?;
+        self.cells[x + y * self.width] = cell_state;
+        Ok(())
+    }
+
+    fn check_boundaries(&self, x: usize, y: usize) -> Result<(), FieldError> {
+        if self.is_within_boundaries(x, y) {
+            return Ok(());
+        }
+        Err(FieldError::OutOfBounds{x, y, width: self.width, height: self.height})
+    }
+
+    /// Whether the cells match the dimensions, which a damaged save may not
+    pub(crate) fn check_cell_count(&self) -> Result<(), FieldError> {
+        if self.cells.len() == self.width * self.height {
+            return Ok(());
+        }
+        Err(FieldError::CellCountMismatch{n_cells: self.cells.len(), expected_n_cells: self.width * self.height})
+    }
+
+    /// Positions of the living cells, row by row
+    pub fn get_living_cells(&self) -> impl Iterator<Item = CellPosition> + '_ {
+        self.cells.iter().enumerate()
+            .filter(|(_, cell_state)| **cell_state == CellState::Alive)
+            .map(|(index, _)| CellPosition{x: index % self.width, y: index / self.width})
+    }
+
+    pub fn get_population(&self) -> usize {
+        self.cells.iter().filter(|cell_state| **cell_state == CellState::Alive).count()
+    }
+
+    pub fn get_number_of_neighbours_alive(&self, x: usize, y: usize, consider_extremes_adjacent: bool) -> usize {
+        self.count_alive(self.get_cell_neighbour_positions(x, y, consider_extremes_adjacent))
+    }
+
+    pub fn get_number_of_triangular_neighbours_alive(&self, x: usize, y: usize, neighbourhood: TriangularNeighbourhood, consider_extremes_adjacent: bool) -> usize {
+        self.count_alive(self.get_triangular_cell_neighbour_positions(x, y, neighbourhood, consider_extremes_adjacent))
+    }
+
+    fn count_alive(&self, cell_positions: Vec<CellPosition>) -> usize {
+        let mut number_of_alive: usize = 0;
+        for cell_position in cell_positions {
+            if self.get_cell_state(cell_position.x, cell_position.y) == CellState::Alive {
+                number_of_alive += 1
+            }
+        }
+        number_of_alive
+    }
+}
+
+/// Position at (x + dx, y + dy) on a width x height board, wrapping around if the extremes are adjacent;
+/// None if it falls outside of the board otherwise.
+pub fn get_offset_position(width: usize, height: usize, x: usize, y: usize, dx: isize, dy: isize, consider_extremes_adjacent: bool) -> Option<CellPosition> {
+    let width = width as isize;
+    let height = height as isize;
+    let mut new_x = x as isize + dx;
+    let mut new_y = y as isize + dy;
+    if consider_extremes_adjacent {
+        new_x = new_x.rem_euclid(width);
+        new_y = new_y.rem_euclid(height);
+    }
+    else if new_x < 0 || new_x >= width || new_y < 0 || new_y >= height {
+        return None;
+    }
+    Some(CellPosition{x: new_x as usize, y: new_y as usize})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangles_have_3_edge_and_12_vertex_neighbours() {
+        let field = Field::new(8, 8);
+        for (neighbourhood, n_neighbours) in [(TriangularNeighbourhood::Edge, 3), (TriangularNeighbourhood::Vertex, 12)] {
+            // (3, 3) points up and (4, 3) points down; on a torus every cell has all its neighbours
+            for (x, y, consider_extremes_adjacent) in [(3, 3, false), (4, 3, false), (0, 0, true), (7, 0, true)] {
+                let mut positions: Vec<(usize, usize)> = field.get_triangular_cell_neighbour_positions(x, y, neighbourhood, consider_extremes_adjacent).iter()
+                    .map(|position| (position.x, position.y))
+                    .collect();
+                positions.sort_unstable();
+                positions.dedup();
+                assert_eq!(positions.len(), n_neighbours, "({x}, {y})");
+                assert!(!positions.contains(&(x, y)));
+                // neighbours are mutual
+                for (neighbour_x, neighbour_y) in positions {
+                    assert!(field.get_triangular_cell_neighbour_positions(neighbour_x, neighbour_y, neighbourhood, consider_extremes_adjacent).iter()
+                        .any(|position| (position.x, position.y) == (x, y)));
+                }
+            }
+        }
+
+        assert!(Field::is_triangle_pointing_up(3, 3) && !Field::is_triangle_pointing_up(4, 3));
+        let below = field.get_triangular_cell_neighbour_positions(3, 3, TriangularNeighbourhood::Edge, false);
+        assert!(below.iter().any(|position| (position.x, position.y) == (3, 4)));
+        let above = field.get_triangular_cell_neighbour_positions(4, 3, TriangularNeighbourhood::Edge, false);
+        assert!(above.iter().any(|position| (position.x, position.y) == (4, 2)));
+        // on a bounded board, corners lose their neighbours beyond the edges
+        assert_eq!(field.get_triangular_cell_neighbour_positions(0, 0, TriangularNeighbourhood::Vertex, false).len(), 5);
+    }
+}
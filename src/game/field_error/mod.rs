@@ -0,0 +1,95 @@
+/// Why an access to the cells of a field or a board was refused
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+pub enum FieldError {
+    /// The cell (x, y) is outside of a field of width x height cells
+    OutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize
+    },
+    /// The cell (x, y, z) is outside of a universe of width x height x depth cells
+    VoxelOutOfBounds {
+        x: usize,
+        y: usize,
+        z: usize,
+        width: usize,
+        height: usize,
+        depth: usize
+    },
+    /// A field holds n_cells cells where its dimensions call for expected_n_cells (e.g. in a damaged save)
+    CellCountMismatch {
+        n_cells: usize,
+        expected_n_cells: usize
+    },
+    /// A field of width x height cells was given where one of expected_width x expected_height cells was needed
+    DimensionMismatch {
+        width: usize,
+        height: usize,
+        expected_width: usize,
+        expected_height: usize
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldError::OutOfBounds{x, y, width, height} =>
+                write!(formatter, "Cell ({x}, {y}) is outside of the {width}x{height} field"),
+            FieldError::VoxelOutOfBounds{x, y, z, width, height, depth} =>
+                write!(formatter, "Cell ({x}, {y}, {z}) is outside of the {width}x{height}x{depth} universe"),
+            FieldError::CellCountMismatch{n_cells, expected_n_cells} =>
+                write!(formatter, "The field holds {n_cells} cells but its dimensions call for {expected_n_cells}"),
+            FieldError::DimensionMismatch{width, height, expected_width, expected_height} =>
+                write!(formatter, "The field is {width}x{height} but the board is {expected_width}x{expected_height}"),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Game;
+    use super::super::continuous_field::ContinuousField;
+    use super::super::field::{CellState, Field};
+    use super::super::multi_state_field::MultiStateField;
+    use super::super::three_dimensional::VoxelField;
+
+    #[test]
+    fn cells_outside_of_the_fields_are_refused() {
+        let mut field = Field::new(3, 2);
+        assert_eq!(field.try_set_cell_state(2, 1, CellState::Alive), Ok(()));
+        assert_eq!(field.try_get_cell_state(2, 1), Ok(CellState::Alive));
+        assert_eq!(field.try_get_cell_state(3, 1), Err(FieldError::OutOfBounds{x: 3, y: 1, width: 3, height: 2}));
+        assert!(field.try_set_cell_state(0, 2, CellState::Alive).is_err());
+
+        let mut multi_state_field = MultiStateField::new(2, 2);
+        assert_eq!(multi_state_field.try_set_cell_state(1, 1, 3), Ok(()));
+        assert_eq!(multi_state_field.try_get_cell_state(1, 1), Ok(3));
+        assert!(multi_state_field.try_get_cell_state(2, 0).is_err());
+
+        let mut continuous_field = ContinuousField::new(2, 2);
+        assert_eq!(continuous_field.try_set_cell_state(0, 1, 0.5), Ok(()));
+        assert_eq!(continuous_field.try_get_cell_state(0, 1), Ok(0.5));
+        assert!(continuous_field.try_set_cell_state(0, 5, 0.5).is_err());
+
+        let mut voxel_field = VoxelField::new(2, 2, 2);
+        assert_eq!(voxel_field.try_set_cell_state(1, 0, 1, CellState::Alive), Ok(()));
+        assert_eq!(voxel_field.try_get_cell_state(1, 0, 1), Ok(CellState::Alive));
+        assert_eq!(voxel_field.try_get_cell_state(0, 0, 2),
+            Err(FieldError::VoxelOutOfBounds{x: 0, y: 0, z: 2, width: 2, height: 2, depth: 2}));
+    }
+
+    #[test]
+    fn fields_must_match_the_board() {
+        let mut game = Game::new(4, 3);
+        assert_eq!(game.set_field(Field::new(3, 4)).err(),
+            Some(FieldError::DimensionMismatch{width: 3, height: 4, expected_width: 4, expected_height: 3}));
+        assert!(game.set_field(Field::new(4, 3)).is_ok());
+        assert_eq!(FieldError::OutOfBounds{x: 5, y: 0, width: 4, height: 3}.to_string(), "Cell (5, 0) is outside of the 4x3 field");
+    }
+}
@@ -0,0 +1,143 @@
+use super::field::{Field, CellState};
+
+/// Fredkin and Toffoli's billiard ball machine
+pub const BILLIARD_BALL_MACHINE: &str = "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15";
+/// Margolus's reversible "critters"
+pub const CRITTERS: &str = "M15,14,13,3,11,5,6,1,7,9,10,2,12,4,8,0";
+pub const TRON: &str = "M15,1,2,3,4,5,6,7,8,9,10,11,12,13,14,0";
+/// Grains fall into empty cells below them, or slide diagonally off other grains
+pub const SAND: &str = "M0,4,8,12,4,12,12,13,8,12,12,14,12,13,14,15";
+
+/// A block cellular automaton on the Margolus neighbourhood: the board is partitioned into 2x2 blocks, and each block is
+/// replaced as a whole according to a 16-entry table. The partition is shifted by one cell diagonally every step.
+///
+/// A block's state adds up 1 for its upper-left, 2 for its upper-right, 4 for its lower-left and 8 for its lower-right
+/// living cell, as in Golly's `MargolusRule` strings such as "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15".
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct MargolusRule {
+    new_block_states: [u8; 16]
+}
+
+impl MargolusRule {
+    /// Parses the 16 new block states, either comma-separated and optionally prefixed with 'M' ("M0,8,4,...") or in
+    /// Golly and MCell's notation, semicolon-separated after "MS,D" ("MS,D0;8;4;...")
+    pub fn parse(rule_string: &str) -> Result<MargolusRule, String> {
+        let rule_string = rule_string.trim();
+        let (rule_string, separator) = match rule_string.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("MS,D") => (&rule_string[4..], ';'),
+            _ => (rule_string.strip_prefix(['M', 'm']).unwrap_or(rule_string), ',')
+        };
+        let mut new_block_states: Vec<u8> = Vec::new();
+        for block_state in rule_string.split(separator) {
+            let block_state = block_state.trim();
+            let new_block_state = block_state.parse::<u8>().ok()
+                .filter(|new_block_state| *new_block_state < 16)
+                .ok_or(String::from("\"") + block_state + "\" is not a block state between 0 and 15")?;
+            new_block_states.push(new_block_state);
+        }
+        let new_block_states: [u8; 16] = new_block_states.try_into()
+            .map_err(|new_block_states: Vec<u8>| String::from("Expected 16 block states, got ") + &new_block_states.len().to_string())?;
+        Ok(MargolusRule{new_block_states})
+    }
+
+    pub fn to_rule_string(&self) -> String {
+        let new_block_states: Vec<String> = self.new_block_states.iter().map(|block_state| block_state.to_string()).collect();
+        String::from("M") + &new_block_states.join(",")
+    }
+
+    /// Reversible rules (such as the billiard ball machine and critters) map the 16 block states onto themselves one to one
+    pub fn is_reversible(&self) -> bool {
+        let mut seen = [false; 16];
+        for new_block_state in self.new_block_states {
+            seen[new_block_state as usize] = true;
+        }
+        seen.iter().all(|was_seen| *was_seen)
+    }
+
+    /// Upper-left corners of the blocks along an axis of the given length. When the extremes are adjacent (and the length
+    /// is even), the last block of the shifted partition wraps around; otherwise cells not covered by a whole block stay as they are.
+    pub fn get_block_starts(length: usize, offset: usize, consider_extremes_adjacent: bool) -> Vec<usize> {
+        let can_wrap = consider_extremes_adjacent && length % 2 == 0;
+        (offset..length)
+            .step_by(2)
+            .filter(|start| can_wrap || start + 1 < length)
+            .collect()
+    }
+
+    /// Replaces the blocks whose upper-left corners are at (offset + 2i, offset + 2j); the offset alternates between 0 and 1
+    pub fn advance_generation(&self, field: &Field, offset: usize, consider_extremes_adjacent: bool) -> Field {
+        let width = field.get_width();
+        let height = field.get_height();
+        let mut next_field = field.clone();
+        for block_x in MargolusRule::get_block_starts(width, offset, consider_extremes_adjacent) {
+            for block_y in MargolusRule::get_block_starts(height, offset, consider_extremes_adjacent) {
+                let positions = [
+                    (block_x, block_y),
+                    ((block_x + 1) % width, block_y),
+                    (block_x, (block_y + 1) % height),
+                    ((block_x + 1) % width, (block_y + 1) % height)
+                ];
+                let mut block_state: u8 = 0;
+                for (bit, (x, y)) in positions.iter().enumerate() {
+                    if field.get_cell_state(*x, *y) == CellState::Alive {
+                        block_state |= 1 << bit;
+                    }
+                }
+                let new_block_state = self.new_block_states[block_state as usize];
+                for (bit, (x, y)) in positions.iter().enumerate() {
+                    let is_alive = new_block_state & (1 << bit) != 0;
+                    next_field.set_cell_state(*x, *y, if is_alive {CellState::Alive} else {CellState::Dead});
+                }
+            }
+        }
+        next_field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_notations() {
+        let billiard_ball_machine = MargolusRule::parse(BILLIARD_BALL_MACHINE).unwrap();
+        assert_eq!(MargolusRule::parse("MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15"), Ok(billiard_ball_machine.clone()));
+        assert_eq!(MargolusRule::parse(" ms,d0; 8;4;3;2;5;9;7;1;6;10;11;12;13;14;15 "), Ok(billiard_ball_machine.clone()));
+        assert_eq!(MargolusRule::parse("0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15"), Ok(billiard_ball_machine.clone()));
+        assert_eq!(billiard_ball_machine.to_rule_string(), BILLIARD_BALL_MACHINE);
+        for rule_string in [CRITTERS, TRON, SAND] {
+            assert_eq!(MargolusRule::parse(rule_string).unwrap().to_rule_string(), rule_string);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(MargolusRule::parse("M0,1,2").is_err());
+        assert!(MargolusRule::parse("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,16").is_err());
+        assert!(MargolusRule::parse("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15,0").is_err());
+        assert!(MargolusRule::parse("MS,D0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15").is_err());
+        assert!(MargolusRule::parse("MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;x").is_err());
+        assert!(MargolusRule::parse("").is_err());
+    }
+
+    #[test]
+    fn reversibility() {
+        assert!(MargolusRule::parse(BILLIARD_BALL_MACHINE).unwrap().is_reversible());
+        assert!(MargolusRule::parse(CRITTERS).unwrap().is_reversible());
+        assert!(!MargolusRule::parse(SAND).unwrap().is_reversible());
+    }
+
+    #[test]
+    fn moves_a_grain_of_sand_down() {
+        let sand = MargolusRule::parse(SAND).unwrap();
+        let mut field = Field::new(2, 2);
+        field.set_cell_state(0, 0, CellState::Alive);
+        let next_field = sand.advance_generation(&field, 0, false);
+        assert_eq!(next_field.get_cell_state(0, 0), CellState::Dead);
+        assert_eq!(next_field.get_cell_state(0, 1), CellState::Alive);
+    }
+}
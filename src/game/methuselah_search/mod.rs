@@ -0,0 +1,325 @@
+use std::collections::HashSet;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use super::field::{CellState, Field};
+use super::rule::Rule;
+use super::soup_search::{ObjectKind, SparsePattern, classify_object, Stabilisation, StabilisationRun};
+
+/// Number of best patterns kept
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// Patterns taking part in each tournament when picking parents
+const TOURNAMENT_SIZE: usize = 3;
+
+/// Best patterns copied unchanged into the next generation
+const N_ELITES: usize = 2;
+
+/// What makes a pattern a good methuselah
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum MethuselahFitness {
+    /// Generations until the pattern becomes periodic
+    #[default]
+    Lifespan,
+    /// Living cells once stable
+    FinalPopulation,
+    /// Spaceships (such as gliders) flying away once stable
+    EscapingSpaceships,
+    /// Lifespan + final population + 100 per escaping spaceship
+    Combined
+}
+
+pub const METHUSELAH_FITNESSES: [(MethuselahFitness, &str); 4] = [
+    (MethuselahFitness::Lifespan, "Lifespan"),
+    (MethuselahFitness::FinalPopulation, "Final population"),
+    (MethuselahFitness::EscapingSpaceships, "Escaping spaceships"),
+    (MethuselahFitness::Combined, "Combined")
+];
+
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct MethuselahSearchSettings {
+    /// Patterns in each generation of the genetic algorithm
+    pub population_size: usize,
+    /// Probability of each cell of a child being flipped
+    pub mutation_rate: f64,
+    /// Side of the square box the seed patterns live in
+    pub box_size: usize,
+    pub fitness: MethuselahFitness,
+    /// Patterns still unstable after this many generations are disqualified (they may grow forever)
+    pub max_generations: usize,
+    pub seed: u64
+}
+
+impl Default for MethuselahSearchSettings {
+    fn default() -> Self {
+        MethuselahSearchSettings {
+            population_size: 30,
+            mutation_rate: 0.05,
+            box_size: 5,
+            fitness: MethuselahFitness::Lifespan,
+            max_generations: 5000,
+            seed: 0
+        }
+    }
+}
+
+/// How a seed pattern fared
+#[derive(Clone)]
+pub struct MethuselahScore {
+    pub lifespan: usize,
+    pub final_population: usize,
+    pub escaping_spaceships: usize,
+    pub has_stabilised: bool
+}
+
+impl MethuselahScore {
+    fn get_outcome(&self) -> (usize, usize, usize) {
+        (self.lifespan, self.final_population, self.escaping_spaceships)
+    }
+
+    pub fn get_fitness(&self, fitness: MethuselahFitness) -> f64 {
+        if !self.has_stabilised {
+            return 0.0;
+        }
+        match fitness {
+            MethuselahFitness::Lifespan => self.lifespan as f64,
+            MethuselahFitness::FinalPopulation => self.final_population as f64,
+            MethuselahFitness::EscapingSpaceships => self.escaping_spaceships as f64,
+            MethuselahFitness::Combined => (self.lifespan + self.final_population + 100 * self.escaping_spaceships) as f64,
+        }
+    }
+}
+
+/// Scores a pattern run on an unbounded plane until it stabilised, counting the spaceships among the objects left
+fn get_methuselah_score(stabilisation: Stabilisation, rule: &Rule) -> MethuselahScore {
+    let escaping_spaceships = if stabilisation.has_stabilised {
+        stabilisation.pattern.split_into_objects(rule).iter()
+            .filter(|object| matches!(classify_object(object, rule).0, ObjectKind::Spaceship{..}))
+            .count()
+    }
+    else {
+        0
+    };
+    MethuselahScore {
+        lifespan: stabilisation.generations,
+        final_population: stabilisation.pattern.get_population(),
+        escaping_spaceships,
+        has_stabilised: stabilisation.has_stabilised
+    }
+}
+
+#[derive(Clone)]
+pub struct MethuselahCandidate {
+    /// Cells of the box, row by row
+    cells: Vec<bool>,
+    pub score: MethuselahScore,
+    pub fitness: f64
+}
+
+impl MethuselahCandidate {
+    pub fn get_pattern(&self, box_size: usize) -> Field {
+        let mut pattern = Field::new(box_size, box_size);
+        for (index, is_alive) in self.cells.iter().enumerate() {
+            if *is_alive {
+                pattern.set_cell_state(index % box_size, index / box_size, CellState::Alive);
+            }
+        }
+        pattern
+    }
+}
+
+/// Genetic algorithm evolving seed patterns within a box: each generation is scored, then the next one is bred from
+/// parents picked by tournament, with uniform crossover and random cell flips, keeping the best patterns unchanged.
+/// Patterns are run a few generations at a time by `step`, so the search can run a bit every frame; the best patterns
+/// carried over keep their scores instead of being run again.
+pub struct MethuselahSearch {
+    settings: MethuselahSearchSettings,
+    rule: Rule,
+    rng: ChaCha8Rng,
+    /// Patterns of the current generation
+    population: Vec<Vec<bool>>,
+    /// Scored patterns of the current generation, in the same order
+    evaluated: Vec<MethuselahCandidate>,
+    /// Run of the next pattern to score, once started
+    current_run: Option<StabilisationRun>,
+    generation: usize,
+    leaderboard: Vec<MethuselahCandidate>,
+    /// Outcomes (lifespan, final population, escaping spaceships) of the leaderboard patterns: patterns with the same outcome
+    /// are nearly always the same methuselah, shifted or with a few extra cells that die at once, so they only count once
+    leaderboard_outcomes: HashSet<(usize, usize, usize)>
+}
+
+impl MethuselahSearch {
+    pub fn new(settings: MethuselahSearchSettings, rule: Rule) -> Result<MethuselahSearch, String> {
+        if rule.get_next_cell_state(CellState::Dead, 0) == CellState::Alive {
+            return Err(String::from("Rules with B0 can't be run on an unbounded plane"));
+        }
+        if settings.population_size < 2 || settings.box_size == 0 {
+            return Err(String::from("The population needs at least 2 patterns, in a box of at least 1 cell"));
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(settings.seed);
+        let n_cells = settings.box_size * settings.box_size;
+        let population: Vec<Vec<bool>> = (0..settings.population_size)
+            .map(|_| (0..n_cells).map(|_| rng.gen_bool(0.5)).collect())
+            .collect();
+        Ok(MethuselahSearch {
+            settings,
+            rule,
+            rng,
+            population,
+            evaluated: Vec::new(),
+            current_run: None,
+            generation: 0,
+            leaderboard: Vec::new(),
+            leaderboard_outcomes: HashSet::new()
+        })
+    }
+
+    pub fn get_settings(&self) -> &MethuselahSearchSettings {
+        &self.settings
+    }
+
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Generations of the genetic algorithm completed so far
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Best patterns found, best first
+    pub fn get_leaderboard(&self) -> &[MethuselahCandidate] {
+        &self.leaderboard
+    }
+
+    /// Runs the patterns of the generation for `n_generations` in all, scoring each one once it stabilises (or is given up on),
+    /// and breeding the next generation once they all are scored
+    pub fn step(&mut self, n_generations: usize) {
+        let mut n_generations_left = n_generations;
+        while n_generations_left > 0 {
+            let index = self.evaluated.len();
+            let box_size = self.settings.box_size;
+            let max_generations = self.settings.max_generations;
+            let cells = &self.population[index];
+            let run = self.current_run.get_or_insert_with(|| {
+                let pattern = SparsePattern::new((0..cells.len()).filter(|index| cells[*index]).map(|index| ((index % box_size) as i64, (index / box_size) as i64)));
+                StabilisationRun::new(pattern, max_generations)
+            });
+            let first_generation = run.get_generation();
+            run.advance(&self.rule, n_generations_left);
+            // scoring a pattern counts as a generation, so that every step makes progress
+            n_generations_left = n_generations_left.saturating_sub((run.get_generation() - first_generation).max(1));
+            if !run.is_finished() {
+                continue;
+            }
+
+            let Some(run) = self.current_run.take() else {
+                break;
+            };
+            let score = get_methuselah_score(run.into_stabilisation(), &self.rule);
+            let fitness = score.get_fitness(self.settings.fitness);
+            let candidate = MethuselahCandidate{cells: self.population[index].clone(), score, fitness};
+            self.add_to_leaderboard(&candidate);
+            self.evaluated.push(candidate);
+            if self.evaluated.len() == self.population.len() {
+                self.breed_next_generation();
+            }
+        }
+    }
+
+    fn add_to_leaderboard(&mut self, candidate: &MethuselahCandidate) {
+        if candidate.fitness <= 0.0 {
+            return;
+        }
+        let is_good_enough = self.leaderboard.len() < LEADERBOARD_SIZE
+            || self.leaderboard.last().is_some_and(|worst| candidate.fitness > worst.fitness);
+        if !is_good_enough {
+            return;
+        }
+        if !self.leaderboard_outcomes.insert(candidate.score.get_outcome()) {
+            return;
+        }
+        self.leaderboard.push(candidate.clone());
+        self.leaderboard.sort_by(|first, second| second.fitness.total_cmp(&first.fitness));
+        if self.leaderboard.len() > LEADERBOARD_SIZE {
+            if let Some(removed) = self.leaderboard.pop() {
+                self.leaderboard_outcomes.remove(&removed.score.get_outcome());
+            }
+        }
+    }
+
+    fn breed_next_generation(&mut self) {
+        let mut ranked: Vec<&MethuselahCandidate> = self.evaluated.iter().collect();
+        ranked.sort_by(|first, second| second.fitness.total_cmp(&first.fitness));
+        // at least one new pattern is bred, so that there is something to run in the next generation
+        let n_elites = N_ELITES.min(self.settings.population_size - 1);
+        let elites: Vec<MethuselahCandidate> = ranked.iter().take(n_elites).map(|candidate| (*candidate).clone()).collect();
+        let mut next_population: Vec<Vec<bool>> = elites.iter().map(|candidate| candidate.cells.clone()).collect();
+        while next_population.len() < self.settings.population_size {
+            let first_parent = self.pick_by_tournament();
+            let second_parent = self.pick_by_tournament();
+            let child: Vec<bool> = first_parent.iter().zip(second_parent.iter())
+                .map(|(first_cell, second_cell)| {
+                    let cell = if self.rng.gen_bool(0.5) {*first_cell} else {*second_cell};
+                    cell != self.rng.gen_bool(self.settings.mutation_rate.clamp(0.0, 1.0))
+                })
+                .collect();
+            next_population.push(child);
+        }
+        self.population = next_population;
+        // the elites come first in the next generation, already scored
+        self.evaluated = elites;
+        self.generation += 1;
+    }
+
+    fn pick_by_tournament(&mut self) -> Vec<bool> {
+        let mut best: Option<&MethuselahCandidate> = None;
+        for _ in 0..TOURNAMENT_SIZE {
+            let contender = &self.evaluated[self.rng.gen_range(0..self.evaluated.len())];
+            if best.map_or(true, |best| contender.fitness > best.fitness) {
+                best = Some(contender);
+            }
+        }
+        best.map(|candidate| candidate.cells.clone()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_search() -> MethuselahSearch {
+        let settings = MethuselahSearchSettings{population_size: 6, box_size: 4, max_generations: 2000, ..MethuselahSearchSettings::default()};
+        MethuselahSearch::new(settings, Rule::conway()).unwrap()
+    }
+
+    #[test]
+    fn runs_a_few_generations_at_a_time() {
+        let mut methuselah_search = get_search();
+        methuselah_search.step(10);
+        let n_generations_run = methuselah_search.current_run.as_ref().map_or(0, |run| run.get_generation());
+        assert!(methuselah_search.evaluated.is_empty());
+        assert!(n_generations_run <= 10);
+    }
+
+    #[test]
+    fn carries_the_elites_over_with_their_scores() {
+        let mut methuselah_search = get_search();
+        while methuselah_search.get_generation() == 0 {
+            methuselah_search.step(100);
+        }
+        let best = methuselah_search.get_leaderboard()[0].clone();
+        assert!(methuselah_search.evaluated.len() >= N_ELITES);
+        assert_eq!(methuselah_search.evaluated[0].cells, best.cells);
+        assert_eq!(methuselah_search.evaluated[0].fitness, best.fitness);
+        assert_eq!(methuselah_search.population[0], best.cells);
+    }
+}
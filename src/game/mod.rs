@@ -0,0 +1,740 @@
+pub mod activity;
+pub mod cell_age;
+pub mod continuous_field;
+pub mod continuous_rule;
+pub mod field;
+pub mod field_error;
+pub mod margolus;
+// The searches are internal to the app, so without it they go unused
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) mod methuselah_search;
+pub mod multi_state_field;
+pub mod one_dimensional;
+pub(crate) mod parallel_step;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) mod predecessor;
+pub mod rle;
+pub mod rule;
+pub mod rule_table;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) mod sat;
+pub mod save_format;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) mod ship_search;
+pub mod soup;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) mod soup_search;
+pub mod three_dimensional;
+pub mod turmite;
+pub mod update_mode;
+pub use parallel_step::get_available_threads;
+use activity::ActivityMap;
+use cell_age::CellAges;
+use continuous_field::ContinuousField;
+use continuous_rule::{ContinuousEngine, ContinuousRule};
+use field::{Field, CellState, TriangularNeighbourhood};
+use field_error::FieldError;
+use margolus::MargolusRule;
+use multi_state_field::MultiStateField;
+use one_dimensional::{OneDimensionalAutomaton, OneDimensionalRule, OneDimensionalSeed};
+use rule::Rule;
+use rule_table::RuleTable;
+use soup::{RandomSoup, SoupSettings, get_probability, get_soup_rng};
+use three_dimensional::{VoxelField, VoxelNeighbourhood};
+use turmite::{Ant, Turmite};
+use update_mode::{UpdateMode, UpdateRng, UpdateSettings};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/// The tiling the cells of the field are laid out on.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum GridType {
+    #[default]
+    Square,
+    Triangular,
+    /// A single row of cells; the field shows successive generations top to bottom
+    OneDimensional,
+    /// Layers of square cells stacked along the Z axis; the game runs `rule` on `voxel_field` instead of `field`
+    ThreeDimensional
+}
+
+/// How ants and the cell rule share the generations when there are ants on the board.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum AntPhases {
+    /// Only the ants move; the cell rule is not applied
+    #[default]
+    AntsOnly,
+    /// Even generations move the ants, odd generations apply the cell rule
+    AlternatingWithRule
+}
+
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(Default)]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct Game {
+    field: Field,
+    generation: usize,
+    grid_type: GridType,
+    triangular_neighbourhood: TriangularNeighbourhood,
+    rule: Rule,
+    /// When set, the game runs this multi-state rule on `multi_state_field` instead of `rule` on `field`
+    rule_table: Option<RuleTable>,
+    multi_state_field: MultiStateField,
+    /// Ants (square grids only) read and write cell colours: dead/alive on `field`, or the states of `multi_state_field` with a rule table
+    ants: Vec<Ant>,
+    turmite: Turmite,
+    ant_phases: AntPhases,
+    one_dimensional: OneDimensionalAutomaton,
+    /// When set (square grids only), the game runs this block rule on `field` instead of `rule`
+    margolus_rule: Option<MargolusRule>,
+    /// Offset (0 or 1) of the 2x2 blocks the next Margolus step will use
+    margolus_block_offset: usize,
+    /// When set (square grids only), the game runs this continuous rule on `continuous_field` instead of `rule` on `field`
+    continuous_engine: Option<ContinuousEngine>,
+    continuous_field: ContinuousField,
+    voxel_field: VoxelField,
+    voxel_neighbourhood: VoxelNeighbourhood,
+    /// Synchronous or stochastic updates of B/S rules on `field`
+    update_settings: UpdateSettings,
+    update_rng: UpdateRng,
+    /// Soup the cells were last randomized with, None once they're cleared
+    soup: Option<RandomSoup>,
+    /// Ages of the cells of `field`, saved so that the board is shaded the same once loaded
+    cell_ages: CellAges,
+    /// Activity of the cells of `field`, only reset on demand
+    activity_map: ActivityMap,
+    /// Threads synchronous B/S generations of `field` are computed on, 0 for one per core (always one on the web);
+    /// not saved, since it depends on the machine rather than the board
+    #[serde(skip)]
+    n_threads: usize
+}
+
+impl Game {
+
+    pub fn new(width: usize, height: usize) -> Game {
+        Game::new_with_grid_type(width, height, GridType::Square)
+    }
+
+    /// A three-dimensional game made this way is as deep as it is wide; see `new_three_dimensional` to choose its depth
+    pub fn new_with_grid_type(width: usize, height: usize, grid_type: GridType) -> Game {
+        let depth = if grid_type == GridType::ThreeDimensional {width} else {0};
+        let mut game = Game{
+            field: Field::new(width, height),
+            generation: 0,
+            grid_type,
+            triangular_neighbourhood: TriangularNeighbourhood::default(),
+            rule: Rule::default(),
+            rule_table: None,
+            multi_state_field: MultiStateField::new(width, height),
+            ants: Vec::new(),
+            turmite: Turmite::default(),
+            ant_phases: AntPhases::default(),
+            one_dimensional: OneDimensionalAutomaton::new(width),
+            margolus_rule: None,
+            margolus_block_offset: 0,
+            continuous_engine: None,
+            continuous_field: ContinuousField::new(width, height),
+            voxel_field: VoxelField::new(width, height, depth),
+            voxel_neighbourhood: VoxelNeighbourhood::default(),
+            update_settings: UpdateSettings::default(),
+            update_rng: UpdateRng::default(),
+            soup: None,
+            cell_ages: CellAges::new(width, height),
+            activity_map: ActivityMap::default(),
+            n_threads: 0
+        };
+        if grid_type == GridType::OneDimensional {
+            game.one_dimensional.fill_field(&mut game.multi_state_field);
+        }
+        game
+    }
+
+    pub fn new_three_dimensional(width: usize, height: usize, depth: usize) -> Game {
+        let mut game = Game::new_with_grid_type(width, height, GridType::ThreeDimensional);
+        game.voxel_field = VoxelField::new(width, height, depth);
+        game
+    }
+
+    pub fn get_grid_type(&self) -> GridType {
+        self.grid_type
+    }
+
+    pub fn get_triangular_neighbourhood(&self) -> TriangularNeighbourhood {
+        self.triangular_neighbourhood
+    }
+
+    pub fn set_triangular_neighbourhood(&mut self, triangular_neighbourhood: TriangularNeighbourhood) {
+        self.triangular_neighbourhood = triangular_neighbourhood;
+    }
+
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    pub fn get_rule_table(&self) -> Option<&RuleTable> {
+        self.rule_table.as_ref()
+    }
+
+    /// Switches to running the rule table (square grids only); living cells become cells in state 1
+    pub fn load_rule_table(&mut self, rule_table: RuleTable) {
+        self.margolus_rule = None;
+        self.continuous_engine = None;
+        if self.rule_table.is_none() {
+            let mut multi_state_field = MultiStateField::new(self.field.get_width(), self.field.get_height());
+            for i in 0..self.field.get_width() {
+                for j in 0..self.field.get_height() {
+                    if self.field.get_cell_state(i, j) == CellState::Alive {
+                        multi_state_field.set_cell_state(i, j, 1);
+                    }
+                }
+            }
+            self.multi_state_field = multi_state_field;
+        }
+        else {
+            // states the new rule doesn't have are reset to the background state
+            for cell in self.multi_state_field.get_cells_mut().iter_mut() {
+                if *cell as usize >= rule_table.get_n_states() {
+                    *cell = 0;
+                }
+            }
+        }
+        self.rule_table = Some(rule_table);
+    }
+
+    /// Switches back to the B/S rule; cells in any state other than the background state become alive
+    pub fn unload_rule_table(&mut self) {
+        if self.rule_table.take().is_some() {
+            for i in 0..self.field.get_width() {
+                for j in 0..self.field.get_height() {
+                    let is_alive = self.multi_state_field.get_cell_state(i, j) != 0;
+                    self.field.set_cell_state(i, j, if is_alive {CellState::Alive} else {CellState::Dead});
+                }
+            }
+        }
+    }
+
+    pub fn get_margolus_rule(&self) -> Option<&MargolusRule> {
+        self.margolus_rule.as_ref()
+    }
+
+    /// Switches to running the block rule (square grids only) from the unshifted partition, or back to the B/S rule with None
+    pub fn set_margolus_rule(&mut self, margolus_rule: Option<MargolusRule>) {
+        if margolus_rule.is_some() {
+            self.unload_rule_table();
+            self.continuous_engine = None;
+        }
+        self.margolus_rule = margolus_rule;
+        self.margolus_block_offset = 0;
+    }
+
+    pub fn get_margolus_block_offset(&self) -> usize {
+        self.margolus_block_offset
+    }
+
+    pub fn get_continuous_rule(&self) -> Option<&ContinuousRule> {
+        self.continuous_engine.as_ref().map(|continuous_engine| continuous_engine.get_rule())
+    }
+
+    /// Switches to running the continuous rule (square grids only), or back to the B/S rule with None;
+    /// the continuous field starts out as the living cells of `field` when switching to it
+    pub fn set_continuous_rule(&mut self, continuous_rule: Option<ContinuousRule>) {
+        if continuous_rule.is_some() {
+            self.unload_rule_table();
+            self.margolus_rule = None;
+            if self.continuous_engine.is_none() {
+                let mut continuous_field = ContinuousField::new(self.field.get_width(), self.field.get_height());
+                for (continuous_cell, cell) in continuous_field.get_cells_mut().iter_mut().zip(self.field.get_cells()) {
+                    *continuous_cell = if *cell == CellState::Alive {1.0} else {0.0};
+                }
+                self.continuous_field = continuous_field;
+            }
+        }
+        self.continuous_engine = continuous_rule.map(ContinuousEngine::new);
+    }
+
+    pub fn get_continuous_field(&self) -> &ContinuousField {
+        &self.continuous_field
+    }
+
+    pub fn get_continuous_field_mut(&mut self) -> &mut ContinuousField {
+        &mut self.continuous_field
+    }
+
+    pub fn get_voxel_field(&self) -> &VoxelField {
+        &self.voxel_field
+    }
+
+    pub fn get_voxel_field_mut(&mut self) -> &mut VoxelField {
+        &mut self.voxel_field
+    }
+
+    pub fn get_voxel_neighbourhood(&self) -> VoxelNeighbourhood {
+        self.voxel_neighbourhood
+    }
+
+    pub fn set_voxel_neighbourhood(&mut self, voxel_neighbourhood: VoxelNeighbourhood) {
+        self.voxel_neighbourhood = voxel_neighbourhood;
+    }
+
+    pub fn get_update_settings(&self) -> &UpdateSettings {
+        &self.update_settings
+    }
+
+    pub fn set_update_settings(&mut self, update_settings: UpdateSettings) {
+        self.update_settings = update_settings;
+    }
+
+    pub fn get_update_seed(&self) -> u64 {
+        self.update_rng.get_seed()
+    }
+
+    /// Restarts the random number generator of the stochastic update modes from the seed
+    pub fn set_update_seed(&mut self, seed: u64) {
+        self.update_rng = UpdateRng::new(seed);
+    }
+
+    pub fn get_soup(&self) -> Option<&RandomSoup> {
+        self.soup.as_ref()
+    }
+
+    pub fn get_one_dimensional(&self) -> &OneDimensionalAutomaton {
+        &self.one_dimensional
+    }
+
+    pub fn set_one_dimensional_rule(&mut self, rule: OneDimensionalRule) {
+        self.one_dimensional.set_rule(rule);
+        self.one_dimensional.fill_field(&mut self.multi_state_field);
+    }
+
+    /// Restarts the one-dimensional run from the given first row
+    pub fn seed_one_dimensional(&mut self, seed: OneDimensionalSeed) -> Result<(), String> {
+        let soup = match seed {
+            OneDimensionalSeed::Random(probability_living_cell, seed) => Some(RandomSoup{seed, settings: SoupSettings::uniform(probability_living_cell)}),
+            _ => None,
+        };
+        self.one_dimensional.seed(seed)?;
+        self.soup = soup;
+        self.generation = 0;
+        self.one_dimensional.fill_field(&mut self.multi_state_field);
+        Ok(())
+    }
+
+    /// First generation to show at the top of the field, or None to follow the current generation
+    pub fn set_one_dimensional_view_start(&mut self, view_start: Option<usize>) {
+        self.one_dimensional.set_view_start(view_start);
+        self.one_dimensional.fill_field(&mut self.multi_state_field);
+    }
+
+    /// Only the row of the current generation can be edited; past generations are history
+    pub fn set_one_dimensional_cell_state(&mut self, x: usize, y: usize, state: u8) {
+        let shown_generation = self.one_dimensional.get_shown_first_generation(self.multi_state_field.get_height()) + y;
+        if shown_generation == self.one_dimensional.get_current_generation() {
+            self.one_dimensional.set_current_cell_state(x, state);
+            self.one_dimensional.fill_field(&mut self.multi_state_field);
+        }
+    }
+
+    pub fn get_multi_state_field(&self) -> &MultiStateField {
+        &self.multi_state_field
+    }
+
+    pub fn get_multi_state_field_mut(&mut self) -> &mut MultiStateField {
+        &mut self.multi_state_field
+    }
+
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn get_field(&self) -> &Field {
+        &self.field
+    }
+
+    pub fn get_field_mut(&mut self) -> &mut Field {
+        &mut self.field
+    }
+
+    /// Replaces the cells of `field` with those of a field of the same dimensions
+    pub fn set_field(&mut self, field: Field) -> Result<(), FieldError> {
+        if field.get_width() != self.field.get_width() || field.get_height() != self.field.get_height() {
+            return Err(FieldError::DimensionMismatch{width: field.get_width(), height: field.get_height(),
+                expected_width: self.field.get_width(), expected_height: self.field.get_height()});
+        }
+        self.field = field;
+        self.reset_cell_ages();
+        Ok(())
+    }
+
+    /// Whether the cells of every field match their dimensions, and the fields kept alongside `field` match the board,
+    /// which a damaged save may not. Fields that are empty, as in saves from before they existed, are let through.
+    pub(crate) fn check_dimensions(&self) -> Result<(), FieldError> {
+        self.field.check_cell_count()?;
+        self.multi_state_field.check_cell_count()?;
+        self.continuous_field.check_cell_count()?;
+        self.voxel_field.check_cell_count()?;
+        let (width, height) = (self.field.get_width(), self.field.get_height());
+        let sub_field_dimensions = [
+            (self.multi_state_field.get_width(), self.multi_state_field.get_height(), self.multi_state_field.get_cells().is_empty()),
+            (self.continuous_field.get_width(), self.continuous_field.get_height(), self.continuous_field.get_cells().is_empty()),
+            (self.voxel_field.get_width(), self.voxel_field.get_height(), self.voxel_field.get_cells().is_empty())
+        ];
+        for (sub_field_width, sub_field_height, is_empty) in sub_field_dimensions {
+            if !is_empty && (sub_field_width != width || sub_field_height != height) {
+                return Err(FieldError::DimensionMismatch{width: sub_field_width, height: sub_field_height, expected_width: width, expected_height: height});
+            }
+        }
+        self.cell_ages.check_dimensions(width, height)
+    }
+
+    pub fn get_ants(&self) -> &[Ant] {
+        &self.ants
+    }
+
+    pub fn get_ants_mut(&mut self) -> &mut Vec<Ant> {
+        &mut self.ants
+    }
+
+    pub fn get_turmite(&self) -> &Turmite {
+        &self.turmite
+    }
+
+    pub fn set_turmite(&mut self, turmite: Turmite) {
+        self.turmite = turmite;
+        for ant in self.ants.iter_mut() {
+            ant.state = 0;
+        }
+    }
+
+    pub fn get_ant_phases(&self) -> AntPhases {
+        self.ant_phases
+    }
+
+    pub fn set_ant_phases(&mut self, ant_phases: AntPhases) {
+        self.ant_phases = ant_phases;
+    }
+
+    /// Number of colours the cells can take, as seen by the ants
+    pub fn get_n_cell_colors(&self) -> usize {
+        self.rule_table.as_ref().map_or(2, |rule_table| rule_table.get_n_states())
+    }
+
+    /// Whether the next generation moves the ants (true) or applies the cell rule (false)
+    pub fn is_ant_phase_next(&self) -> bool {
+        if self.ants.is_empty() || self.grid_type != GridType::Square {
+            return false;
+        }
+        match self.ant_phases {
+            AntPhases::AntsOnly => true,
+            AntPhases::AlternatingWithRule => self.generation % 2 == 0,
+        }
+    }
+
+    pub fn advance_generation(&mut self, consider_extremes_adjacent: bool) {
+        self.advance_generations(1, consider_extremes_adjacent);
+    }
+
+    pub fn advance_generations(&mut self, n_generations: usize, consider_extremes_adjacent: bool) {
+        if self.is_stepped_in_bands() {
+            self.advance_in_bands(n_generations, consider_extremes_adjacent);
+            return;
+        }
+        for _ in 0..n_generations {
+            self.advance_one_generation(consider_extremes_adjacent);
+        }
+    }
+
+    /// Whether generations only apply a synchronous B/S rule to `field`, so that many of them can be stepped in parallel at once
+    fn is_stepped_in_bands(&self) -> bool {
+        // ants only move on square grids
+        let has_ants = !self.ants.is_empty() && self.grid_type == GridType::Square;
+        self.is_running_field() && !has_ants && self.margolus_rule.is_none() && !self.update_settings.is_stochastic()
+    }
+
+    /// Steps `field` generation by generation on the threads of `n_threads`, keeping the cell ages and the activity map up to date
+    fn advance_in_bands(&mut self, n_generations: usize, consider_extremes_adjacent: bool) {
+        let (rule, grid_type, triangular_neighbourhood) = (&self.rule, self.grid_type, self.triangular_neighbourhood);
+        let (cell_ages, activity_map, generation) = (&mut self.cell_ages, &mut self.activity_map, &mut self.generation);
+        let mut previous_field = if activity_map.is_enabled() {Some(self.field.clone())} else {None};
+        let field = std::mem::take(&mut self.field);
+        self.field = parallel_step::advance_in_bands(field, n_generations, self.n_threads, |field, x, y| {
+            get_next_cell_state(rule, grid_type, triangular_neighbourhood, field, x, y, consider_extremes_adjacent)
+        }, |field| {
+            *generation += 1;
+            cell_ages.update(field);
+            if let Some(previous_field) = &mut previous_field {
+                activity_map.update(previous_field, field);
+                previous_field.clone_from(field);
+            }
+        });
+    }
+
+    fn advance_one_generation(&mut self, consider_extremes_adjacent: bool) {
+        let is_running_field = self.is_running_field();
+        // the previous field is only kept for the activity map, while it is enabled
+        let previous_field = if is_running_field && self.activity_map.is_enabled() {Some(self.field.clone())} else {None};
+        if self.is_ant_phase_next() {
+            self.advance_ants(consider_extremes_adjacent);
+        }
+        else {
+            self.advance_cells(consider_extremes_adjacent);
+        }
+        self.generation += 1;
+        if is_running_field {
+            self.cell_ages.update(&self.field);
+        }
+        if let Some(previous_field) = previous_field {
+            self.activity_map.update(&previous_field, &self.field);
+        }
+    }
+
+    pub fn get_n_threads(&self) -> usize {
+        self.n_threads
+    }
+
+    /// Threads synchronous B/S generations are computed on, 0 for one per core; the results don't depend on it
+    pub fn set_n_threads(&mut self, n_threads: usize) {
+        self.n_threads = n_threads;
+    }
+
+    pub fn get_cell_ages(&self) -> &CellAges {
+        &self.cell_ages
+    }
+
+    /// Whether the cells shown are those of `field` (square or triangular grids, without a rule table or continuous rule)
+    pub fn is_running_field(&self) -> bool {
+        self.rule_table.is_none() && self.continuous_engine.is_none()
+            && (self.grid_type == GridType::Square || self.grid_type == GridType::Triangular)
+    }
+
+    pub fn get_activity_map(&self) -> &ActivityMap {
+        &self.activity_map
+    }
+
+    /// Number of generations the activity is counted over, 0 for all of them
+    pub fn set_activity_window(&mut self, window: usize) {
+        self.activity_map.set_window(window);
+    }
+
+    /// Whether the activity of the cells is counted, e.g. only while the heat map is shown; counting starts over when turned on
+    pub fn set_activity_tracking(&mut self, is_tracking_activity: bool) {
+        self.activity_map.set_enabled(is_tracking_activity);
+    }
+
+    /// Starts the activity counts over, without touching the cells
+    pub fn reset_activity_map(&mut self) {
+        self.activity_map.reset(self.field.get_width(), self.field.get_height());
+    }
+
+    /// Starts the cell ages over from the current field, whose living cells count as newborn
+    fn reset_cell_ages(&mut self) {
+        self.cell_ages.reset(self.field.get_width(), self.field.get_height());
+        self.cell_ages.update(&self.field);
+    }
+
+    /// Each ant, in turn, recolours the cell it is on, turns and steps forward; on a bounded board, ants stepping off it are removed
+    fn advance_ants(&mut self, consider_extremes_adjacent: bool) {
+        let n_cell_colors = self.get_n_cell_colors();
+        let mut ants = std::mem::take(&mut self.ants);
+        ants.retain_mut(|ant| {
+            let color: u8 = match self.rule_table {
+                Some(_) => self.multi_state_field.get_cell_state(ant.x, ant.y),
+                None => if self.field.get_cell_state(ant.x, ant.y) == CellState::Alive {1} else {0},
+            };
+            let transition = self.turmite.get_transition(ant.state, color);
+            let write_color = (transition.write_color as usize % n_cell_colors) as u8;
+            match self.rule_table {
+                Some(_) => self.multi_state_field.set_cell_state(ant.x, ant.y, write_color),
+                None => self.field.set_cell_state(ant.x, ant.y, if write_color == 0 {CellState::Dead} else {CellState::Alive}),
+            }
+            ant.direction = ant.direction.turned(transition.turn);
+            ant.state = transition.next_state;
+            let (dx, dy) = ant.direction.get_offset();
+            match self.field.get_offset_position(ant.x, ant.y, dx, dy, consider_extremes_adjacent) {
+                Some(position) => {
+                    ant.x = position.x;
+                    ant.y = position.y;
+                    true
+                }
+                None => false,
+            }
+        });
+        self.ants = ants;
+    }
+
+    fn advance_cells(&mut self, consider_extremes_adjacent: bool) {
+        if self.grid_type == GridType::OneDimensional {
+            self.one_dimensional.advance_generation(consider_extremes_adjacent);
+            self.one_dimensional.fill_field(&mut self.multi_state_field);
+            return;
+        }
+
+        if self.grid_type == GridType::ThreeDimensional {
+            self.voxel_field = self.voxel_field.advance_generation(&self.rule, self.voxel_neighbourhood, consider_extremes_adjacent);
+            return;
+        }
+
+        if let Some(rule_table) = &mut self.rule_table {
+            self.multi_state_field = rule_table.advance_generation(&self.multi_state_field, consider_extremes_adjacent);
+            return;
+        }
+
+        if let Some(continuous_engine) = &mut self.continuous_engine {
+            self.continuous_field = continuous_engine.advance_generation(&self.continuous_field, consider_extremes_adjacent);
+            return;
+        }
+
+        if let Some(margolus_rule) = &self.margolus_rule {
+            self.field = margolus_rule.advance_generation(&self.field, self.margolus_block_offset, consider_extremes_adjacent);
+            self.margolus_block_offset = 1 - self.margolus_block_offset;
+            return;
+        }
+
+        if self.update_settings.is_stochastic() {
+            self.advance_cells_stochastically(consider_extremes_adjacent);
+            return;
+        }
+
+        let (rule, grid_type, triangular_neighbourhood) = (&self.rule, self.grid_type, self.triangular_neighbourhood);
+        let field = std::mem::take(&mut self.field);
+        self.field = parallel_step::advance_in_bands(field, 1, self.n_threads, |field, x, y| {
+            get_next_cell_state(rule, grid_type, triangular_neighbourhood, field, x, y, consider_extremes_adjacent)
+        }, |_| ());
+    }
+
+    /// State the B/S rule gives the cell, from the states of its neighbours on `field`
+    fn get_next_cell_state(&self, field: &Field, x: usize, y: usize, consider_extremes_adjacent: bool) -> CellState {
+        get_next_cell_state(&self.rule, self.grid_type, self.triangular_neighbourhood, field, x, y, consider_extremes_adjacent)
+    }
+
+    /// Applies the update mode and the birth/survival probabilities, drawing random numbers from `update_rng`
+    fn advance_cells_stochastically(&mut self, consider_extremes_adjacent: bool) {
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        for j in 0..self.field.get_height() {
+            for i in 0..self.field.get_width() {
+                positions.push((i, j));
+            }
+        }
+        let is_random_order = self.update_settings.mode == UpdateMode::RandomOrder;
+        if is_random_order {
+            positions.shuffle(&mut self.update_rng.rng);
+        }
+
+        let previous_field = self.field.clone();
+        for (i, j) in positions {
+            if self.update_settings.mode == UpdateMode::AlphaAsynchronous && !self.update_rng.rng.gen_bool(self.update_settings.synchrony_rate.clamp(0.0, 1.0)) {
+                continue;
+            }
+            // in random order, each cell sees the cells updated before it
+            let neighbours_field = if is_random_order {&self.field} else {&previous_field};
+            let cell_state = neighbours_field.get_cell_state(i, j);
+            let mut new_cell_state = self.get_next_cell_state(neighbours_field, i, j, consider_extremes_adjacent);
+            if new_cell_state == CellState::Alive {
+                let probability = if cell_state == CellState::Alive {self.update_settings.survival_probability} else {self.update_settings.birth_probability};
+                if probability < 1.0 && !self.update_rng.rng.gen_bool(probability.clamp(0.0, 1.0)) {
+                    new_cell_state = CellState::Dead;
+                }
+            }
+            self.field.set_cell_state(i, j, new_cell_state);
+        }
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.soup = None;
+        if self.grid_type == GridType::OneDimensional {
+            self.seed_one_dimensional(OneDimensionalSeed::Cells(String::new())).ok();
+            return;
+        }
+        for i in 0..self.field.get_width() {
+            for j in 0..self.field.get_height() {
+                    self.field.set_cell_state(i, j, CellState::Dead);
+            }
+        }
+        for cell in self.multi_state_field.get_cells_mut().iter_mut() {
+            *cell = 0;
+        }
+        for cell in self.continuous_field.get_cells_mut().iter_mut() {
+            *cell = 0.0;
+        }
+        for cell in self.voxel_field.get_cells_mut().iter_mut() {
+            *cell = CellState::Dead;
+        }
+        self.reset_cell_ages();
+    }
+
+    /// Fills the cells with a soup, which is the same for the same seed and settings on every platform. The symmetry,
+    /// region and gradient of the settings shape the soup of `field`; other kinds of cells get a uniform soup over the whole board.
+    pub fn randomize(&mut self, soup_settings: &SoupSettings, seed: u64) {
+        let probability_living_cell = soup_settings.probability_living_cell;
+        if self.grid_type == GridType::OneDimensional {
+            self.seed_one_dimensional(OneDimensionalSeed::Random(probability_living_cell, seed)).ok();
+            return;
+        }
+        self.soup = Some(RandomSoup{seed, settings: soup_settings.clone()});
+        self.field = soup_settings.generate_field(self.field.get_width(), self.field.get_height(), seed);
+        let probability = get_probability(probability_living_cell);
+        let mut rng = get_soup_rng(seed);
+        if let Some(rule_table) = &self.rule_table {
+            // living cells take any of the non-background states
+            let n_states = rule_table.get_n_states();
+            for cell in self.multi_state_field.get_cells_mut().iter_mut() {
+                let is_alive = rng.gen_bool(probability);
+                *cell = if is_alive {rng.gen_range(1..n_states as u32) as u8} else {0};
+            }
+        }
+        if self.continuous_engine.is_some() {
+            // living cells take any value
+            for cell in self.continuous_field.get_cells_mut().iter_mut() {
+                let is_alive = rng.gen_bool(probability);
+                *cell = if is_alive {rng.gen::<f32>()} else {0.0};
+            }
+        }
+        for cell in self.voxel_field.get_cells_mut().iter_mut() {
+            let is_alive = rng.gen_bool(probability);
+            *cell = if is_alive {CellState::Alive} else {CellState::Dead};
+        }
+        self.reset_cell_ages();
+    }
+
+    #[allow(dead_code)]
+    pub fn apply_seed_1_to_field(&mut self)
+    {
+        let seed_x: usize = 32;
+        let seed_y: usize = 32;
+
+        let x = seed_x;
+        let y = seed_y;
+        self.field.set_cell_state(x, y, CellState::Alive);
+        self.field.set_cell_state(x - 1, y, CellState::Alive);
+        self.field.set_cell_state(x, y - 1, CellState::Alive);
+        self.field.set_cell_state(x, y + 1, CellState::Alive);
+        self.field.set_cell_state(x + 1, y + 1, CellState::Alive);
+    }
+
+}
+
+/// State the B/S rule gives the cell, from the states of its neighbours on `field`
+fn get_next_cell_state(rule: &Rule, grid_type: GridType, triangular_neighbourhood: TriangularNeighbourhood, field: &Field,
+    x: usize, y: usize, consider_extremes_adjacent: bool) -> CellState {
+    let number_of_neighbours_alive = match grid_type {
+        // one-dimensional and three-dimensional runs never get here, see advance_cells
+        GridType::Square | GridType::OneDimensional | GridType::ThreeDimensional => field.get_number_of_neighbours_alive(x, y, consider_extremes_adjacent),
+        GridType::Triangular => field.get_number_of_triangular_neighbours_alive(x, y, triangular_neighbourhood, consider_extremes_adjacent),
+    };
+    rule.get_next_cell_state(field.get_cell_state(x, y), number_of_neighbours_alive)
+}
@@ -0,0 +1,95 @@
+use super::field::get_offset_position;
+use super::field_error::FieldError;
+
+/// Like `Field`, but each cell holds one of up to 256 states instead of just dead/alive.
+/// State 0 is the background ("dead") state.
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct MultiStateField {
+    width: usize,
+    height: usize,
+    #[serde(with = "super::save_format::packed_states")]
+    cells: Vec<u8>
+}
+
+impl MultiStateField {
+    pub fn new(width: usize, height: usize) -> MultiStateField {
+        MultiStateField{width, height, cells: vec![0; width * height]}
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// The states row by row: the cell (x, y) is at x + y * width
+    pub fn get_cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    pub(crate) fn get_cells_mut(&mut self) -> &mut [u8] {
+        &mut self.cells
+    }
+
+    pub fn is_within_boundaries(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Cells outside of the board are in the background state
+    pub fn get_cell_state(&self, x: usize, y: usize) -> u8 {
+        if self.is_within_boundaries(x, y) {
+            return self.cells[x + y * self.width]
+        }
+        0
+    }
+
+    pub fn set_cell_state(&mut self, x: usize, y: usize, cell_state: u8) {
+        if self.is_within_boundaries(x, y) {
+            self.cells[x + y * self.width] = cell_state
+        }
+    }
+
+    /// Like `get_cell_state`, but with an error rather than the background state outside of the board
+    pub fn try_get_cell_state(&self, x: usize, y: usize) -> Result<u8, FieldError> {
+        self.check_boundaries(x, y)?;
+        Ok(self.cells[x + y * self.width])
+    }
+
+    /// Like `set_cell_state`, but with an error rather than nothing happening outside of the board
+    pub fn try_set_cell_state(&mut self, x: usize, y: usize, cell_state: u8) -> Result<(), FieldError> {
+        self.check_boundaries(x, y)?;
+        self.cells[x + y * self.width] = cell_state;
+        Ok(())
+    }
+
+    fn check_boundaries(&self, x: usize, y: usize) -> Result<(), FieldError> {
+        if self.is_within_boundaries(x, y) {
+            return Ok(());
+        }
+        Err(FieldError::OutOfBounds{x, y, width: self.width, height: self.height})
+    }
+
+    /// Whether the cells match the dimensions, which a damaged save may not
+    pub(crate) fn check_cell_count(&self) -> Result<(), FieldError> {
+        if self.cells.len() == self.width * self.height {
+            return Ok(());
+        }
+        Err(FieldError::CellCountMismatch{n_cells: self.cells.len(), expected_n_cells: self.width * self.height})
+    }
+
+    /// Writes the states of the cells at the given offsets from (x, y) into `states`, in the same order as the offsets
+    pub fn get_neighbour_states(&self, x: usize, y: usize, offsets: &[(isize, isize)], consider_extremes_adjacent: bool, states: &mut [u8]) {
+        for ((dx, dy), state) in offsets.iter().zip(states.iter_mut()) {
+            *state = match get_offset_position(self.width, self.height, x, y, *dx, *dy, consider_extremes_adjacent) {
+                Some(position) => self.get_cell_state(position.x, position.y),
+                None => 0,
+            };
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::VecDeque;
+use rand::Rng;
+use super::soup::{get_probability, get_soup_rng};
+use super::multi_state_field::MultiStateField;
+
+/// Older generations are forgotten past this many rows of history
+pub const MAX_HISTORY_ROWS: usize = 10_000;
+
+/// A rule for a row of cells, where each cell's next state depends on itself and the `radius` cells on each side.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum OneDimensionalRule {
+    /// Wolfram's numbering of the 256 two-state, radius 1 rules: bit (4 * left + 2 * center + right) of the number is the next state
+    Elementary(u8),
+    /// The next state only depends on the sum of the states in the neighbourhood:
+    /// it is digit `sum` (in base `n_colors`, least significant first) of `code`
+    Totalistic{n_colors: usize, radius: usize, code: u64}
+}
+
+impl Default for OneDimensionalRule {
+    fn default() -> Self {
+        OneDimensionalRule::Elementary(30)
+    }
+}
+
+impl OneDimensionalRule {
+    pub fn new_totalistic(n_colors: usize, radius: usize, code: u64) -> Result<OneDimensionalRule, String> {
+        if !(2..=256).contains(&n_colors) {
+            return Err(String::from("The number of colours must be between 2 and 256"));
+        }
+        if radius == 0 {
+            return Err(String::from("The radius must be at least 1"));
+        }
+        let n_sums = (2 * radius + 1) * (n_colors - 1) + 1;
+        let n_codes = (n_colors as u64).checked_pow(n_sums as u32);
+        if let Some(n_codes) = n_codes {
+            if code >= n_codes {
+                return Err(String::from("The code must be below ") + &n_codes.to_string() + " for " + &n_colors.to_string() + " colours and radius " + &radius.to_string());
+            }
+        }
+        Ok(OneDimensionalRule::Totalistic{n_colors, radius, code})
+    }
+
+    pub fn get_n_colors(&self) -> usize {
+        match self {
+            OneDimensionalRule::Elementary(_) => 2,
+            OneDimensionalRule::Totalistic{n_colors, ..} => *n_colors,
+        }
+    }
+
+    pub fn get_radius(&self) -> usize {
+        match self {
+            OneDimensionalRule::Elementary(_) => 1,
+            OneDimensionalRule::Totalistic{radius, ..} => *radius,
+        }
+    }
+
+    /// `neighbourhood` holds the 2 * radius + 1 states from left to right
+    pub fn get_next_cell_state(&self, neighbourhood: &[u8]) -> u8 {
+        match self {
+            OneDimensionalRule::Elementary(rule_number) => {
+                let index = neighbourhood.iter().fold(0, |index, state| index * 2 + (*state).min(1));
+                (rule_number >> index) & 1
+            }
+            OneDimensionalRule::Totalistic{n_colors, code, ..} => {
+                let sum: u32 = neighbourhood.iter().map(|state| *state as u32).sum();
+                match (*n_colors as u64).checked_pow(sum) {
+                    Some(place_value) => ((code / place_value) % *n_colors as u64) as u8,
+                    None => 0,
+                }
+            }
+        }
+    }
+
+    pub fn to_rule_string(self) -> String {
+        match self {
+            OneDimensionalRule::Elementary(rule_number) => String::from("Rule ") + &rule_number.to_string(),
+            OneDimensionalRule::Totalistic{n_colors, radius, code} => {
+                String::from("Totalistic code ") + &code.to_string() + " (" + &n_colors.to_string() + " colours, radius " + &radius.to_string() + ")"
+            }
+        }
+    }
+}
+
+/// Ways of setting the first row of a one-dimensional run
+pub enum OneDimensionalSeed {
+    /// A single cell in the highest state, in the middle of the row
+    SingleCell,
+    /// Each cell is in a random non-zero state with the given probability (percentage), drawn from the given seed
+    Random(f64, u64),
+    /// One digit per cell, e.g. "0110101", centred on the row
+    Cells(String)
+}
+
+/// A one-dimensional run: the rows of every generation, stacked top to bottom onto the field as a spacetime diagram.
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct OneDimensionalAutomaton {
+    rule: OneDimensionalRule,
+    /// Rows of the generations remembered so far, oldest first; the last one is the current generation
+    history: VecDeque<Vec<u8>>,
+    /// Generation of the first row of `history`
+    first_history_generation: usize,
+    /// First generation shown on the field, or None to keep the current generation in view
+    view_start: Option<usize>
+}
+
+impl OneDimensionalAutomaton {
+    pub fn new(width: usize) -> OneDimensionalAutomaton {
+        let mut automaton = OneDimensionalAutomaton{
+            rule: OneDimensionalRule::default(),
+            history: VecDeque::from([vec![0; width]]),
+            first_history_generation: 0,
+            view_start: None
+        };
+        automaton.seed(OneDimensionalSeed::SingleCell).ok();
+        automaton
+    }
+
+    pub fn get_rule(&self) -> OneDimensionalRule {
+        self.rule
+    }
+
+    /// States the new rule doesn't have are reset to 0 in the current row
+    pub fn set_rule(&mut self, rule: OneDimensionalRule) {
+        self.rule = rule;
+        let n_colors = rule.get_n_colors();
+        for cell in self.get_current_row_mut().iter_mut() {
+            if *cell as usize >= n_colors {
+                *cell = 0;
+            }
+        }
+    }
+
+    fn get_width(&self) -> usize {
+        self.history.back().map_or(0, |row| row.len())
+    }
+
+    pub fn get_current_row(&self) -> &[u8] {
+        self.history.back().map_or(&[], |row| row.as_slice())
+    }
+
+    fn get_current_row_mut(&mut self) -> &mut Vec<u8> {
+        if self.history.is_empty() {
+            self.history.push_back(Vec::new());
+        }
+        let last_index = self.history.len() - 1;
+        &mut self.history[last_index]
+    }
+
+    pub fn get_first_history_generation(&self) -> usize {
+        self.first_history_generation
+    }
+
+    pub fn get_current_generation(&self) -> usize {
+        self.first_history_generation + self.history.len().saturating_sub(1)
+    }
+
+    pub fn get_view_start(&self) -> Option<usize> {
+        self.view_start
+    }
+
+    pub fn set_view_start(&mut self, view_start: Option<usize>) {
+        self.view_start = view_start;
+    }
+
+    /// Replaces the history with a single row, as generation 0
+    pub fn seed(&mut self, seed: OneDimensionalSeed) -> Result<(), String> {
+        let width = self.get_width();
+        let highest_state = (self.rule.get_n_colors() - 1) as u8;
+        let mut row: Vec<u8> = vec![0; width];
+        match seed {
+            OneDimensionalSeed::SingleCell => {
+                if width > 0 {
+                    row[width / 2] = highest_state;
+                }
+            }
+            OneDimensionalSeed::Random(probability_living_cell, seed) => {
+                let mut rng = get_soup_rng(seed);
+                for cell in row.iter_mut() {
+                    if rng.gen_bool(get_probability(probability_living_cell)) {
+                        *cell = rng.gen_range(1..=highest_state);
+                    }
+                }
+            }
+            OneDimensionalSeed::Cells(cells) => {
+                let mut states: Vec<u8> = Vec::new();
+                for c in cells.chars().filter(|c| !c.is_whitespace()) {
+                    let state = c.to_digit(10)
+                        .filter(|digit| *digit <= highest_state as u32)
+                        .ok_or(String::from("'") + &c.to_string() + "' is not a state between 0 and " + &highest_state.to_string())?;
+                    states.push(state as u8);
+                }
+                if states.len() > width {
+                    return Err(String::from("The row is only ") + &width.to_string() + " cells wide");
+                }
+                let start = (width - states.len()) / 2;
+                row[start..start + states.len()].copy_from_slice(&states);
+            }
+        }
+        self.history = VecDeque::from([row]);
+        self.first_history_generation = 0;
+        self.view_start = None;
+        Ok(())
+    }
+
+    pub fn set_current_cell_state(&mut self, x: usize, state: u8) {
+        let n_colors = self.rule.get_n_colors();
+        if let Some(cell) = self.get_current_row_mut().get_mut(x) {
+            *cell = (state as usize).min(n_colors - 1) as u8;
+        }
+    }
+
+    /// Cells beyond the ends of the row are in state 0, unless the extremes are adjacent
+    pub fn advance_generation(&mut self, consider_extremes_adjacent: bool) {
+        let current_row = self.get_current_row();
+        let width = current_row.len() as isize;
+        let radius = self.rule.get_radius() as isize;
+        let next_row: Vec<u8> = (0..width)
+            .map(|x| {
+                let neighbourhood: Vec<u8> = (x - radius..=x + radius)
+                    .map(|neighbour_x| {
+                        if consider_extremes_adjacent {
+                            current_row[neighbour_x.rem_euclid(width) as usize]
+                        }
+                        else if neighbour_x < 0 || neighbour_x >= width {
+                            0
+                        }
+                        else {
+                            current_row[neighbour_x as usize]
+                        }
+                    })
+                    .collect();
+                self.rule.get_next_cell_state(&neighbourhood)
+            })
+            .collect();
+        self.history.push_back(next_row);
+        if self.history.len() > MAX_HISTORY_ROWS {
+            self.history.pop_front();
+            self.first_history_generation += 1;
+        }
+    }
+
+    /// First generation to show on a field of the given height
+    pub fn get_shown_first_generation(&self, field_height: usize) -> usize {
+        let latest_start = (self.get_current_generation() + 1).saturating_sub(field_height).max(self.first_history_generation);
+        match self.view_start {
+            Some(view_start) => view_start.clamp(self.first_history_generation, latest_start),
+            None => latest_start,
+        }
+    }
+
+    /// Draws the shown generations onto the field, one row per generation
+    pub fn fill_field(&self, field: &mut MultiStateField) {
+        let first_generation = self.get_shown_first_generation(field.get_height());
+        for y in 0..field.get_height() {
+            let row = self.history.get(first_generation - self.first_history_generation + y);
+            for x in 0..field.get_width() {
+                let state = row.and_then(|row| row.get(x)).copied().unwrap_or(0);
+                field.set_cell_state(x, y, state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows of the first generations of a single cell in state 1, as digits
+    fn get_rows(rule: OneDimensionalRule, width: usize, n_generations: usize, consider_extremes_adjacent: bool) -> Vec<String> {
+        let mut automaton = OneDimensionalAutomaton::new(width);
+        automaton.set_rule(rule);
+        automaton.seed(OneDimensionalSeed::Cells(String::from("1"))).unwrap();
+        let mut rows: Vec<String> = Vec::new();
+        for _ in 0..=n_generations {
+            rows.push(automaton.get_current_row().iter().map(|state| state.to_string()).collect());
+            automaton.advance_generation(consider_extremes_adjacent);
+        }
+        rows
+    }
+
+    #[test]
+    fn elementary_rules_grow_from_a_single_cell() {
+        assert_eq!(get_rows(OneDimensionalRule::Elementary(30), 7, 5, false), ["0001000", "0011100", "0110010", "1101111", "1001000", "1111100"]);
+        assert_eq!(get_rows(OneDimensionalRule::Elementary(30), 7, 5, true), ["0001000", "0011100", "0110010", "1101111", "0001000", "0011100"]);
+        assert_eq!(get_rows(OneDimensionalRule::Elementary(90), 7, 5, false), ["0001000", "0010100", "0100010", "1010101", "0000000", "0000000"]);
+        assert_eq!(get_rows(OneDimensionalRule::Elementary(90), 7, 5, true), ["0001000", "0010100", "0100010", "1010101", "1000001", "1100011"]);
+    }
+
+    #[test]
+    fn totalistic_codes_are_decoded() {
+        // Wolfram's code 1599: 2 0 1 2 0 2 0 for the sums 6 down to 0
+        let rule = OneDimensionalRule::new_totalistic(3, 1, 1599).unwrap();
+        for (neighbourhood, next_state) in [([0, 0, 0], 0), ([0, 1, 0], 2), ([1, 0, 1], 0), ([1, 1, 1], 2), ([2, 0, 2], 1), ([2, 2, 1], 0), ([2, 2, 2], 2)] {
+            assert_eq!(rule.get_next_cell_state(&neighbourhood), next_state, "{neighbourhood:?}");
+        }
+        assert_eq!(get_rows(rule, 7, 2, false), ["0001000", "0022200", "0012100"]);
+        assert!(OneDimensionalRule::new_totalistic(3, 1, 3u64.pow(7)).is_err());
+        assert!(OneDimensionalRule::new_totalistic(1, 1, 0).is_err());
+        assert!(OneDimensionalRule::new_totalistic(2, 0, 0).is_err());
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut automaton = OneDimensionalAutomaton::new(8);
+        for _ in 0..MAX_HISTORY_ROWS + 5 {
+            automaton.advance_generation(true);
+        }
+        assert_eq!(automaton.history.len(), MAX_HISTORY_ROWS);
+        assert_eq!(automaton.get_first_history_generation(), 6);
+        assert_eq!(automaton.get_current_generation(), MAX_HISTORY_ROWS + 5);
+    }
+
+    #[test]
+    fn shown_generations_stay_within_the_history() {
+        let mut automaton = OneDimensionalAutomaton::new(8);
+        assert_eq!(automaton.get_shown_first_generation(10), 0);
+        for _ in 0..30 {
+            automaton.advance_generation(true);
+        }
+        assert_eq!(automaton.get_shown_first_generation(10), 21);
+        automaton.set_view_start(Some(5));
+        assert_eq!(automaton.get_shown_first_generation(10), 5);
+        automaton.set_view_start(Some(100));
+        assert_eq!(automaton.get_shown_first_generation(10), 21);
+        assert_eq!(automaton.get_shown_first_generation(50), 0);
+
+        for _ in 0..MAX_HISTORY_ROWS {
+            automaton.advance_generation(true);
+        }
+        automaton.set_view_start(Some(0));
+        assert_eq!(automaton.get_shown_first_generation(10), automaton.get_first_history_generation());
+        let mut field = MultiStateField::new(8, 10);
+        automaton.fill_field(&mut field);
+    }
+}
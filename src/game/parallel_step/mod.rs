@@ -0,0 +1,240 @@
+use super::field::{CellState, Field};
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+use std::sync::{Barrier, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Fewest rows a band is given, so that small boards aren't slowed down by starting threads
+#[cfg(not(target_arch = "wasm32"))]
+const MIN_ROWS_PER_BAND: usize = 16;
+
+/// Threads stepping in parallel when their number is left automatic: one per core on native, a single one on the web
+pub fn get_available_threads() -> usize {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::thread::available_parallelism().map_or(1, |n_threads| n_threads.get())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        1
+    }
+}
+
+/// Bands the rows are split into for `n_threads` threads (0 for automatic); always 1 on the web, where there are no threads
+fn get_n_bands(height: usize, n_threads: usize) -> usize {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let n_threads = if n_threads == 0 {get_available_threads()} else {n_threads};
+        n_threads.min(height / MIN_ROWS_PER_BAND).max(1)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (height, n_threads);
+        1
+    }
+}
+
+/// Advances `field` by `n_generations` generations, each cell (x, y) taking the state `get_next_cell_state` gives it
+/// from the previous generation, and calls `after_generation` with each new generation.
+/// The rows are split into horizontal bands computed on their own threads, each writing only its band. Their halos,
+/// the rows just beyond a band that its cells' neighbourhoods reach into (wrapping around to the other side of the board
+/// on a torus, and missing at the edges of a bounded board), are read from the previous generation shared by all the
+/// threads, so that each band sees its neighbours' rows as they were before the step, as the serial kernel does.
+/// The threads are started once for all the generations, and wait for each other at a barrier between them.
+/// A panic in `get_next_cell_state` or `after_generation` stops every thread at the next barrier and is passed on.
+pub fn advance_in_bands<F, G>(field: Field, n_generations: usize, n_threads: usize, get_next_cell_state: F, after_generation: G) -> Field
+where F: Fn(&Field, usize, usize) -> CellState + Sync,
+      G: FnMut(&Field)
+{
+    let n_bands = get_n_bands(field.get_height(), n_threads);
+    advance_in_n_bands(field, n_generations, n_bands, get_next_cell_state, after_generation)
+}
+
+fn advance_in_n_bands<F, G>(mut field: Field, n_generations: usize, n_bands: usize, get_next_cell_state: F, mut after_generation: G) -> Field
+where F: Fn(&Field, usize, usize) -> CellState + Sync,
+      G: FnMut(&Field)
+{
+    let width = field.get_width();
+    let height = field.get_height();
+    #[cfg(target_arch = "wasm32")]
+    let n_bands = n_bands.min(1); // there are no threads on the web
+    if n_bands <= 1 || width == 0 {
+        let mut next_field = field.clone();
+        for _ in 0..n_generations {
+            advance_band(&field, next_field.get_cells_mut(), 0, &get_next_cell_state);
+            std::mem::swap(&mut field, &mut next_field);
+            after_generation(&field);
+        }
+        return field;
+    }
+
+    let rows_per_band = height.div_ceil(n_bands);
+    let n_bands = height.div_ceil(rows_per_band);
+    let shared_field = RwLock::new(field);
+    // the bands and this thread meet twice per generation: once all the bands are computed, then once they are all written
+    let barrier = Barrier::new(n_bands + 1);
+    // set before the first meeting of a generation by whichever thread panicked, so that all of them stop after it,
+    // rather than waiting forever for the panicked one
+    let has_panicked = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        for band_index in 0..n_bands {
+            let (shared_field, barrier, has_panicked, get_next_cell_state) = (&shared_field, &barrier, &has_panicked, &get_next_cell_state);
+            scope.spawn(move || {
+                let first_row = band_index * rows_per_band;
+                let band_cells = first_row * width..(first_row + rows_per_band).min(height) * width;
+                let mut band: Vec<CellState> = vec![CellState::Dead; band_cells.len()];
+                for _ in 0..n_generations {
+                    let result = catch_unwind(AssertUnwindSafe(|| advance_band(&read(shared_field), &mut band, first_row, get_next_cell_state)));
+                    if result.is_err() {
+                        has_panicked.store(true, Ordering::Relaxed);
+                    }
+                    barrier.wait();
+                    if let Err(panic) = result {
+                        resume_unwind(panic);
+                    }
+                    if has_panicked.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    write(shared_field).get_cells_mut()[band_cells.clone()].copy_from_slice(&band);
+                    barrier.wait();
+                }
+            });
+        }
+        for generation in 0..n_generations {
+            barrier.wait();
+            if has_panicked.load(Ordering::Relaxed) {
+                // the scope passes on the panic of the band once all the threads are joined
+                return;
+            }
+            barrier.wait();
+            if let Err(panic) = catch_unwind(AssertUnwindSafe(|| after_generation(&read(&shared_field)))) {
+                has_panicked.store(true, Ordering::Relaxed);
+                // the bands are computing the next generation, and stop once they meet
+                if generation + 1 < n_generations {
+                    barrier.wait();
+                }
+                resume_unwind(panic);
+            }
+        }
+    });
+    shared_field.into_inner().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn read(shared_field: &RwLock<Field>) -> RwLockReadGuard<'_, Field> {
+    shared_field.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write(shared_field: &RwLock<Field>) -> RwLockWriteGuard<'_, Field> {
+    shared_field.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Fills `band`, the rows of the next cells starting at row `first_row`
+fn advance_band<F>(field: &Field, band: &mut [CellState], first_row: usize, get_next_cell_state: &F)
+where F: Fn(&Field, usize, usize) -> CellState
+{
+    let width = field.get_width();
+    for (index, cell_state) in band.iter_mut().enumerate() {
+        *cell_state = get_next_cell_state(field, index % width, first_row + index / width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Game;
+    use super::super::activity::ActivityMeasure;
+    use super::super::rule::Rule;
+
+    /// Field of the given size with about a third of its cells alive, the same every time
+    fn get_soup(width: usize, height: usize) -> Field {
+        let mut field = Field::new(width, height);
+        let mut state: u64 = 12345;
+        for y in 0..height {
+            for x in 0..width {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                if (state >> 33) % 3 == 0 {
+                    field.set_cell_state(x, y, CellState::Alive);
+                }
+            }
+        }
+        field
+    }
+
+    /// Generations of the soup stepped in `n_bands` bands, with the populations `after_generation` saw
+    fn advance_soup(width: usize, height: usize, consider_extremes_adjacent: bool, n_generations: usize, n_bands: usize) -> (Field, Vec<usize>) {
+        let rule = Rule::conway();
+        let mut populations: Vec<usize> = Vec::new();
+        let field = advance_in_n_bands(get_soup(width, height), n_generations, n_bands, |field, x, y| {
+            rule.get_next_cell_state(field.get_cell_state(x, y), field.get_number_of_neighbours_alive(x, y, consider_extremes_adjacent))
+        }, |field| populations.push(field.get_population()));
+        (field, populations)
+    }
+
+    #[test]
+    fn bands_match_the_serial_step() {
+        for consider_extremes_adjacent in [true, false] {
+            for (width, height) in [(20, 17), (9, 7), (16, 1)] {
+                let (serial_field, serial_populations) = advance_soup(width, height, consider_extremes_adjacent, 12, 1);
+                assert_eq!(serial_populations.len(), 12);
+                // a few bands of unequal heights, then one band per row
+                for n_bands in [2, 3, height] {
+                    let (field, populations) = advance_soup(width, height, consider_extremes_adjacent, 12, n_bands);
+                    assert_eq!(field.get_cells(), serial_field.get_cells(), "{width}x{height} in {n_bands} bands, torus: {consider_extremes_adjacent}");
+                    assert_eq!(populations, serial_populations);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn panics_are_passed_on_rather_than_waited_for() {
+        let rule = Rule::conway();
+        let get_next_cell_state = |field: &Field, x: usize, y: usize| {
+            rule.get_next_cell_state(field.get_cell_state(x, y), field.get_number_of_neighbours_alive(x, y, true))
+        };
+        // in the middle band, during the third generation
+        let n_cells_computed = std::sync::atomic::AtomicUsize::new(0);
+        let result = std::panic::catch_unwind(|| {
+            advance_in_n_bands(get_soup(20, 17), 6, 3, |field, x, y| {
+                let n_cells = n_cells_computed.fetch_add(1, Ordering::Relaxed);
+                assert!(y != 9 || n_cells < 2 * 20 * 17, "panic in a band");
+                get_next_cell_state(field, x, y)
+            }, |_| ())
+        });
+        assert!(result.is_err());
+        // after the third generation, then after the last one
+        for panicking_generation in [3, 6] {
+            let result = std::panic::catch_unwind(|| {
+                let mut generation: usize = 0;
+                advance_in_n_bands(get_soup(20, 17), 6, 3, get_next_cell_state, |_| {
+                    generation += 1;
+                    assert!(generation != panicking_generation, "panic after a generation");
+                })
+            });
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn games_stepped_at_once_match_games_stepped_one_generation_at_a_time() {
+        let get_game = || {
+            let mut game = Game::new(24, 50);
+            game.set_field(get_soup(24, 50)).unwrap();
+            game.set_activity_tracking(true);
+            game
+        };
+        let (mut game, mut stepped_game) = (get_game(), get_game());
+        game.set_n_threads(1);
+        stepped_game.set_n_threads(3); // three bands of 16 rows or more
+        for _ in 0..15 {
+            game.advance_generation(true);
+        }
+        stepped_game.advance_generations(15, true);
+        assert_eq!(stepped_game.get_generation(), 15);
+        assert_eq!(stepped_game.get_field().get_cells(), game.get_field().get_cells());
+        for (x, y) in [(0, 0), (5, 17), (23, 49), (12, 33)] {
+            assert_eq!(stepped_game.get_cell_ages().get_age(x, y), game.get_cell_ages().get_age(x, y));
+            assert_eq!(stepped_game.get_activity_map().get_count(ActivityMeasure::StateChanges, x, y),
+                game.get_activity_map().get_count(ActivityMeasure::StateChanges, x, y));
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use super::field::{CellRegion, CellState, Field};
+use super::rule::Rule;
+use super::sat::{Literal, SatResult, SatSolver};
+
+/// What a predecessor search found
+pub enum PredecessorSearchResult {
+    /// Parents of the target, each covering the target region grown by one cell (or two if the surroundings must die) on each side
+    Parents(Vec<Field>),
+    /// No parent exists within the bounds, so any pattern containing the target is a Garden of Eden
+    GardenOfEden,
+    /// No parent within the bounds leaves the surroundings of the target dead: the target can't appear on an otherwise
+    /// empty board from so close a parent, but it may still have parents that don't clear their surroundings
+    NoIsolatedParent,
+    /// The conflict limit was reached before the first parent (or a proof that there is none) was found
+    GaveUp
+}
+
+/// Looks for patterns (parents) that evolve into the cells of `region` of `target` in one generation of `rule`.
+/// Only the cells that can influence the region are searched, so a proof that no parent exists is a proof that
+/// the region is an orphan: no pattern whatsoever contains it in the next generation.
+/// If `surroundings_must_die`, the parents are alone on an empty board and every cell they can reach outside of the region
+/// must be dead in the next generation, which finds parents that work on an otherwise empty board.
+/// The search is run a few conflicts at a time by `step`, so it can run a bit every frame.
+pub struct PredecessorSearch {
+    /// Board coordinates of the top left cell of the parents (negative if they stick out of the board)
+    parent_x: isize,
+    parent_y: isize,
+    parent_width: usize,
+    parent_height: usize,
+    surroundings_must_die: bool,
+    solver: SatSolver,
+    max_parents: usize,
+    /// Conflicts allowed for each parent (or for the proof that there is no other one)
+    conflict_limit: usize,
+    /// Conflicts spent on the next parent so far
+    n_conflicts: usize,
+    parents: Vec<Field>,
+    result: Option<PredecessorSearchResult>
+}
+
+impl PredecessorSearch {
+    /// Prepares the search for up to `max_parents` distinct parents, spending at most `conflict_limit` solver conflicts on each
+    pub fn new(target: &Field, region: CellRegion, rule: &Rule, surroundings_must_die: bool, max_parents: usize, conflict_limit: usize) -> PredecessorSearch {
+        let margin: usize = if surroundings_must_die {2} else {1};
+        let parent_width = region.width + 2 * margin;
+        let parent_height = region.height + 2 * margin;
+        let parent_x = region.x as isize - margin as isize;
+        let parent_y = region.y as isize - margin as isize;
+        let mut solver = SatSolver::new(parent_width * parent_height);
+
+        // Every assignment of a target cell's 3x3 neighbourhood leading to the wrong state is forbidden by a clause;
+        // the assignments are the same for every cell, so they are enumerated once per target state.
+        let mut forbidden_assignments: [Vec<usize>; 2] = [Vec::new(), Vec::new()];
+        for assignment in 0..(1usize << 9) {
+            let cell_state = if assignment & (1 << 4) != 0 {CellState::Alive} else {CellState::Dead};
+            let number_of_neighbours_alive = (assignment & !(1 << 4)).count_ones() as usize;
+            let next_cell_state = rule.get_next_cell_state(cell_state, number_of_neighbours_alive);
+            let forbidden_when_target_is_alive = next_cell_state != CellState::Alive;
+            forbidden_assignments[forbidden_when_target_is_alive as usize].push(assignment);
+        }
+
+        // Without the surroundings, the constrained cells are those of the region: all the parent cells but its outermost ring.
+        // With them, they are all the cells the parent reaches: the parent and the ring around it, whose cells outside of the
+        // parent are dead (so the assignments making them alive can't happen, and their literals are left out of the clauses).
+        let reach: isize = if surroundings_must_die {1} else {-1};
+        for constrained_y in -reach..parent_height as isize + reach {
+            for constrained_x in -reach..parent_width as isize + reach {
+                let board_x = parent_x + constrained_x;
+                let board_y = parent_y + constrained_y;
+                let is_within_region = board_x >= 0 && board_y >= 0 && region.contains(board_x as usize, board_y as usize);
+                let is_target_alive = is_within_region && target.get_cell_state(board_x as usize, board_y as usize) == CellState::Alive;
+
+                let mut neighbourhood_variables: Vec<Option<usize>> = Vec::with_capacity(9);
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let (x, y) = (constrained_x + dx, constrained_y + dy);
+                        let is_within_parent = x >= 0 && y >= 0 && x < parent_width as isize && y < parent_height as isize;
+                        neighbourhood_variables.push(is_within_parent.then(|| x as usize + y as usize * parent_width));
+                    }
+                }
+                for assignment in &forbidden_assignments[is_target_alive as usize] {
+                    let is_possible = neighbourhood_variables.iter().enumerate()
+                        .all(|(bit, variable)| variable.is_some() || assignment & (1 << bit) == 0);
+                    if !is_possible {
+                        continue;
+                    }
+                    let clause: Vec<Literal> = neighbourhood_variables.iter().enumerate()
+                        .filter_map(|(bit, variable)| variable.map(|variable| (bit, variable)))
+                        .map(|(bit, variable)| if assignment & (1 << bit) != 0 {Literal::negative(variable)} else {Literal::positive(variable)})
+                        .collect();
+                    solver.add_clause(&clause);
+                }
+            }
+        }
+        PredecessorSearch{
+            parent_x,
+            parent_y,
+            parent_width,
+            parent_height,
+            surroundings_must_die,
+            solver,
+            max_parents,
+            conflict_limit,
+            n_conflicts: 0,
+            parents: Vec::new(),
+            result: None
+        }
+    }
+
+    /// Runs the solver for at most `n_conflicts` more conflicts, recording the parents it finds on the way
+    pub fn step(&mut self, n_conflicts: usize) {
+        if self.result.is_some() {
+            return;
+        }
+        let n_conflicts = n_conflicts.min(self.conflict_limit - self.n_conflicts).max(1);
+        match self.solver.solve(n_conflicts) {
+            SatResult::Satisfiable(model) => {
+                let mut parent = Field::new(self.parent_width, self.parent_height);
+                for (index, is_alive) in model.iter().enumerate() {
+                    if *is_alive {
+                        parent.set_cell_state(index % self.parent_width, index / self.parent_width, CellState::Alive);
+                    }
+                }
+                // Block this parent so the next solve finds a different one
+                let blocking_clause: Vec<Literal> = model.iter().enumerate()
+                    .map(|(variable, is_alive)| if *is_alive {Literal::negative(variable)} else {Literal::positive(variable)})
+                    .collect();
+                self.solver.add_clause(&blocking_clause);
+                self.parents.push(parent);
+                self.n_conflicts = 0;
+                if self.parents.len() >= self.max_parents {
+                    self.stop();
+                }
+            }
+            SatResult::Unsatisfiable if self.parents.is_empty() => {
+                self.result = Some(if self.surroundings_must_die {PredecessorSearchResult::NoIsolatedParent} else {PredecessorSearchResult::GardenOfEden});
+            }
+            SatResult::Unsatisfiable => self.stop(),
+            SatResult::Unknown => {
+                self.n_conflicts += n_conflicts;
+                if self.n_conflicts >= self.conflict_limit {
+                    self.stop();
+                }
+            }
+        }
+    }
+
+    /// Ends the search with the parents found so far
+    pub fn stop(&mut self) {
+        if self.result.is_none() {
+            let parents = std::mem::take(&mut self.parents);
+            self.result = Some(if parents.is_empty() {PredecessorSearchResult::GaveUp} else {PredecessorSearchResult::Parents(parents)});
+        }
+    }
+
+    /// Parents found so far, while the search runs
+    pub fn get_parents(&self) -> &[Field] {
+        &self.parents
+    }
+
+    /// What the search found, once it is over
+    pub fn get_result(&self) -> Option<&PredecessorSearchResult> {
+        self.result.as_ref()
+    }
+
+    /// Writes a parent found by the search onto the board, where it evolves into the target; cells falling outside of the board are left out
+    pub fn place_parent(&self, parent: &Field, board: &mut Field) {
+        for y in 0..self.parent_height {
+            for x in 0..self.parent_width {
+                let board_x = self.parent_x + x as isize;
+                let board_y = self.parent_y + y as isize;
+                if board_x >= 0 && board_y >= 0 {
+                    board.set_cell_state(board_x as usize, board_y as usize, parent.get_cell_state(x, y));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_to_end(predecessor_search: &mut PredecessorSearch) {
+        while predecessor_search.get_result().is_none() {
+            predecessor_search.step(1000);
+        }
+    }
+
+    /// The board with the pattern, given as rows of 'o' (alive) and '.' (dead), at (x, y)
+    fn get_board(width: usize, height: usize, x: usize, y: usize, pattern: &str) -> Field {
+        let mut board = Field::new(width, height);
+        for (dy, row) in pattern.split('/').enumerate() {
+            for (dx, c) in row.chars().enumerate() {
+                if c == 'o' {
+                    board.set_cell_state(x + dx, y + dy, CellState::Alive);
+                }
+            }
+        }
+        board
+    }
+
+    fn get_next_board(board: &Field, rule: &Rule) -> Field {
+        let mut next_board = Field::new(board.get_width(), board.get_height());
+        for y in 0..board.get_height() {
+            for x in 0..board.get_width() {
+                let number_of_neighbours_alive = board.get_number_of_neighbours_alive(x, y, false);
+                next_board.set_cell_state(x, y, rule.get_next_cell_state(board.get_cell_state(x, y), number_of_neighbours_alive));
+            }
+        }
+        next_board
+    }
+
+    fn is_region_equal(first: &Field, second: &Field, region: CellRegion) -> bool {
+        (region.y..region.y + region.height)
+            .all(|y| (region.x..region.x + region.width).all(|x| first.get_cell_state(x, y) == second.get_cell_state(x, y)))
+    }
+
+    #[test]
+    fn finds_parents_of_a_block() {
+        let rule = Rule::conway();
+        let target = get_board(12, 12, 5, 5, "oo/oo");
+        let region = CellRegion{x: 5, y: 5, width: 2, height: 2};
+        for surroundings_must_die in [false, true] {
+            let mut predecessor_search = PredecessorSearch::new(&target, region, &rule, surroundings_must_die, 5, 100_000);
+            run_to_end(&mut predecessor_search);
+            let Some(PredecessorSearchResult::Parents(parents)) = predecessor_search.get_result() else {
+                panic!("a block has parents");
+            };
+            assert_eq!(parents.len(), 5);
+            for parent in parents {
+                let mut board = Field::new(12, 12);
+                predecessor_search.place_parent(parent, &mut board);
+                let next_board = get_next_board(&board, &rule);
+                if surroundings_must_die {
+                    assert!(is_region_equal(&next_board, &target, CellRegion{x: 0, y: 0, width: 12, height: 12}));
+                }
+                else {
+                    assert!(is_region_equal(&next_board, &target, region));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_still_life_is_its_own_parent() {
+        let rule = Rule::conway();
+        let target = get_board(12, 12, 4, 4, ".oo./o..o/.oo.");
+        let region = CellRegion{x: 4, y: 4, width: 4, height: 3};
+        let mut predecessor_search = PredecessorSearch::new(&target, region, &rule, true, 1000, 100_000);
+        // beehives have too many parents to list them all, so the parents are kept within the beehive's cells
+        for y in 0..predecessor_search.parent_height {
+            for x in 0..predecessor_search.parent_width {
+                if !(2..6).contains(&x) || !(2..5).contains(&y) {
+                    predecessor_search.solver.add_clause(&[Literal::negative(x + y * predecessor_search.parent_width)]);
+                }
+            }
+        }
+        run_to_end(&mut predecessor_search);
+        let Some(PredecessorSearchResult::Parents(parents)) = predecessor_search.get_result() else {
+            panic!("a beehive has parents");
+        };
+        let beehive = get_board(8, 7, 2, 2, ".oo./o..o/.oo.");
+        let whole_parent = CellRegion{x: 0, y: 0, width: 8, height: 7};
+        assert!(parents.iter().any(|parent| is_region_equal(parent, &beehive, whole_parent)));
+    }
+
+    #[test]
+    fn proves_orphans() {
+        // nothing survives or is born under B/S, so a living cell has no parent
+        let rule = Rule::parse("B/S").unwrap();
+        let target = get_board(5, 5, 2, 2, "o");
+        let region = CellRegion{x: 1, y: 1, width: 3, height: 3};
+        let mut predecessor_search = PredecessorSearch::new(&target, region, &rule, false, 1, 100_000);
+        run_to_end(&mut predecessor_search);
+        assert!(matches!(predecessor_search.get_result(), Some(PredecessorSearchResult::GardenOfEden)));
+        let mut predecessor_search = PredecessorSearch::new(&target, region, &rule, true, 1, 100_000);
+        run_to_end(&mut predecessor_search);
+        assert!(matches!(predecessor_search.get_result(), Some(PredecessorSearchResult::NoIsolatedParent)));
+    }
+
+    #[test]
+    fn tells_orphans_from_patterns_without_isolated_parents() {
+        // under B1/S, a lone cell is born next to a single living cell, but every parent also gives birth to other cells
+        let rule = Rule::parse("B1/S").unwrap();
+        let target = get_board(9, 9, 4, 4, "o");
+        let region = CellRegion{x: 4, y: 4, width: 1, height: 1};
+        let mut predecessor_search = PredecessorSearch::new(&target, region, &rule, false, 1, 100_000);
+        run_to_end(&mut predecessor_search);
+        assert!(matches!(predecessor_search.get_result(), Some(PredecessorSearchResult::Parents(_))));
+        let mut predecessor_search = PredecessorSearch::new(&target, region, &rule, true, 1, 100_000);
+        run_to_end(&mut predecessor_search);
+        assert!(matches!(predecessor_search.get_result(), Some(PredecessorSearchResult::NoIsolatedParent)));
+    }
+}
@@ -0,0 +1,128 @@
+use super::field::{CellState, Field};
+
+/// Writes the cells in the run-length encoding of Golly and the LifeWiki, without the header line:
+/// `b` for dead cells, `o` for living ones, `$` between rows and `!` at the end, each optionally preceded by its count
+/// (e.g. a glider is `bo$2bo$3o!`). Dead cells at the end of rows and empty rows at the end are left out.
+pub fn encode_rle(field: &Field) -> String {
+    let mut rle = String::new();
+    let mut n_pending_row_ends: usize = 0;
+    for y in 0..field.get_height() {
+        let row: Vec<bool> = (0..field.get_width()).map(|x| field.get_cell_state(x, y) == CellState::Alive).collect();
+        let row_length = row.iter().rposition(|is_alive| *is_alive).map_or(0, |last_alive| last_alive + 1);
+        if row_length > 0 {
+            push_run(&mut rle, n_pending_row_ends, '$');
+            n_pending_row_ends = 0;
+            let mut x = 0;
+            while x < row_length {
+                let run_start = x;
+                while x < row_length && row[x] == row[run_start] {
+                    x += 1;
+                }
+                push_run(&mut rle, x - run_start, if row[run_start] {'o'} else {'b'});
+            }
+        }
+        n_pending_row_ends += 1;
+    }
+    rle.push('!');
+    rle
+}
+
+fn push_run(rle: &mut String, length: usize, tag: char) {
+    if length > 1 {
+        rle.push_str(&length.to_string());
+    }
+    if length > 0 {
+        rle.push(tag);
+    }
+}
+
+/// Reads run-length encoded cells (see `encode_rle`) into a field of the given size; whitespace is ignored, and so is
+/// anything after the `!`. Any letter other than `b` or `.` is a living cell.
+pub fn decode_rle(rle: &str, width: usize, height: usize) -> Result<Field, String> {
+    let mut field = Field::new(width, height);
+    let (mut x, mut y): (usize, usize) = (0, 0);
+    let mut count: Option<usize> = None;
+    for character in rle.chars() {
+        if character.is_ascii_digit() {
+            let digit = character as usize - '0' as usize;
+            count = Some(count.unwrap_or(0).checked_mul(10).and_then(|count| count.checked_add(digit)).ok_or_else(|| String::from("Run length too long"))?);
+            continue;
+        }
+        let run_length = count.take().unwrap_or(1);
+        match character {
+            '!' => return Ok(field),
+            '$' => {
+                y = y.checked_add(run_length).ok_or_else(|| String::from("Run length too long"))?;
+                x = 0;
+            }
+            'b' | '.' => x = x.checked_add(run_length).ok_or_else(|| String::from("Run length too long"))?,
+            character if character.is_ascii_alphabetic() => {
+                if x.checked_add(run_length).map_or(true, |end| end > width) || y >= height {
+                    return Err(String::from("The pattern doesn't fit in ") + &width.to_string() + "x" + &height.to_string() + " cells");
+                }
+                for _ in 0..run_length {
+                    field.set_cell_state(x, y, CellState::Alive);
+                    x += 1;
+                }
+            }
+            character if character.is_whitespace() => (),
+            character => return Err(String::from("Unexpected character in the pattern: ") + &character.to_string()),
+        }
+    }
+    Err(String::from("The pattern doesn't end with !"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_field(rows: &[&str]) -> Field {
+        let mut field = Field::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, character) in row.chars().enumerate() {
+                if character == 'o' {
+                    field.set_cell_state(x, y, CellState::Alive);
+                }
+            }
+        }
+        field
+    }
+
+    fn get_rows(field: &Field) -> Vec<String> {
+        (0..field.get_height())
+            .map(|y| (0..field.get_width()).map(|x| if field.get_cell_state(x, y) == CellState::Alive {'o'} else {'.'}).collect())
+            .collect()
+    }
+
+    #[test]
+    fn glider_is_encoded() {
+        assert_eq!(encode_rle(&get_field(&[".o.", "..o", "ooo"])), "bo$2bo$3o!");
+    }
+
+    #[test]
+    fn patterns_round_trip() {
+        let rows = [".....", "o..oo", ".....", ".....", "oooo.", "....."];
+        let field = get_field(&rows);
+        let rle = encode_rle(&field);
+        assert_eq!(rle, "$o2b2o3$4o!");
+        assert_eq!(get_rows(&decode_rle(&rle, 5, 6).unwrap()), rows);
+        assert_eq!(encode_rle(&get_field(&["...", "..."])), "!");
+    }
+
+    #[test]
+    fn whitespace_and_trailing_text_are_ignored() {
+        let field = decode_rle("bo$2b\no$3o! trailing comment", 3, 3).unwrap();
+        assert_eq!(get_rows(&field), [".o.", "..o", "ooo"]);
+    }
+
+    #[test]
+    fn malformed_patterns_are_rejected() {
+        assert!(decode_rle("bo$2bo$3o", 3, 3).is_err()); // no !
+        assert!(decode_rle("4o!", 3, 3).is_err()); // too wide
+        assert!(decode_rle("3$o!", 3, 3).is_err()); // too high
+        assert!(decode_rle("b#o!", 3, 3).is_err());
+        assert!(decode_rle("99999999999999999999999o!", 3, 3).is_err());
+        assert!(decode_rle("18446744073709551615b18446744073709551615bo!", 3, 3).is_err());
+        assert!(decode_rle("18446744073709551615$18446744073709551615$o!", 3, 3).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use super::field::CellState;
+
+/// A totalistic rule in "B/S" notation: a dead cell is born when its number of living
+/// neighbours is listed in `birth`, and a living cell survives when it is listed in `survival`.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Rule {
+    birth: Vec<usize>,
+    survival: Vec<usize>
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+impl Rule {
+    pub fn new(birth: Vec<usize>, survival: Vec<usize>) -> Rule {
+        let mut rule = Rule{birth, survival};
+        rule.birth.sort_unstable();
+        rule.birth.dedup();
+        rule.survival.sort_unstable();
+        rule.survival.dedup();
+        rule
+    }
+
+    /// Conway's Game of Life, B3/S23
+    pub fn conway() -> Rule {
+        Rule::new(vec![3], vec![2, 3])
+    }
+
+    /// Parses rules such as "B3/S23" or "b36/s23" (case and the order of the two parts don't matter).
+    /// Neighbour counts above 9 (which occur on some grids) can be given comma-separated, e.g. "B4,5/S3,4,10".
+    pub fn parse(rule_string: &str) -> Result<Rule, String> {
+        let rule_string = rule_string.trim();
+        let parts: Vec<&str> = rule_string.split('/').collect();
+        if parts.len() != 2 {
+            return Err(String::from("Expected a rule of the form B<counts>/S<counts>, got \"") + rule_string + "\"");
+        }
+
+        let mut birth: Option<Vec<usize>> = None;
+        let mut survival: Option<Vec<usize>> = None;
+        for part in parts {
+            let part = part.trim();
+            let mut characters = part.chars();
+            let prefix = characters.next().map(|c| c.to_ascii_uppercase());
+            let counts = parse_neighbour_counts(characters.as_str())?;
+            match prefix {
+                Some('B') if birth.is_none() => birth = Some(counts),
+                Some('S') if survival.is_none() => survival = Some(counts),
+                _ => return Err(String::from("Unexpected rule part \"") + part + "\", expected one B part and one S part"),
+            }
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule::new(birth, survival)),
+            _ => Err(String::from("The rule must have both a B part and an S part")),
+        }
+    }
+
+    pub fn to_rule_string(&self) -> String {
+        String::from("B") + &format_neighbour_counts(&self.birth) + "/S" + &format_neighbour_counts(&self.survival)
+    }
+
+    pub fn get_next_cell_state(&self, cell_state: CellState, number_of_neighbours_alive: usize) -> CellState {
+        match cell_state {
+            CellState::Alive => {
+                if self.survival.contains(&number_of_neighbours_alive) {CellState::Alive} else {CellState::Dead}
+            }
+            _ => {
+                if self.birth.contains(&number_of_neighbours_alive) {CellState::Alive} else {CellState::Dead}
+            }
+        }
+    }
+}
+
+fn parse_neighbour_counts(counts: &str) -> Result<Vec<usize>, String> {
+    let counts = counts.trim();
+    if counts.contains(',') {
+        return counts.split(',')
+            .filter(|count| !count.trim().is_empty())
+            .map(|count| count.trim().parse::<usize>().map_err(|_| String::from("Invalid neighbour count \"") + count.trim() + "\""))
+            .collect();
+    }
+    counts.chars()
+        .map(|c| c.to_digit(10).map(|digit| digit as usize).ok_or(String::from("Invalid neighbour count '") + &c.to_string() + "'"))
+        .collect()
+}
+
+/// Counts above 9 switch to the comma-separated form, with a trailing comma when there is only one ("10,", not "10")
+fn format_neighbour_counts(counts: &[usize]) -> String {
+    let separator = if counts.iter().any(|count| *count > 9) {","} else {""};
+    let formatted_counts = counts.iter().map(|count| count.to_string()).collect::<Vec<String>>().join(separator);
+    if counts.len() == 1 && counts[0] > 9 {
+        formatted_counts + ","
+    }
+    else {
+        formatted_counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_parsed_and_formatted() {
+        let rule = Rule::parse("b36/s23").unwrap();
+        assert_eq!(rule, Rule::new(vec![3, 6], vec![2, 3]));
+        assert_eq!(Rule::parse(" S23 / B36 ").unwrap(), rule);
+        assert_eq!(rule.to_rule_string(), "B36/S23");
+        assert_eq!(Rule::parse("B/S").unwrap().to_rule_string(), "B/S");
+        for invalid_rule_string in ["B3", "B3/S23/S4", "B3/B4", "X3/S23", "B3a/S23", "B4,x/S3"] {
+            assert!(Rule::parse(invalid_rule_string).is_err(), "{invalid_rule_string}");
+        }
+    }
+
+    #[test]
+    fn counts_above_9_round_trip() {
+        let rule = Rule::parse("B4,5/S3,4,10").unwrap();
+        assert_eq!(rule, Rule::new(vec![4, 5], vec![3, 4, 10]));
+        assert_eq!(rule.to_rule_string(), "B45/S3,4,10");
+        assert_eq!(Rule::parse(&rule.to_rule_string()).unwrap(), rule);
+        assert_eq!(rule.get_next_cell_state(CellState::Alive, 10), CellState::Alive);
+
+        let rule = Rule::new(vec![12], vec![1, 0]);
+        assert_eq!(rule.to_rule_string(), "B12,/S01");
+        assert_eq!(Rule::parse(&rule.to_rule_string()).unwrap(), rule);
+    }
+}
@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+use super::multi_state_field::MultiStateField;
+
+pub const WIREWORLD_RULE: &str = include_str!("../../../assets/rules/WireWorld.rule");
+pub const BRIANS_BRAIN_RULE: &str = include_str!("../../../assets/rules/BriansBrain.rule");
+
+// Neighbour order used by Golly rule tables: N, NE, E, SE, S, SW, W, NW (Moore) or N, E, S, W (von Neumann)
+const MOORE_NEIGHBOUR_OFFSETS: [(isize, isize); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+const VON_NEUMANN_NEIGHBOUR_OFFSETS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Neighbours in the largest neighbourhood, Moore's
+const MAX_NEIGHBOURS: usize = 8;
+
+/// Center state followed by the neighbour states; the neighbourhood fixes how many are used, the others stay 0
+type CellKey = [u8; 1 + MAX_NEIGHBOURS];
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum RuleTableNeighbourhood {
+    Moore,
+    VonNeumann
+}
+
+impl RuleTableNeighbourhood {
+    pub fn get_neighbour_offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            RuleTableNeighbourhood::Moore => &MOORE_NEIGHBOUR_OFFSETS,
+            RuleTableNeighbourhood::VonNeumann => &VON_NEUMANN_NEIGHBOUR_OFFSETS,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+enum Symmetries {
+    None,
+    ReflectHorizontal,
+    Rotate4,
+    Rotate4Reflect,
+    Rotate8,
+    Rotate8Reflect,
+    Permute
+}
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+enum TransitionTerm {
+    State(u8),
+    /// Index into `RuleTable::variables`; all the occurrences of a variable within a transition are bound to the same state
+    Variable(usize)
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Transition {
+    center: TransitionTerm,
+    neighbours: Vec<TransitionTerm>,
+    output: TransitionTerm
+}
+
+/// A multi-state rule loaded from the @TABLE section of a Golly `.rule` file, together with its @COLORS.
+/// Cells for which no transition matches keep their state, as in Golly.
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RuleTable {
+    name: String,
+    n_states: usize,
+    neighbourhood: RuleTableNeighbourhood,
+    symmetries: Symmetries,
+    variables: Vec<Vec<u8>>,
+    transitions: Vec<Transition>,
+    colors: Vec<[u8; 3]>,
+
+    /// Neighbour orderings to try each transition with, according to `symmetries` (unused for `Symmetries::Permute`)
+    #[serde(skip)]
+    symmetric_orderings: Vec<Vec<usize>>,
+
+    /// Results of previous lookups
+    #[serde(skip)]
+    cache: HashMap<CellKey, u8>
+}
+
+impl RuleTable {
+    pub fn parse(rule_text: &str) -> Result<RuleTable, String> {
+        let mut name = String::from("Unnamed");
+        let mut table_lines: Vec<(usize, &str)> = Vec::new();
+        let mut color_lines: Vec<(usize, &str)> = Vec::new();
+        let mut has_tree_section = false;
+        let mut current_section = "";
+
+        for (index, line) in rule_text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('@') {
+                let mut words = line.split_whitespace();
+                current_section = words.next().unwrap_or("");
+                if current_section == "@RULE" {
+                    if let Some(rule_name) = words.next() {
+                        name = String::from(rule_name);
+                    }
+                }
+                has_tree_section |= current_section == "@TREE";
+                continue;
+            }
+            match current_section {
+                "@TABLE" => table_lines.push((line_number, line)),
+                "@COLORS" => color_lines.push((line_number, line)),
+                _ => {}
+            }
+        }
+
+        if table_lines.is_empty() {
+            if has_tree_section {
+                return Err(String::from("Only rules with a @TABLE section are supported, this one only has a @TREE"));
+            }
+            return Err(String::from("The rule has no @TABLE section"));
+        }
+
+        let mut rule_table = parse_table(name, &table_lines)?;
+        rule_table.colors = parse_colors(&color_lines, rule_table.n_states)?;
+        Ok(rule_table)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_n_states(&self) -> usize {
+        self.n_states
+    }
+
+    pub fn get_state_color(&self, state: u8) -> [u8; 3] {
+        self.colors.get(state as usize).copied().unwrap_or([0, 0, 0])
+    }
+
+    pub fn get_next_cell_state(&mut self, center: u8, neighbours: &[u8]) -> u8 {
+        let mut key: CellKey = [0; 1 + MAX_NEIGHBOURS];
+        key[0] = center;
+        key[1..=neighbours.len()].copy_from_slice(neighbours);
+        if let Some(next_state) = self.cache.get(&key) {
+            return *next_state;
+        }
+
+        if self.symmetric_orderings.is_empty() {
+            self.symmetric_orderings = get_symmetric_orderings(self.symmetries, neighbours.len());
+        }
+        let next_state = self.transitions.iter()
+            .find_map(|transition| self.match_transition(transition, center, neighbours))
+            .unwrap_or(center);
+        self.cache.insert(key, next_state);
+        next_state
+    }
+
+    pub fn advance_generation(&mut self, field: &MultiStateField, consider_extremes_adjacent: bool) -> MultiStateField {
+        let offsets = self.neighbourhood.get_neighbour_offsets();
+        let mut next_field = field.clone();
+        let mut neighbours: [u8; MAX_NEIGHBOURS] = [0; MAX_NEIGHBOURS];
+        let neighbours = &mut neighbours[..offsets.len()];
+        for i in 0..field.get_width() {
+            for j in 0..field.get_height() {
+                field.get_neighbour_states(i, j, offsets, consider_extremes_adjacent, neighbours);
+                let next_state = self.get_next_cell_state(field.get_cell_state(i, j), neighbours);
+                next_field.set_cell_state(i, j, next_state);
+            }
+        }
+        next_field
+    }
+
+    fn match_transition(&self, transition: &Transition, center: u8, neighbours: &[u8]) -> Option<u8> {
+        let mut bindings: Vec<Option<u8>> = vec![None; self.variables.len()];
+        if !self.match_term(transition.center, center, &mut bindings) {
+            return None;
+        }
+
+        let matched = if self.symmetries == Symmetries::Permute {
+            self.match_permuted(&transition.neighbours, neighbours, &mut vec![false; neighbours.len()], &mut bindings)
+        }
+        else {
+            let center_bindings = bindings.clone();
+            self.symmetric_orderings.iter().any(|ordering| {
+                bindings.clone_from(&center_bindings);
+                ordering.iter().zip(&transition.neighbours)
+                    .all(|(neighbour_index, term)| self.match_term(*term, neighbours[*neighbour_index], &mut bindings))
+            })
+        };
+
+        if !matched {
+            return None;
+        }
+        match transition.output {
+            TransitionTerm::State(state) => Some(state),
+            TransitionTerm::Variable(variable) => bindings[variable],
+        }
+    }
+
+    /// Tries to assign each of the (unused) neighbours to one of the terms, in any order
+    fn match_permuted(&self, terms: &[TransitionTerm], neighbours: &[u8], used: &mut Vec<bool>, bindings: &mut Vec<Option<u8>>) -> bool {
+        let Some((term, remaining_terms)) = terms.split_first() else {
+            return true;
+        };
+        for neighbour_index in 0..neighbours.len() {
+            if used[neighbour_index] {
+                continue;
+            }
+            // neighbours in the same state are interchangeable, so only the first unused one needs to be tried
+            let is_repeated_state = (0..neighbour_index).any(|other| !used[other] && neighbours[other] == neighbours[neighbour_index]);
+            if is_repeated_state {
+                continue;
+            }
+            let previous_bindings = bindings.clone();
+            if self.match_term(*term, neighbours[neighbour_index], bindings) {
+                used[neighbour_index] = true;
+                if self.match_permuted(remaining_terms, neighbours, used, bindings) {
+                    return true;
+                }
+                used[neighbour_index] = false;
+            }
+            bindings.clone_from(&previous_bindings);
+        }
+        false
+    }
+
+    fn match_term(&self, term: TransitionTerm, state: u8, bindings: &mut [Option<u8>]) -> bool {
+        match term {
+            TransitionTerm::State(term_state) => term_state == state,
+            TransitionTerm::Variable(variable) => {
+                match bindings[variable] {
+                    Some(bound_state) => bound_state == state,
+                    None => {
+                        if self.variables[variable].contains(&state) {
+                            bindings[variable] = Some(state);
+                            true
+                        }
+                        else {
+                            false
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_table(name: String, table_lines: &[(usize, &str)]) -> Result<RuleTable, String> {
+    let mut n_states: Option<usize> = None;
+    let mut neighbourhood: Option<RuleTableNeighbourhood> = None;
+    let mut symmetries = Symmetries::None;
+    let mut variable_names: Vec<String> = Vec::new();
+    let mut variables: Vec<Vec<u8>> = Vec::new();
+    let mut transitions: Vec<Transition> = Vec::new();
+
+    for (line_number, line) in table_lines {
+        let line_error = |message: &str| String::from("Line ") + &line_number.to_string() + ": " + message;
+
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" | "num_states" => {
+                    let parsed_n_states = value.parse::<usize>().map_err(|_| line_error("invalid number of states"))?;
+                    if !(2..=256).contains(&parsed_n_states) {
+                        return Err(line_error("the number of states must be between 2 and 256"));
+                    }
+                    n_states = Some(parsed_n_states);
+                }
+                "neighborhood" | "neighbourhood" => {
+                    neighbourhood = Some(match value {
+                        "Moore" => RuleTableNeighbourhood::Moore,
+                        "vonNeumann" => RuleTableNeighbourhood::VonNeumann,
+                        _ => return Err(line_error(&(String::from("unsupported neighbourhood \"") + value + "\", only Moore and vonNeumann are supported"))),
+                    });
+                }
+                "symmetries" => {
+                    symmetries = match value {
+                        "none" => Symmetries::None,
+                        "reflect_horizontal" => Symmetries::ReflectHorizontal,
+                        "rotate4" => Symmetries::Rotate4,
+                        "rotate4reflect" => Symmetries::Rotate4Reflect,
+                        "rotate8" => Symmetries::Rotate8,
+                        "rotate8reflect" => Symmetries::Rotate8Reflect,
+                        "permute" => Symmetries::Permute,
+                        _ => return Err(line_error(&(String::from("unsupported symmetries \"") + value + "\""))),
+                    };
+                }
+                _ => return Err(line_error(&(String::from("unknown setting \"") + key.trim() + "\""))),
+            }
+            continue;
+        }
+
+        let n_states = n_states.ok_or_else(|| line_error("n_states must be given before variables and transitions"))?;
+        let neighbourhood = neighbourhood.ok_or_else(|| line_error("neighborhood must be given before variables and transitions"))?;
+
+        if let Some(variable_definition) = line.strip_prefix("var ") {
+            let (variable_name, values) = variable_definition.split_once('=')
+                .ok_or_else(|| line_error("expected a variable definition like var a={0,1,2}"))?;
+            let values = values.trim().strip_prefix('{').and_then(|values| values.strip_suffix('}'))
+                .ok_or_else(|| line_error("variable values must be enclosed in braces"))?;
+            let mut states: Vec<u8> = Vec::new();
+            for value in values.split(',') {
+                match parse_term(value.trim(), n_states, &variable_names) {
+                    Some(TransitionTerm::State(state)) => states.push(state),
+                    Some(TransitionTerm::Variable(variable)) => states.extend_from_slice(&variables[variable]),
+                    None => return Err(line_error(&(String::from("invalid state or variable \"") + value.trim() + "\""))),
+                }
+            }
+            states.sort_unstable();
+            states.dedup();
+            // a redefined variable shadows the previous definition for the transitions that follow
+            variable_names.push(String::from(variable_name.trim()));
+            variables.push(states);
+            continue;
+        }
+
+        let tokens: Vec<String> = if line.contains(',') {
+            line.split(',').map(|token| String::from(token.trim())).collect()
+        }
+        else if n_states <= 10 {
+            // compact form, one digit per state, e.g. "0123456781"
+            line.chars().filter(|c| !c.is_whitespace()).map(String::from).collect()
+        }
+        else {
+            return Err(line_error("transitions must be comma-separated when there are more than 10 states"));
+        };
+
+        let n_neighbours = neighbourhood.get_neighbour_offsets().len();
+        if tokens.len() != n_neighbours + 2 {
+            return Err(line_error(&(String::from("expected ") + &(n_neighbours + 2).to_string() + " terms in the transition, found " + &tokens.len().to_string())));
+        }
+        let mut terms: Vec<TransitionTerm> = Vec::new();
+        for token in &tokens {
+            let term = parse_term(token, n_states, &variable_names)
+                .ok_or_else(|| line_error(&(String::from("invalid state or variable \"") + token + "\"")))?;
+            terms.push(term);
+        }
+        let output = terms.pop().unwrap_or(TransitionTerm::State(0));
+        if let TransitionTerm::Variable(output_variable) = output {
+            let is_bound = terms.iter().any(|term| matches!(term, TransitionTerm::Variable(variable) if *variable == output_variable));
+            if !is_bound {
+                return Err(line_error("the output variable must also appear in the inputs"));
+            }
+        }
+        transitions.push(Transition{center: terms[0], neighbours: terms[1..].to_vec(), output});
+    }
+
+    let n_states = n_states.ok_or("The @TABLE section doesn't specify n_states")?;
+    let neighbourhood = neighbourhood.ok_or("The @TABLE section doesn't specify the neighborhood")?;
+    let is_rotate8 = matches!(symmetries, Symmetries::Rotate8 | Symmetries::Rotate8Reflect);
+    if is_rotate8 && neighbourhood == RuleTableNeighbourhood::VonNeumann {
+        return Err(String::from("rotate8 symmetries require the Moore neighbourhood"));
+    }
+
+    Ok(RuleTable{
+        name,
+        n_states,
+        neighbourhood,
+        symmetries,
+        variables,
+        transitions,
+        colors: Vec::new(),
+        symmetric_orderings: Vec::new(),
+        cache: HashMap::new()
+    })
+}
+
+fn parse_term(token: &str, n_states: usize, variable_names: &[String]) -> Option<TransitionTerm> {
+    if let Ok(state) = token.parse::<usize>() {
+        return if state < n_states {Some(TransitionTerm::State(state as u8))} else {None};
+    }
+    // the most recent definition of a variable wins
+    variable_names.iter().rposition(|name| name == token).map(TransitionTerm::Variable)
+}
+
+/// Either "state r g b" or "first_state last_state r1 g1 b1 r2 g2 b2" for a gradient
+fn parse_colors(color_lines: &[(usize, &str)], n_states: usize) -> Result<Vec<[u8; 3]>, String> {
+    let mut colors = get_default_colors(n_states);
+    for (line_number, line) in color_lines {
+        let line_error = String::from("Line ") + &line_number.to_string() + ": invalid @COLORS entry";
+        let numbers: Vec<usize> = line.split_whitespace()
+            .map(|number| number.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| line_error.clone())?;
+        let color_component = |index: usize| -> Result<u8, String> {
+            u8::try_from(numbers[index]).map_err(|_| line_error.clone())
+        };
+        match numbers.len() {
+            4 => {
+                if numbers[0] < n_states {
+                    colors[numbers[0]] = [color_component(1)?, color_component(2)?, color_component(3)?];
+                }
+            }
+            8 => {
+                let first_color = [color_component(2)?, color_component(3)?, color_component(4)?];
+                let last_color = [color_component(5)?, color_component(6)?, color_component(7)?];
+                let (first_state, last_state) = (numbers[0], numbers[1].min(n_states - 1));
+                for (state, color) in colors.iter_mut().enumerate().take(last_state + 1).skip(first_state) {
+                    let t = if last_state > first_state {(state - first_state) as f32 / (last_state - first_state) as f32} else {0.0};
+                    *color = interpolate_color(first_color, last_color, t);
+                }
+            }
+            _ => return Err(line_error),
+        }
+    }
+    Ok(colors)
+}
+
+/// Like Golly: black background, and a red to yellow gradient for the other states
+fn get_default_colors(n_states: usize) -> Vec<[u8; 3]> {
+    let mut colors = vec![[0, 0, 0]];
+    for state in 1..n_states {
+        let t = if n_states > 2 {(state - 1) as f32 / (n_states - 2) as f32} else {0.0};
+        colors.push(interpolate_color([255, 0, 0], [255, 255, 0], t));
+    }
+    colors
+}
+
+fn interpolate_color(first_color: [u8; 3], last_color: [u8; 3], t: f32) -> [u8; 3] {
+    let component = |index: usize| (first_color[index] as f32 + (last_color[index] as f32 - first_color[index] as f32) * t).round() as u8;
+    [component(0), component(1), component(2)]
+}
+
+/// Neighbour orderings under which a transition also applies, as indices into the neighbours
+fn get_symmetric_orderings(symmetries: Symmetries, n_neighbours: usize) -> Vec<Vec<usize>> {
+    let rotate = |ordering: &Vec<usize>, steps: usize| -> Vec<usize> {
+        (0..n_neighbours).map(|index| ordering[(index + steps) % n_neighbours]).collect()
+    };
+    // mirrors left and right, keeping N (and S) in place
+    let reflect = |ordering: &Vec<usize>| -> Vec<usize> {
+        (0..n_neighbours).map(|index| ordering[(n_neighbours - index) % n_neighbours]).collect()
+    };
+    let identity: Vec<usize> = (0..n_neighbours).collect();
+    // a quarter turn is 2 neighbours for the Moore neighbourhood but only 1 for von Neumann
+    let quarter_turn = n_neighbours / 4;
+
+    let mut orderings: Vec<Vec<usize>> = match symmetries {
+        Symmetries::None | Symmetries::Permute => vec![identity.clone()],
+        Symmetries::ReflectHorizontal => vec![identity.clone(), reflect(&identity)],
+        Symmetries::Rotate4 | Symmetries::Rotate4Reflect => (0..4).map(|turns| rotate(&identity, turns * quarter_turn)).collect(),
+        Symmetries::Rotate8 | Symmetries::Rotate8Reflect => (0..n_neighbours).map(|steps| rotate(&identity, steps)).collect(),
+    };
+    if matches!(symmetries, Symmetries::Rotate4Reflect | Symmetries::Rotate8Reflect) {
+        let reflected_orderings: Vec<Vec<usize>> = orderings.iter().map(reflect).collect();
+        orderings.extend(reflected_orderings);
+    }
+    orderings.sort();
+    orderings.dedup();
+    orderings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_bundled_rules() {
+        let wireworld = RuleTable::parse(WIREWORLD_RULE).unwrap();
+        assert_eq!(wireworld.get_n_states(), 4);
+        let brians_brain = RuleTable::parse(BRIANS_BRAIN_RULE).unwrap();
+        assert_eq!(brians_brain.get_n_states(), 3);
+    }
+
+    #[test]
+    fn moves_an_electron_along_a_wire() {
+        let mut wireworld = RuleTable::parse(WIREWORLD_RULE).unwrap();
+        let mut field = MultiStateField::new(6, 3);
+        for (x, state) in [2, 1, 3, 3, 3, 3].into_iter().enumerate() {
+            field.set_cell_state(x, 1, state);
+        }
+        let field = wireworld.advance_generation(&field, false);
+        let wire: Vec<u8> = (0..6).map(|x| field.get_cell_state(x, 1)).collect();
+        assert_eq!(wire, vec![3, 2, 1, 3, 3, 3]);
+    }
+
+    #[test]
+    fn applies_symmetries_and_variables() {
+        let rule = "@RULE Test\n@TABLE\nn_states:3\nneighborhood:vonNeumann\nsymmetries:rotate4\nvar a={1,2}\n0,a,0,0,0,a\n";
+        let mut rule_table = RuleTable::parse(rule).unwrap();
+        assert_eq!(rule_table.get_next_cell_state(0, &[0, 0, 2, 0]), 2);
+        assert_eq!(rule_table.get_next_cell_state(0, &[0, 1, 1, 0]), 0);
+        assert_eq!(rule_table.get_next_cell_state(1, &[0, 0, 0, 0]), 1);
+    }
+
+    #[test]
+    fn rejects_invalid_tables() {
+        assert!(RuleTable::parse("@RULE Empty\n").is_err());
+        assert!(RuleTable::parse("@TABLE\nn_states:1\nneighborhood:Moore\n").is_err());
+        assert!(RuleTable::parse("@TABLE\nn_states:2\nneighborhood:hexagonal\n").is_err());
+        assert!(RuleTable::parse("@TABLE\nn_states:2\nneighborhood:vonNeumann\n0,1,0,0,0\n").is_err());
+        assert!(RuleTable::parse("@TABLE\nn_states:2\nneighborhood:vonNeumann\n0,1,0,0,0,5\n").is_err());
+    }
+}
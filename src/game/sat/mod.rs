@@ -0,0 +1,420 @@
+/// A literal: a variable (numbered from 0) or its negation
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub struct Literal {
+    pub variable: usize,
+    pub is_negated: bool
+}
+
+impl Literal {
+    pub fn positive(variable: usize) -> Literal {
+        Literal{variable, is_negated: false}
+    }
+
+    pub fn negative(variable: usize) -> Literal {
+        Literal{variable, is_negated: true}
+    }
+
+    /// Literals are stored as 2 * variable (+ 1 if negated), so a literal and its negation differ in the last bit
+    fn get_code(&self) -> usize {
+        2 * self.variable + self.is_negated as usize
+    }
+}
+
+pub enum SatResult {
+    /// A value for each variable satisfying every clause
+    Satisfiable(Vec<bool>),
+    Unsatisfiable,
+    /// The conflict limit was reached before an answer was found
+    Unknown
+}
+
+const UNASSIGNED: i8 = 0;
+const TRUE: i8 = 1;
+const FALSE: i8 = -1;
+
+/// A small conflict-driven clause learning solver: two watched literals for unit propagation,
+/// first-UIP learning with non-chronological backjumping, activity-based decisions with phase saving, and restarts.
+/// Clauses can be added between calls to `solve` (e.g. to block a solution and look for another one).
+pub struct SatSolver {
+    clauses: Vec<Vec<usize>>,
+    /// Clauses watching each literal code; the watched literals of a clause are its first two
+    watches: Vec<Vec<usize>>,
+    unit_literals: Vec<usize>,
+    values: Vec<i8>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<usize>,
+    trail_level_starts: Vec<usize>,
+    propagation_head: usize,
+    activities: Vec<f64>,
+    activity_increment: f64,
+    saved_phases: Vec<bool>,
+    is_unsatisfiable: bool
+}
+
+impl SatSolver {
+    pub fn new(n_variables: usize) -> SatSolver {
+        SatSolver {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * n_variables],
+            unit_literals: Vec::new(),
+            values: vec![UNASSIGNED; n_variables],
+            levels: vec![0; n_variables],
+            reasons: vec![None; n_variables],
+            trail: Vec::new(),
+            trail_level_starts: Vec::new(),
+            propagation_head: 0,
+            activities: vec![0.0; n_variables],
+            activity_increment: 1.0,
+            saved_phases: vec![false; n_variables],
+            is_unsatisfiable: false
+        }
+    }
+
+    pub fn add_clause(&mut self, literals: &[Literal]) {
+        self.unassign_all();
+        let mut codes: Vec<usize> = literals.iter().map(|literal| literal.get_code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        if codes.windows(2).any(|pair| pair[0] ^ 1 == pair[1]) {
+            return; // contains a literal and its negation, so it always holds
+        }
+        match codes.len() {
+            0 => self.is_unsatisfiable = true,
+            1 => self.unit_literals.push(codes[0]),
+            _ => {
+                self.add_watched_clause(codes);
+            }
+        }
+    }
+
+    /// Looks for an assignment satisfying every clause, giving up after `conflict_limit` conflicts
+    pub fn solve(&mut self, conflict_limit: usize) -> SatResult {
+        self.unassign_all();
+        if self.is_unsatisfiable {
+            return SatResult::Unsatisfiable;
+        }
+        for index in 0..self.unit_literals.len() {
+            let code = self.unit_literals[index];
+            match self.get_literal_value(code) {
+                FALSE => {
+                    self.is_unsatisfiable = true;
+                    return SatResult::Unsatisfiable;
+                }
+                UNASSIGNED => self.enqueue(code, None),
+                _ => (),
+            }
+        }
+
+        let mut n_conflicts: usize = 0;
+        let mut n_conflicts_until_restart: f64 = 100.0;
+        let mut n_conflicts_since_restart: usize = 0;
+        loop {
+            if let Some(conflicting_clause) = self.propagate() {
+                n_conflicts += 1;
+                n_conflicts_since_restart += 1;
+                if self.trail_level_starts.is_empty() {
+                    self.is_unsatisfiable = true;
+                    return SatResult::Unsatisfiable;
+                }
+                let (learnt_clause, backjump_level) = self.analyze(conflicting_clause);
+                self.backtrack(backjump_level);
+                let asserting_literal = learnt_clause[0];
+                if learnt_clause.len() == 1 {
+                    self.unit_literals.push(asserting_literal);
+                    self.enqueue(asserting_literal, None);
+                }
+                else {
+                    let clause_index = self.add_watched_clause(learnt_clause);
+                    self.enqueue(asserting_literal, Some(clause_index));
+                }
+                self.activity_increment /= 0.95;
+                if n_conflicts >= conflict_limit {
+                    self.backtrack(0);
+                    return SatResult::Unknown;
+                }
+                if n_conflicts_since_restart as f64 >= n_conflicts_until_restart {
+                    n_conflicts_since_restart = 0;
+                    n_conflicts_until_restart *= 1.5;
+                    self.backtrack(0);
+                }
+            }
+            else {
+                match self.pick_branching_variable() {
+                    Some(variable) => {
+                        self.trail_level_starts.push(self.trail.len());
+                        let literal = if self.saved_phases[variable] {Literal::positive(variable)} else {Literal::negative(variable)};
+                        self.enqueue(literal.get_code(), None);
+                    }
+                    None => {
+                        let model: Vec<bool> = self.values.iter().map(|value| *value == TRUE).collect();
+                        self.backtrack(0);
+                        return SatResult::Satisfiable(model);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_watched_clause(&mut self, codes: Vec<usize>) -> usize {
+        let clause_index = self.clauses.len();
+        self.watches[codes[0]].push(clause_index);
+        self.watches[codes[1]].push(clause_index);
+        self.clauses.push(codes);
+        clause_index
+    }
+
+    fn get_literal_value(&self, code: usize) -> i8 {
+        let value = self.values[code / 2];
+        if code % 2 == 1 {-value} else {value}
+    }
+
+    fn get_decision_level(&self) -> usize {
+        self.trail_level_starts.len()
+    }
+
+    fn enqueue(&mut self, code: usize, reason: Option<usize>) {
+        let variable = code / 2;
+        self.values[variable] = if code % 2 == 1 {FALSE} else {TRUE};
+        self.levels[variable] = self.get_decision_level();
+        self.reasons[variable] = reason;
+        self.trail.push(code);
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.get_decision_level() <= level {
+            return;
+        }
+        let level_start = self.trail_level_starts[level];
+        for code in self.trail.drain(level_start..) {
+            let variable = code / 2;
+            self.saved_phases[variable] = code % 2 == 0;
+            self.values[variable] = UNASSIGNED;
+            self.reasons[variable] = None;
+        }
+        self.trail_level_starts.truncate(level);
+        self.propagation_head = self.propagation_head.min(self.trail.len());
+    }
+
+    /// Also undoes the assignments of level 0, so clauses added afterwards get their watches right
+    fn unassign_all(&mut self) {
+        self.backtrack(0);
+        for code in self.trail.drain(..) {
+            self.values[code / 2] = UNASSIGNED;
+            self.reasons[code / 2] = None;
+        }
+        self.propagation_head = 0;
+    }
+
+    /// Unit propagation over the watched literals; returns the index of a clause with every literal false, if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagation_head < self.trail.len() {
+            let false_code = self.trail[self.propagation_head] ^ 1;
+            self.propagation_head += 1;
+            let watching_clauses = std::mem::take(&mut self.watches[false_code]);
+            let mut kept_watches: Vec<usize> = Vec::with_capacity(watching_clauses.len());
+            let mut conflict: Option<usize> = None;
+            for (position, clause_index) in watching_clauses.iter().enumerate() {
+                let clause_index = *clause_index;
+                if conflict.is_some() {
+                    kept_watches.extend_from_slice(&watching_clauses[position..]);
+                    break;
+                }
+                let clause = &mut self.clauses[clause_index];
+                if clause[0] == false_code {
+                    clause.swap(0, 1);
+                }
+                let other_watched_code = clause[0];
+                if self.get_literal_value(other_watched_code) == TRUE {
+                    kept_watches.push(clause_index);
+                    continue;
+                }
+                let clause = &self.clauses[clause_index];
+                let new_watch = (2..clause.len()).find(|index| self.get_literal_value(clause[*index]) != FALSE);
+                match new_watch {
+                    Some(index) => {
+                        let clause = &mut self.clauses[clause_index];
+                        clause.swap(1, index);
+                        let new_watched_code = clause[1];
+                        self.watches[new_watched_code].push(clause_index);
+                    }
+                    None => {
+                        kept_watches.push(clause_index);
+                        if self.get_literal_value(other_watched_code) == FALSE {
+                            conflict = Some(clause_index);
+                        }
+                        else {
+                            self.enqueue(other_watched_code, Some(clause_index));
+                        }
+                    }
+                }
+            }
+            self.watches[false_code] = kept_watches;
+            if conflict.is_some() {
+                self.propagation_head = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// First-UIP conflict analysis: returns the learnt clause (asserting literal first, then
+    /// a literal of the backjump level) and the level to backjump to
+    fn analyze(&mut self, conflicting_clause: usize) -> (Vec<usize>, usize) {
+        let decision_level = self.get_decision_level();
+        let mut seen: Vec<bool> = vec![false; self.values.len()];
+        let mut learnt_clause: Vec<usize> = vec![0];
+        let mut n_pending_at_decision_level: usize = 0;
+        let mut resolved_code: Option<usize> = None;
+        let mut clause_index = conflicting_clause;
+        let mut trail_index = self.trail.len();
+        loop {
+            for position in 0..self.clauses[clause_index].len() {
+                let code = self.clauses[clause_index][position];
+                if Some(code) == resolved_code {
+                    continue;
+                }
+                let variable = code / 2;
+                if seen[variable] || self.levels[variable] == 0 {
+                    continue;
+                }
+                seen[variable] = true;
+                self.bump_activity(variable);
+                if self.levels[variable] == decision_level {
+                    n_pending_at_decision_level += 1;
+                }
+                else {
+                    learnt_clause.push(code);
+                }
+            }
+            loop {
+                trail_index -= 1;
+                if seen[self.trail[trail_index] / 2] {
+                    break;
+                }
+            }
+            let code = self.trail[trail_index];
+            seen[code / 2] = false;
+            resolved_code = Some(code);
+            n_pending_at_decision_level -= 1;
+            if n_pending_at_decision_level == 0 {
+                break;
+            }
+            clause_index = self.reasons[code / 2].expect("Implied literals have a reason clause");
+        }
+        learnt_clause[0] = resolved_code.expect("The conflict involves the decision level") ^ 1;
+
+        let mut backjump_level: usize = 0;
+        for position in 1..learnt_clause.len() {
+            let level = self.levels[learnt_clause[position] / 2];
+            if level > backjump_level {
+                backjump_level = level;
+                learnt_clause.swap(1, position);
+            }
+        }
+        (learnt_clause, backjump_level)
+    }
+
+    fn bump_activity(&mut self, variable: usize) {
+        self.activities[variable] += self.activity_increment;
+        if self.activities[variable] > 1e100 {
+            for activity in self.activities.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.activity_increment *= 1e-100;
+        }
+    }
+
+    fn pick_branching_variable(&self) -> Option<usize> {
+        let mut best_variable: Option<usize> = None;
+        for variable in 0..self.values.len() {
+            if self.values[variable] != UNASSIGNED {
+                continue;
+            }
+            if best_variable.map_or(true, |best| self.activities[variable] > self.activities[best]) {
+                best_variable = Some(variable);
+            }
+        }
+        best_variable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_satisfied(clauses: &[Vec<Literal>], model: &[bool]) -> bool {
+        clauses.iter().all(|clause| clause.iter().any(|literal| model[literal.variable] != literal.is_negated))
+    }
+
+    /// Clauses saying that each pigeon gets one of the holes to itself, unsatisfiable with more pigeons than holes;
+    /// variable `pigeon * n_holes + hole` puts the pigeon in the hole
+    fn get_pigeonhole_solver(n_pigeons: usize, n_holes: usize) -> SatSolver {
+        let mut solver = SatSolver::new(n_pigeons * n_holes);
+        for pigeon in 0..n_pigeons {
+            let clause: Vec<Literal> = (0..n_holes).map(|hole| Literal::positive(pigeon * n_holes + hole)).collect();
+            solver.add_clause(&clause);
+        }
+        for hole in 0..n_holes {
+            for first_pigeon in 0..n_pigeons {
+                for second_pigeon in first_pigeon + 1..n_pigeons {
+                    solver.add_clause(&[Literal::negative(first_pigeon * n_holes + hole), Literal::negative(second_pigeon * n_holes + hole)]);
+                }
+            }
+        }
+        solver
+    }
+
+    #[test]
+    fn finds_every_solution() {
+        // exactly one of the 3 variables is true
+        let clauses = vec![
+            vec![Literal::positive(0), Literal::positive(1), Literal::positive(2)],
+            vec![Literal::negative(0), Literal::negative(1)],
+            vec![Literal::negative(0), Literal::negative(2)],
+            vec![Literal::negative(1), Literal::negative(2)]
+        ];
+        let mut solver = SatSolver::new(3);
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+        let mut n_solutions = 0;
+        while let SatResult::Satisfiable(model) = solver.solve(1000) {
+            assert!(is_satisfied(&clauses, &model));
+            let blocking_clause: Vec<Literal> = model.iter().enumerate()
+                .map(|(variable, value)| if *value {Literal::negative(variable)} else {Literal::positive(variable)})
+                .collect();
+            solver.add_clause(&blocking_clause);
+            n_solutions += 1;
+        }
+        assert_eq!(n_solutions, 3);
+        assert!(matches!(solver.solve(1000), SatResult::Unsatisfiable));
+    }
+
+    #[test]
+    fn proves_the_pigeonhole_principle() {
+        let mut solver = get_pigeonhole_solver(5, 4);
+        assert!(matches!(solver.solve(1_000_000), SatResult::Unsatisfiable));
+    }
+
+    #[test]
+    fn handles_trivial_formulas() {
+        let mut solver = SatSolver::new(2);
+        solver.add_clause(&[Literal::positive(0), Literal::negative(0)]);
+        solver.add_clause(&[Literal::negative(1)]);
+        let SatResult::Satisfiable(model) = solver.solve(10) else {
+            panic!("the formula is satisfiable");
+        };
+        assert!(!model[1]);
+        solver.add_clause(&[]);
+        assert!(matches!(solver.solve(10), SatResult::Unsatisfiable));
+    }
+
+    #[test]
+    fn gives_up_at_the_conflict_limit() {
+        let mut solver = get_pigeonhole_solver(9, 8);
+        assert!(matches!(solver.solve(10), SatResult::Unknown));
+    }
+}
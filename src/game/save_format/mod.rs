@@ -0,0 +1,302 @@
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use super::Game;
+
+/// Version of the layout written by `save_game`
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// Version of the saves of the bare game, before `save_game`: they have no version, and the cells of their fields
+/// written out one enum variant per cell, which the fields still read
+const UNVERSIONED_SAVE_FORMAT: u32 = 0;
+
+/// Largest field read back, in cells, so that a damaged save can't make us allocate without bound
+const MAX_SAVED_CELLS: usize = 1 << 28;
+
+/// A game with the version of the layout it was written with
+#[derive(serde::Serialize)]
+struct SavedGameRef<'a> {
+    version: u32,
+    game: &'a Game
+}
+
+#[derive(serde::Deserialize)]
+struct SavedGame {
+    game: Game
+}
+
+/// Just the version of a save, so it can be checked before reading the rest
+#[derive(serde::Deserialize)]
+struct SaveVersion {
+    #[serde(default)]
+    version: u32
+}
+
+/// Writes the game in the current save format
+pub fn save_game(game: &Game) -> Result<String, String> {
+    ron::to_string(&SavedGameRef{version: SAVE_FORMAT_VERSION, game})
+        .map_err(|error| String::from("Couldn't save the game: ") + &error.to_string())
+}
+
+/// Reads a game written by `save_game`, or by the versions of rusty-life that saved the bare game, checking that its
+/// cells match its dimensions
+pub fn load_game(text: &str) -> Result<Game, String> {
+    let game = read_game(text)?;
+    game.check_dimensions().map_err(|error| String::from("The save is damaged: ") + &error.to_string())?;
+    Ok(game)
+}
+
+fn read_game(text: &str) -> Result<Game, String> {
+    let version = ron::from_str::<SaveVersion>(text)
+        .map_err(|error| String::from("The save is damaged or isn't a game: ") + &error.to_string())?
+        .version;
+    match version {
+        UNVERSIONED_SAVE_FORMAT => ron::from_str::<Game>(text)
+            .map_err(|error| String::from("Couldn't read the game saved in the layout before versioning: ") + &error.to_string()),
+        SAVE_FORMAT_VERSION => ron::from_str::<SavedGame>(text)
+            .map(|saved_game| saved_game.game)
+            .map_err(|error| String::from("Couldn't read the game (save format ") + &SAVE_FORMAT_VERSION.to_string() + "): " + &error.to_string()),
+        version => Err(String::from("The game was saved in format ") + &version.to_string()
+            + " by a newer version of rusty-life; this one reads formats up to " + &SAVE_FORMAT_VERSION.to_string()),
+    }
+}
+
+/// Deflates the bytes and writes them in base64, after their count
+fn encode_bytes(n_values: usize, bytes: &[u8]) -> String {
+    n_values.to_string() + ":" + &STANDARD.encode(miniz_oxide::deflate::compress_to_vec(bytes, 6))
+}
+
+/// Reads text written by `encode_bytes`, returning the count and the inflated bytes
+fn decode_bytes(text: &str) -> Result<(usize, Vec<u8>), String> {
+    let (n_values, encoded) = text.split_once(':').ok_or_else(|| String::from("Packed cells lack their count"))?;
+    let n_values: usize = n_values.parse().map_err(|_| String::from("Packed cells have an invalid count: ") + n_values)?;
+    if n_values > MAX_SAVED_CELLS {
+        return Err(String::from("Packed cells are too many: ") + &n_values.to_string());
+    }
+    let compressed = STANDARD.decode(encoded).map_err(|error| String::from("Packed cells aren't valid base64: ") + &error.to_string())?;
+    let bytes = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_SAVED_CELLS)
+        .map_err(|_| String::from("Packed cells can't be inflated"))?;
+    Ok((n_values, bytes))
+}
+
+/// Serde of dead/alive cells as one bit each (set if alive, least significant bit first), deflated and in base64;
+/// the list of enum variants of older saves is still read
+pub(crate) mod packed_cells {
+    use serde::de::Error as _;
+    use super::super::field::CellState;
+    use super::{decode_bytes, encode_bytes};
+
+    pub fn serialize<S: serde::Serializer>(cells: &[CellState], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bits: Vec<u8> = vec![0; cells.len().div_ceil(8)];
+        for (index, cell_state) in cells.iter().enumerate() {
+            if *cell_state == CellState::Alive {
+                bits[index / 8] |= 1 << (index % 8);
+            }
+        }
+        serializer.serialize_str(&encode_bytes(cells.len(), &bits))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<CellState>, D::Error> {
+        match deserializer.deserialize_any(super::PackedOrListedVisitor(std::marker::PhantomData))? {
+            super::PackedOrListed::Listed(cells) => Ok(cells),
+            super::PackedOrListed::Packed(text) => {
+                let (n_cells, bits) = decode_bytes(&text).map_err(D::Error::custom)?;
+                if bits.len() != n_cells.div_ceil(8) {
+                    return Err(D::Error::custom("Packed cells don't match their count"));
+                }
+                Ok((0..n_cells).map(|index| if bits[index / 8] & (1 << (index % 8)) != 0 {CellState::Alive} else {CellState::Dead}).collect())
+            }
+        }
+    }
+}
+
+/// Serde of multi-state cells as one byte each, deflated and in base64; the list of states of older saves is still read
+pub(crate) mod packed_states {
+    use serde::de::Error as _;
+    use super::{decode_bytes, encode_bytes};
+
+    pub fn serialize<S: serde::Serializer>(cells: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode_bytes(cells.len(), cells))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        match deserializer.deserialize_any(super::PackedOrListedVisitor(std::marker::PhantomData))? {
+            super::PackedOrListed::Listed(cells) => Ok(cells),
+            super::PackedOrListed::Packed(text) => {
+                let (n_cells, cells) = decode_bytes(&text).map_err(D::Error::custom)?;
+                if cells.len() != n_cells {
+                    return Err(D::Error::custom("Packed cells don't match their count"));
+                }
+                Ok(cells)
+            }
+        }
+    }
+}
+
+/// Serde of continuous cells as four little-endian bytes each, deflated and in base64; the list of levels of older saves is still read
+pub(crate) mod packed_levels {
+    use serde::de::Error as _;
+    use super::{decode_bytes, encode_bytes};
+
+    pub fn serialize<S: serde::Serializer>(cells: &[f32], serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<u8> = cells.iter().flat_map(|level| level.to_le_bytes()).collect();
+        serializer.serialize_str(&encode_bytes(cells.len(), &bytes))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+        match deserializer.deserialize_any(super::PackedOrListedVisitor(std::marker::PhantomData))? {
+            super::PackedOrListed::Listed(cells) => Ok(cells),
+            super::PackedOrListed::Packed(text) => {
+                let (n_cells, bytes) = decode_bytes(&text).map_err(D::Error::custom)?;
+                if bytes.len() != 4 * n_cells {
+                    return Err(D::Error::custom("Packed cells don't match their count"));
+                }
+                Ok(bytes.chunks_exact(4).map(|level| f32::from_le_bytes([level[0], level[1], level[2], level[3]])).collect())
+            }
+        }
+    }
+}
+
+/// Serde of per-cell counters (such as cell ages) as four little-endian bytes each, deflated and in base64
+pub(crate) mod packed_counts {
+    use serde::de::Error as _;
+    use super::{decode_bytes, encode_bytes};
+
+    pub fn serialize<S: serde::Serializer>(counts: &[u32], serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<u8> = counts.iter().flat_map(|count| count.to_le_bytes()).collect();
+        serializer.serialize_str(&encode_bytes(counts.len(), &bytes))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<u32>, D::Error> {
+        match deserializer.deserialize_any(super::PackedOrListedVisitor(std::marker::PhantomData))? {
+            super::PackedOrListed::Listed(counts) => Ok(counts),
+            super::PackedOrListed::Packed(text) => {
+                let (n_counts, bytes) = decode_bytes(&text).map_err(D::Error::custom)?;
+                if bytes.len() != 4 * n_counts {
+                    return Err(D::Error::custom("Packed counts don't match their count"));
+                }
+                Ok(bytes.chunks_exact(4).map(|count| u32::from_le_bytes([count[0], count[1], count[2], count[3]])).collect())
+            }
+        }
+    }
+}
+
+/// Cells as written by the current format (a string) or by older saves (a list)
+enum PackedOrListed<T> {
+    Packed(String),
+    Listed(Vec<T>)
+}
+
+struct PackedOrListedVisitor<T>(std::marker::PhantomData<T>);
+
+impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for PackedOrListedVisitor<T> {
+    type Value = PackedOrListed<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("packed cells or a list of cells")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        Ok(PackedOrListed::Packed(String::from(text)))
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut sequence: A) -> Result<Self::Value, A::Error> {
+        let mut cells: Vec<T> = Vec::new();
+        while let Some(cell) = sequence.next_element()? {
+            cells.push(cell);
+        }
+        Ok(PackedOrListed::Listed(cells))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::field::{CellState, Field};
+
+    #[derive(PartialEq)]
+    #[derive(Debug)]
+    #[derive(serde::Deserialize, serde::Serialize)]
+    struct PackedValues {
+        #[serde(with = "super::packed_cells")]
+        cells: Vec<CellState>,
+        #[serde(with = "super::packed_states")]
+        states: Vec<u8>,
+        #[serde(with = "super::packed_levels")]
+        levels: Vec<f32>,
+        #[serde(with = "super::packed_counts")]
+        counts: Vec<u32>
+    }
+
+    fn get_packed_values() -> PackedValues {
+        PackedValues {
+            cells: (0..37).map(|index| if index % 3 == 0 {CellState::Alive} else {CellState::Dead}).collect(),
+            states: (0..=255).collect(),
+            levels: vec![0.0, 0.25, 1.0, -0.5, f32::MIN_POSITIVE],
+            counts: vec![0, 1, 70_000, u32::MAX]
+        }
+    }
+
+    #[test]
+    fn saves_carry_the_current_version() {
+        let mut game = Game::new(5, 4);
+        game.get_field_mut().set_cell_state(1, 2, CellState::Alive);
+        let text = save_game(&game).unwrap();
+        assert_eq!(ron::from_str::<SaveVersion>(&text).unwrap().version, SAVE_FORMAT_VERSION);
+        let loaded_game = load_game(&text).unwrap();
+        assert_eq!(loaded_game.get_field().get_width(), 5);
+        assert_eq!(loaded_game.get_field().get_cell_state(1, 2), CellState::Alive);
+        assert_eq!(loaded_game.get_field().get_cell_state(2, 1), CellState::Dead);
+    }
+
+    #[test]
+    fn newer_and_damaged_saves_are_rejected() {
+        let text = save_game(&Game::new(3, 3)).unwrap().replacen("version:1", "version:2", 1);
+        assert!(load_game(&text).is_err_and(|error| error.contains("newer version")));
+        assert!(load_game("not a save").is_err());
+    }
+
+    #[test]
+    fn unversioned_saves_with_listed_cells_are_read() {
+        let game = load_game("(field: (width: 2, height: 2, cells: [Alive, Dead, Dead, Alive]), generation: 7)").unwrap();
+        assert_eq!(game.get_generation(), 7);
+        assert_eq!(game.get_field().get_cell_state(0, 0), CellState::Alive);
+        assert_eq!(game.get_field().get_cell_state(1, 0), CellState::Dead);
+        assert_eq!(game.get_field().get_cell_state(1, 1), CellState::Alive);
+    }
+
+    #[test]
+    fn saves_with_missing_cells_are_rejected() {
+        let truncated_cells = encode_bytes(4, &[0]);
+        let text = String::from("(version: 1, game: (field: (width: 10, height: 10, cells: \"") + &truncated_cells + "\")))";
+        assert!(load_game(&text).is_err_and(|error| error.contains("4 cells")));
+        let text = "(field: (width: 2, height: 1, cells: [Alive, Dead]), multi_state_field: (width: 1, height: 2, cells: [0, 1]))";
+        assert!(load_game(text).is_err_and(|error| error.contains("1x2")));
+    }
+
+    #[test]
+    fn packed_values_round_trip() {
+        let values = get_packed_values();
+        let text = ron::to_string(&values).unwrap();
+        assert!(!text.contains('['));
+        assert_eq!(ron::from_str::<PackedValues>(&text).unwrap(), values);
+
+        let field: Field = ron::from_str(&ron::to_string(&Field::new(9, 7)).unwrap()).unwrap();
+        assert_eq!((field.get_width(), field.get_height()), (9, 7));
+    }
+
+    #[test]
+    fn listed_values_are_read() {
+        let values: PackedValues = ron::from_str("(cells: [Alive, Dead], states: [0, 3], levels: [0.5], counts: [1, 70000])").unwrap();
+        assert_eq!(values, PackedValues{cells: vec![CellState::Alive, CellState::Dead], states: vec![0, 3], levels: vec![0.5], counts: vec![1, 70_000]});
+    }
+
+    #[test]
+    fn packed_values_must_match_their_count() {
+        let text = ron::to_string(&get_packed_values()).unwrap();
+        let (prefix, counts) = text.split_once("counts:\"4:").unwrap();
+        assert!(ron::from_str::<PackedValues>(&(String::from(prefix) + "counts:\"5:" + counts)).is_err());
+        let (prefix, states) = text.split_once("states:\"256:").unwrap();
+        assert!(ron::from_str::<PackedValues>(&(String::from(prefix) + "states:\"-1:" + states)).is_err());
+        assert!(ron::from_str::<PackedValues>(&text.replacen("cells:\"37:", "cells:\"37:!", 1)).is_err());
+    }
+}
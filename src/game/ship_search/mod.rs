@@ -0,0 +1,467 @@
+use std::collections::{HashSet, VecDeque};
+use super::field::{CellState, Field};
+use super::rule::Rule;
+use super::soup_search::{ObjectKind, SparsePattern, classify_object};
+
+/// Widest searchable row (the searched half of symmetric rows), so that every candidate row can be enumerated
+pub const MAX_SEARCH_WIDTH: usize = 16;
+
+/// Every partial stays in memory (around a hundred bytes each, more for long periods), so searches give up after at most this many
+pub const MAX_SEARCH_NODES: usize = 2_000_000;
+
+/// Which left-right symmetry the searched patterns have; the symmetric ones only search half of each row
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum SearchSymmetry {
+    #[default]
+    Asymmetric,
+    /// Mirrored about a central column (odd total width)
+    OddSymmetric,
+    /// Mirrored about a line between two columns (even total width)
+    EvenSymmetric,
+    /// Asymmetric, but mirrored after each period, so the pattern comes back after two periods (like the LWSS searched as c/2)
+    GlideReflective
+}
+
+pub const SEARCH_SYMMETRIES: [(SearchSymmetry, &str); 4] = [
+    (SearchSymmetry::Asymmetric, "Asymmetric"),
+    (SearchSymmetry::OddSymmetric, "Odd symmetric"),
+    (SearchSymmetry::EvenSymmetric, "Even symmetric"),
+    (SearchSymmetry::GlideReflective, "Glide reflective")
+];
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum SearchOrder {
+    /// Finds the shortest patterns first, but keeps every partial of the current length in memory
+    #[default]
+    BreadthFirst,
+    /// Goes deep quickly, which finds long partials sooner
+    DepthFirst
+}
+
+/// What to look for: patterns of `period` generations moving `displacement` cells orthogonally (0 for oscillators)
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct ShipSearchSettings {
+    pub period: usize,
+    pub displacement: usize,
+    /// Columns searched; symmetric patterns are about twice as wide
+    pub width: usize,
+    pub symmetry: SearchSymmetry,
+    pub order: SearchOrder,
+    /// Longest pattern looked for, in rows
+    pub max_length: usize,
+    /// The search gives up after creating this many partial patterns (at most `MAX_SEARCH_NODES`)
+    pub max_nodes: usize,
+    pub max_results: usize
+}
+
+impl Default for ShipSearchSettings {
+    /// c/4 orthogonal ships of width 7, like the ones gfind finds in a few seconds
+    fn default() -> Self {
+        ShipSearchSettings {
+            period: 4,
+            displacement: 1,
+            width: 7,
+            symmetry: SearchSymmetry::Asymmetric,
+            order: SearchOrder::BreadthFirst,
+            max_length: 40,
+            max_nodes: 1_000_000,
+            max_results: 10
+        }
+    }
+}
+
+impl ShipSearchSettings {
+    pub fn get_full_width(&self) -> usize {
+        match self.symmetry {
+            SearchSymmetry::Asymmetric | SearchSymmetry::GlideReflective => self.width,
+            SearchSymmetry::OddSymmetric => 2 * self.width - 1,
+            SearchSymmetry::EvenSymmetric => 2 * self.width,
+        }
+    }
+}
+
+/// A complete pattern found by the search, in phase 0, with what it turned out to be when run
+pub struct ShipSearchPattern {
+    pub pattern: Field,
+    pub kind: ObjectKind,
+    pub code: String
+}
+
+struct SearchNode {
+    row: u32,
+    parent: usize,
+    depth: usize,
+    has_living_cells: bool
+}
+
+/// Row-by-row search in the spirit of David Eppstein's gfind. The rows of all the phases are interleaved in a single
+/// sequence R, where row y of phase t is R[p * y + d * t] for a c/p ship moving d rows up every p generations (and
+/// R[p * y + t] for oscillators), so that each new row R[n] is constrained by R[n - 2p] and R[n - p] evolving into a
+/// row that is already known. The search starts from empty rows at the front and extends the sequence with every
+/// consistent row, either breadth-first or depth-first, merging partials that end with the same 2p rows (which have the
+/// same continuations); a partial ending with 2p empty rows is a complete pattern. Glide-reflective patterns are
+/// searched the same way, except that the last phase evolves into the mirror image of the next row of phase 0.
+pub struct ShipSearch {
+    settings: ShipSearchSettings,
+    rule: Rule,
+    /// Which neighbour counts give birth / survival
+    birth_counts: [bool; 9],
+    survival_counts: [bool; 9],
+    /// Every candidate row, expanded to the full width (with symmetry) and shifted so that bit x + 1 is column x
+    expanded_rows: Vec<u64>,
+    nodes: Vec<SearchNode>,
+    frontier: VecDeque<usize>,
+    visited: HashSet<(usize, Vec<u32>)>,
+    deepest_node: usize,
+    results: Vec<ShipSearchPattern>,
+    result_codes: HashSet<String>
+}
+
+impl ShipSearch {
+    pub fn new(mut settings: ShipSearchSettings, rule: Rule) -> Result<ShipSearch, String> {
+        if settings.period == 0 {
+            return Err(String::from("The period must be at least 1"));
+        }
+        if settings.displacement >= settings.period {
+            return Err(String::from("Patterns can't move as fast as (or faster than) light: the displacement must be smaller than the period"));
+        }
+        if settings.displacement > 0 && get_greatest_common_divisor(settings.displacement, settings.period) != 1 {
+            return Err(String::from("The displacement and the period must be coprime (e.g. search 1c/2 instead of 2c/4)"));
+        }
+        if settings.symmetry == SearchSymmetry::GlideReflective && settings.displacement == 0 {
+            return Err(String::from("Glide-reflective patterns must move: the displacement must be at least 1"));
+        }
+        if settings.width == 0 || settings.width > MAX_SEARCH_WIDTH {
+            return Err(String::from("The width must be between 1 and ") + &MAX_SEARCH_WIDTH.to_string());
+        }
+        if rule.get_next_cell_state(CellState::Dead, 0) == CellState::Alive {
+            return Err(String::from("Rules with B0 have no finite spaceships or oscillators on an empty background"));
+        }
+
+        settings.max_nodes = settings.max_nodes.min(MAX_SEARCH_NODES);
+
+        let birth_counts: [bool; 9] = std::array::from_fn(|count| rule.get_next_cell_state(CellState::Dead, count) == CellState::Alive);
+        let survival_counts: [bool; 9] = std::array::from_fn(|count| rule.get_next_cell_state(CellState::Alive, count) == CellState::Alive);
+        let expanded_rows: Vec<u64> = (0..1u32 << settings.width).map(|row| expand_row(row, &settings)).collect();
+        let root = SearchNode{row: 0, parent: 0, depth: 0, has_living_cells: false};
+        let mut visited: HashSet<(usize, Vec<u32>)> = HashSet::new();
+        visited.insert((0, vec![0; 2 * settings.period]));
+        Ok(ShipSearch {
+            settings,
+            rule,
+            birth_counts,
+            survival_counts,
+            expanded_rows,
+            nodes: vec![root],
+            frontier: VecDeque::from([0]),
+            visited,
+            deepest_node: 0,
+            results: Vec::new(),
+            result_codes: HashSet::new()
+        })
+    }
+
+    pub fn get_settings(&self) -> &ShipSearchSettings {
+        &self.settings
+    }
+
+    pub fn get_n_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn get_results(&self) -> &[ShipSearchPattern] {
+        &self.results
+    }
+
+    /// Whether every partial was extended, or a limit was reached
+    pub fn is_finished(&self) -> bool {
+        self.frontier.is_empty() || self.has_given_up() || self.results.len() >= self.settings.max_results
+    }
+
+    pub fn has_given_up(&self) -> bool {
+        self.nodes.len() >= self.settings.max_nodes
+    }
+
+    /// Phase 0 of the longest partial pattern found so far, if it has any living cells
+    pub fn get_deepest_partial(&self) -> Option<Field> {
+        if self.nodes[self.deepest_node].has_living_cells {
+            Some(self.get_phase_zero(self.deepest_node))
+        }
+        else {
+            None
+        }
+    }
+
+    /// Extends partials until about `max_candidate_rows` candidate rows have been tried, so it can run a bit every frame
+    pub fn step(&mut self, max_candidate_rows: usize) {
+        let mut n_candidate_rows: usize = 0;
+        while n_candidate_rows < max_candidate_rows && !self.is_finished() {
+            let node_index = match self.settings.order {
+                SearchOrder::BreadthFirst => self.frontier.pop_front(),
+                SearchOrder::DepthFirst => self.frontier.pop_back(),
+            };
+            let Some(node_index) = node_index else {
+                break;
+            };
+            self.extend(node_index);
+            n_candidate_rows += self.expanded_rows.len();
+        }
+    }
+
+    /// Adds a node for every row consistent with the previous ones, recording complete patterns
+    fn extend(&mut self, node_index: usize) {
+        let period = self.settings.period;
+        let depth = self.nodes[node_index].depth;
+        if depth >= (self.settings.max_length + 2) * period {
+            return;
+        }
+        // last_rows[i] is R[depth - 2p + i], and the new row R[depth] must make R[depth - 2p] and R[depth - p] evolve into
+        // R[depth - p + d] (into the next phase, d rows ahead), or for oscillators into R[depth - p + 1], and R[depth - 2p + 1]
+        // for the last phase (which comes back to phase 0)
+        let last_rows = self.get_last_rows(node_index, 2 * period);
+        let target_position = match self.settings.displacement {
+            0 if depth % period == period - 1 => 1,
+            0 => period + 1,
+            displacement => period + displacement,
+        };
+        let mut target_row = self.expanded_rows[last_rows[target_position] as usize];
+        // R[depth - p] is in phase t where d * t = depth (mod p)
+        let is_last_phase = (period - 1) * self.settings.displacement % period == depth % period;
+        if self.settings.symmetry == SearchSymmetry::GlideReflective && is_last_phase {
+            target_row = mirror_row(target_row, self.settings.width);
+        }
+        let row_two_above = self.expanded_rows[last_rows[0] as usize];
+        let row_above = self.expanded_rows[last_rows[period] as usize];
+        let partial_counts = get_neighbour_counts([row_two_above << 1, row_two_above, row_two_above >> 1, row_above << 1, row_above >> 1], [0; 4]);
+        let valid_columns: u64 = (1 << (self.settings.get_full_width() + 2)) - 1;
+
+        for row in 0..self.expanded_rows.len() {
+            let expanded_row = self.expanded_rows[row];
+            let counts = get_neighbour_counts([expanded_row << 1, expanded_row, expanded_row >> 1], partial_counts);
+            if self.get_next_row(counts, row_above) & valid_columns != target_row {
+                continue;
+            }
+            let row = row as u32;
+            let mut state: Vec<u32> = last_rows[1..].to_vec();
+            state.push(row);
+            let has_living_cells = self.nodes[node_index].has_living_cells || row != 0;
+            let is_complete = has_living_cells && state.iter().all(|row| *row == 0);
+            if is_complete {
+                let child = SearchNode{row, parent: node_index, depth: depth + 1, has_living_cells};
+                self.nodes.push(child);
+                self.record_complete_pattern(self.nodes.len() - 1);
+                continue;
+            }
+            // Partials closing up (ending with p empty rows) aren't merged, otherwise every pattern ending the same way would
+            // merge into one before completing
+            let is_closing = has_living_cells && state[period..].iter().all(|row| *row == 0);
+            if !is_closing && !self.visited.insert(((depth + 1) % period, state)) {
+                continue;
+            }
+            self.nodes.push(SearchNode{row, parent: node_index, depth: depth + 1, has_living_cells});
+            let child_index = self.nodes.len() - 1;
+            if depth + 1 > self.nodes[self.deepest_node].depth {
+                self.deepest_node = child_index;
+            }
+            self.frontier.push_back(child_index);
+        }
+    }
+
+    /// Keeps a complete pattern if it really has the period (and speed) searched for, and wasn't found already
+    fn record_complete_pattern(&mut self, node_index: usize) {
+        let pattern = self.get_phase_zero(node_index);
+        let mut cells: Vec<(i64, i64)> = Vec::new();
+        for y in 0..pattern.get_height() {
+            for x in 0..pattern.get_width() {
+                if pattern.get_cell_state(x, y) == CellState::Alive {
+                    cells.push((x as i64, y as i64));
+                }
+            }
+        }
+        let (kind, code) = classify_object(&SparsePattern::new(cells), &self.rule);
+        let has_searched_period = match kind {
+            ObjectKind::StillLife => self.settings.period == 1,
+            ObjectKind::Oscillator{period} => period == self.settings.period && self.settings.displacement == 0,
+            ObjectKind::Spaceship{period, ..} if self.settings.symmetry == SearchSymmetry::GlideReflective => {
+                period == 2 * self.settings.period
+            }
+            ObjectKind::Spaceship{period, ..} => period == self.settings.period && self.settings.displacement > 0,
+            ObjectKind::Unclassified => false,
+        };
+        if has_searched_period && self.result_codes.insert(code.clone()) {
+            self.results.push(ShipSearchPattern{pattern, kind, code});
+        }
+    }
+
+    /// The last `count` rows of the sequence ending at the node, oldest first, with empty rows before its start
+    fn get_last_rows(&self, node_index: usize, count: usize) -> Vec<u32> {
+        let mut rows: Vec<u32> = vec![0; count];
+        let mut current = node_index;
+        for position in (0..count).rev() {
+            if self.nodes[current].depth == 0 {
+                break;
+            }
+            rows[position] = self.nodes[current].row;
+            current = self.nodes[current].parent;
+        }
+        rows
+    }
+
+    /// The rows of phase 0 (R[0], R[p], R[2p]...) of the sequence ending at the node, without the empty rows around them
+    fn get_phase_zero(&self, node_index: usize) -> Field {
+        let depth = self.nodes[node_index].depth;
+        let all_rows = self.get_last_rows(node_index, depth);
+        let mut rows: Vec<u64> = all_rows.iter().step_by(self.settings.period).map(|row| self.expanded_rows[*row as usize] >> 1).collect();
+        while rows.last() == Some(&0) {
+            rows.pop();
+        }
+        let first_row = rows.iter().position(|row| *row != 0).unwrap_or(rows.len());
+        let rows = &rows[first_row..];
+
+        let full_width = self.settings.get_full_width();
+        let mut pattern = Field::new(full_width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0..full_width {
+                if row & (1 << x) != 0 {
+                    pattern.set_cell_state(x, y, CellState::Alive);
+                }
+            }
+        }
+        pattern
+    }
+
+    /// Next state of the middle row, given the bit-sliced neighbour counts of its cells
+    fn get_next_row(&self, counts: [u64; 4], middle_row: u64) -> u64 {
+        let mut next_row: u64 = 0;
+        for count in 0..9 {
+            if !self.birth_counts[count] && !self.survival_counts[count] {
+                continue;
+            }
+            let mut has_count: u64 = !0;
+            for (bit, count_bits) in counts.iter().enumerate() {
+                has_count &= if count & (1 << bit) != 0 {*count_bits} else {!count_bits};
+            }
+            if self.birth_counts[count] {
+                next_row |= has_count & !middle_row;
+            }
+            if self.survival_counts[count] {
+                next_row |= has_count & middle_row;
+            }
+        }
+        next_row
+    }
+}
+
+/// Adds neighbour rows (each shifted so that the neighbour lines up with the cell) to bit-sliced counts:
+/// bit x of counts[i] is bit i of the count of column x.
+fn get_neighbour_counts<const N: usize>(neighbour_rows: [u64; N], mut counts: [u64; 4]) -> [u64; 4] {
+    for neighbour_row in neighbour_rows {
+        let mut carry = neighbour_row;
+        for count_bits in counts.iter_mut() {
+            let next_carry = *count_bits & carry;
+            *count_bits ^= carry;
+            carry = next_carry;
+        }
+    }
+    counts
+}
+
+/// Mirrors the searched columns of a row as the symmetry requires, leaving a dead column on each side (bit x + 1 is column x)
+fn expand_row(row: u32, settings: &ShipSearchSettings) -> u64 {
+    let width = settings.width;
+    let mut full_row: u64 = row as u64;
+    for x in 0..width {
+        if row & (1 << x) == 0 {
+            continue;
+        }
+        match settings.symmetry {
+            SearchSymmetry::Asymmetric | SearchSymmetry::GlideReflective => (),
+            SearchSymmetry::OddSymmetric => full_row |= 1 << (2 * width - 2 - x),
+            SearchSymmetry::EvenSymmetric => full_row |= 1 << (2 * width - 1 - x),
+        }
+    }
+    full_row << 1
+}
+
+/// Mirrors an expanded row of `width` columns (bit x + 1 is column x)
+fn mirror_row(expanded_row: u64, width: usize) -> u64 {
+    let mut mirrored_row: u64 = 0;
+    for x in 0..width {
+        if expanded_row & (1 << (x + 1)) != 0 {
+            mirrored_row |= 1 << (width - x);
+        }
+    }
+    mirrored_row
+}
+
+fn get_greatest_common_divisor(a: usize, b: usize) -> usize {
+    if b == 0 {a} else {get_greatest_common_divisor(b, a % b)}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_search(settings: ShipSearchSettings) -> ShipSearch {
+        let mut ship_search = ShipSearch::new(settings, Rule::conway()).unwrap();
+        while !ship_search.is_finished() {
+            ship_search.step(100_000);
+        }
+        ship_search
+    }
+
+    #[test]
+    fn finds_the_lightweight_spaceship() {
+        let settings = ShipSearchSettings {
+            period: 2,
+            displacement: 1,
+            width: 5,
+            symmetry: SearchSymmetry::GlideReflective,
+            max_results: 1,
+            ..ShipSearchSettings::default()
+        };
+        let ship_search = run_search(settings);
+        let results = ship_search.get_results();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].kind, ObjectKind::Spaceship{period: 4, dx: 0, dy} if dy.abs() == 2));
+        assert_eq!(results[0].code, "xq4_6frc");
+    }
+
+    #[test]
+    fn finds_the_blinker() {
+        let settings = ShipSearchSettings {
+            period: 2,
+            displacement: 0,
+            width: 3,
+            max_results: 1,
+            ..ShipSearchSettings::default()
+        };
+        let ship_search = run_search(settings);
+        let results = ship_search.get_results();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].kind, ObjectKind::Oscillator{period: 2}));
+        assert_eq!(results[0].code, "xp2_7");
+    }
+
+    #[test]
+    fn limits_are_checked() {
+        let settings = ShipSearchSettings{period: 4, displacement: 2, ..ShipSearchSettings::default()};
+        assert!(ShipSearch::new(settings, Rule::conway()).is_err());
+        let settings = ShipSearchSettings{max_nodes: usize::MAX, ..ShipSearchSettings::default()};
+        let ship_search = ShipSearch::new(settings, Rule::conway()).unwrap();
+        assert_eq!(ship_search.get_settings().max_nodes, MAX_SEARCH_NODES);
+    }
+}
@@ -0,0 +1,409 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use super::field::{CellState, Field};
+
+/// Random number generator for soups: ChaCha8 gives the same numbers for the same seed on every platform
+/// (native and web alike), so a soup can be reproduced from its seed and density.
+pub fn get_soup_rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// Probability (between 0 and 1) of a percentage that may be out of range or NaN, as `gen_bool` needs
+pub fn get_probability(percentage: f64) -> f64 {
+    if percentage.is_nan() {
+        0.0
+    }
+    else {
+        (percentage / 100.0).clamp(0.0, 1.0)
+    }
+}
+
+/// The parameters a random soup was generated with.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RandomSoup {
+    pub seed: u64,
+    pub settings: SoupSettings
+}
+
+impl RandomSoup {
+    /// One-line description, meant to be recorded in exported files
+    pub fn to_comment_string(&self) -> String {
+        String::from("Random soup with seed ") + &self.seed.to_string() + ": " + &self.settings.to_description_string()
+    }
+}
+
+/// Symmetries of soups, named as in apgsearch. The suffix tells where the centre of symmetry lies: _1 on a cell,
+/// _2 on the midpoint of an edge, _4 on a vertex; + reflects across orthogonal axes and x across diagonal ones.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[allow(non_camel_case_types)] // variants follow apgsearch's names
+pub enum SoupSymmetry {
+    #[default]
+    C1,
+    C2_1,
+    C2_2,
+    C2_4,
+    C4_1,
+    C4_4,
+    D2_Plus1,
+    D2_Plus2,
+    D2_X,
+    D4_Plus1,
+    D4_Plus2,
+    D4_Plus4,
+    D4_X1,
+    D4_X4,
+    D8_1,
+    D8_4
+}
+
+pub const SOUP_SYMMETRIES: [SoupSymmetry; 16] = [
+    SoupSymmetry::C1, SoupSymmetry::C2_1, SoupSymmetry::C2_2, SoupSymmetry::C2_4, SoupSymmetry::C4_1, SoupSymmetry::C4_4,
+    SoupSymmetry::D2_Plus1, SoupSymmetry::D2_Plus2, SoupSymmetry::D2_X,
+    SoupSymmetry::D4_Plus1, SoupSymmetry::D4_Plus2, SoupSymmetry::D4_Plus4, SoupSymmetry::D4_X1, SoupSymmetry::D4_X4,
+    SoupSymmetry::D8_1, SoupSymmetry::D8_4
+];
+
+// Linear maps (a, b, c, d), taking (u, v) to (a * u + b * v, c * u + d * v)
+const IDENTITY: [i64; 4] = [1, 0, 0, 1];
+const ROTATION_90: [i64; 4] = [0, -1, 1, 0];
+const ROTATION_180: [i64; 4] = [-1, 0, 0, -1];
+const ROTATION_270: [i64; 4] = [0, 1, -1, 0];
+const HORIZONTAL_MIRROR: [i64; 4] = [-1, 0, 0, 1];
+const VERTICAL_MIRROR: [i64; 4] = [1, 0, 0, -1];
+const DIAGONAL_MIRROR: [i64; 4] = [0, 1, 1, 0];
+const ANTIDIAGONAL_MIRROR: [i64; 4] = [0, -1, -1, 0];
+
+/// Whether the soup region must have an odd or even number of cells along an axis, for its centre to be where the symmetry needs it
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+enum Parity {
+    Any,
+    Odd,
+    Even
+}
+
+impl SoupSymmetry {
+    /// apgsearch's name, e.g. "D4_+2"
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SoupSymmetry::C1 => "C1",
+            SoupSymmetry::C2_1 => "C2_1",
+            SoupSymmetry::C2_2 => "C2_2",
+            SoupSymmetry::C2_4 => "C2_4",
+            SoupSymmetry::C4_1 => "C4_1",
+            SoupSymmetry::C4_4 => "C4_4",
+            SoupSymmetry::D2_Plus1 => "D2_+1",
+            SoupSymmetry::D2_Plus2 => "D2_+2",
+            SoupSymmetry::D2_X => "D2_x",
+            SoupSymmetry::D4_Plus1 => "D4_+1",
+            SoupSymmetry::D4_Plus2 => "D4_+2",
+            SoupSymmetry::D4_Plus4 => "D4_+4",
+            SoupSymmetry::D4_X1 => "D4_x1",
+            SoupSymmetry::D4_X4 => "D4_x4",
+            SoupSymmetry::D8_1 => "D8_1",
+            SoupSymmetry::D8_4 => "D8_4",
+        }
+    }
+
+    fn get_transforms(&self) -> Vec<[i64; 4]> {
+        match self {
+            SoupSymmetry::C1 => vec![IDENTITY],
+            SoupSymmetry::C2_1 | SoupSymmetry::C2_2 | SoupSymmetry::C2_4 => vec![IDENTITY, ROTATION_180],
+            SoupSymmetry::C4_1 | SoupSymmetry::C4_4 => vec![IDENTITY, ROTATION_90, ROTATION_180, ROTATION_270],
+            SoupSymmetry::D2_Plus1 | SoupSymmetry::D2_Plus2 => vec![IDENTITY, VERTICAL_MIRROR],
+            SoupSymmetry::D2_X => vec![IDENTITY, DIAGONAL_MIRROR],
+            SoupSymmetry::D4_Plus1 | SoupSymmetry::D4_Plus2 | SoupSymmetry::D4_Plus4 => vec![IDENTITY, HORIZONTAL_MIRROR, VERTICAL_MIRROR, ROTATION_180],
+            SoupSymmetry::D4_X1 | SoupSymmetry::D4_X4 => vec![IDENTITY, DIAGONAL_MIRROR, ANTIDIAGONAL_MIRROR, ROTATION_180],
+            SoupSymmetry::D8_1 | SoupSymmetry::D8_4 => vec![
+                IDENTITY, ROTATION_90, ROTATION_180, ROTATION_270,
+                HORIZONTAL_MIRROR, VERTICAL_MIRROR, DIAGONAL_MIRROR, ANTIDIAGONAL_MIRROR
+            ],
+        }
+    }
+
+    /// Parities of the region's width and height
+    fn get_parities(&self) -> (Parity, Parity) {
+        match self {
+            SoupSymmetry::C1 | SoupSymmetry::D2_X => (Parity::Any, Parity::Any),
+            SoupSymmetry::C2_1 | SoupSymmetry::C4_1 | SoupSymmetry::D4_Plus1 | SoupSymmetry::D4_X1 | SoupSymmetry::D8_1 => (Parity::Odd, Parity::Odd),
+            SoupSymmetry::C2_2 | SoupSymmetry::D4_Plus2 => (Parity::Odd, Parity::Even),
+            SoupSymmetry::C2_4 | SoupSymmetry::C4_4 | SoupSymmetry::D4_Plus4 | SoupSymmetry::D4_X4 | SoupSymmetry::D8_4 => (Parity::Even, Parity::Even),
+            SoupSymmetry::D2_Plus1 => (Parity::Any, Parity::Odd),
+            SoupSymmetry::D2_Plus2 => (Parity::Any, Parity::Even),
+        }
+    }
+
+    /// Rotations by 90 degrees and diagonal reflections need a square region
+    fn needs_square_region(&self) -> bool {
+        self.get_transforms().iter().any(|transform| transform[0] == 0)
+    }
+
+    /// Largest region fitting in width x height whose centre suits the symmetry (empty if there is none, e.g. an even
+    /// width on a board 1 cell wide)
+    pub fn get_region_size(&self, width: usize, height: usize) -> (usize, usize) {
+        let (mut width, mut height) = if self.needs_square_region() {
+            (width.min(height), width.min(height))
+        }
+        else {
+            (width, height)
+        };
+        let (width_parity, height_parity) = self.get_parities();
+        width = adjust_to_parity(width, width_parity);
+        height = adjust_to_parity(height, height_parity);
+        (width, height)
+    }
+}
+
+fn adjust_to_parity(length: usize, parity: Parity) -> usize {
+    let is_odd = length % 2 == 1;
+    match parity {
+        Parity::Odd if !is_odd => length.saturating_sub(1),
+        Parity::Even if is_odd => length - 1,
+        _ => length,
+    }
+}
+
+/// How the probability of living cells varies across the soup region.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum SoupGradient {
+    #[default]
+    Uniform,
+    /// From the probability on the left edge to the end probability on the right edge
+    LeftToRight,
+    /// From the probability at the centre to the end probability at the corners
+    CentreToEdges
+}
+
+/// Everything but the seed needed to generate a soup.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct SoupSettings {
+    pub symmetry: SoupSymmetry,
+    /// Side of the square soup region, centred on the board; None fills the whole board
+    pub region_size: Option<usize>,
+    /// Percentage of living cells (at the start of the gradient, if any)
+    pub probability_living_cell: f64,
+    pub gradient: SoupGradient,
+    /// Percentage of living cells at the end of the gradient
+    pub gradient_end_probability_living_cell: f64
+}
+
+impl Default for SoupSettings {
+    fn default() -> Self {
+        SoupSettings{
+            symmetry: SoupSymmetry::C1,
+            region_size: None,
+            probability_living_cell: 30.0,
+            gradient: SoupGradient::Uniform,
+            gradient_end_probability_living_cell: 0.0
+        }
+    }
+}
+
+impl SoupSettings {
+    /// Settings of a uniform soup filling the whole board
+    pub fn uniform(probability_living_cell: f64) -> SoupSettings {
+        SoupSettings{probability_living_cell, ..SoupSettings::default()}
+    }
+
+    /// Percentage of living cells at (x, y) in a region of the given size, not clamped
+    fn get_probability_living_cell(&self, x: usize, y: usize, width: usize, height: usize) -> f64 {
+        let progress = match self.gradient {
+            SoupGradient::Uniform => 0.0,
+            SoupGradient::LeftToRight => x as f64 / (width.max(2) - 1) as f64,
+            SoupGradient::CentreToEdges => {
+                let dx = x as f64 + 0.5 - width as f64 / 2.0;
+                let dy = y as f64 + 0.5 - height as f64 / 2.0;
+                let corner_distance = ((width * width + height * height) as f64).sqrt() / 2.0;
+                (dx * dx + dy * dy).sqrt() / corner_distance
+            }
+        };
+        self.probability_living_cell + (self.gradient_end_probability_living_cell - self.probability_living_cell) * progress
+    }
+
+    /// Living cells (true) of the soup region, row by row, along with its width and height. Cells mapped onto one
+    /// another by the symmetry share a single random draw, made when the first of them (in row order) is reached.
+    pub fn generate_region(&self, board_width: usize, board_height: usize, seed: u64) -> (Vec<bool>, usize, usize) {
+        let region_width = self.region_size.map_or(board_width, |region_size| region_size.min(board_width));
+        let region_height = self.region_size.map_or(board_height, |region_size| region_size.min(board_height));
+        let (width, height) = self.symmetry.get_region_size(region_width, region_height);
+        let transforms = self.symmetry.get_transforms();
+        let mut rng = get_soup_rng(seed);
+        let mut cells: Vec<bool> = vec![false; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                // coordinates doubled, relative to the centre of the region, so that centres on edges and vertices are whole numbers
+                let u = 2 * x as i64 + 1 - width as i64;
+                let v = 2 * y as i64 + 1 - height as i64;
+                let representative = transforms.iter()
+                    .map(|[a, b, c, d]| {
+                        let image_x = ((a * u + b * v + width as i64 - 1) / 2) as usize;
+                        let image_y = ((c * u + d * v + height as i64 - 1) / 2) as usize;
+                        image_x + image_y * width
+                    })
+                    .min()
+                    .unwrap_or(x + y * width);
+                cells[x + y * width] = if representative < x + y * width {
+                    cells[representative]
+                }
+                else {
+                    let probability_living_cell = self.get_probability_living_cell(x, y, width, height);
+                    rng.gen_bool(get_probability(probability_living_cell))
+                };
+            }
+        }
+        (cells, width, height)
+    }
+
+    /// A board of the given size, with the soup in the middle
+    pub fn generate_field(&self, width: usize, height: usize, seed: u64) -> Field {
+        let mut field = Field::new(width, height);
+        let (cells, region_width, region_height) = self.generate_region(width, height, seed);
+        let left = (width - region_width) / 2;
+        let top = (height - region_height) / 2;
+        for y in 0..region_height {
+            for x in 0..region_width {
+                if cells[x + y * region_width] {
+                    field.set_cell_state(left + x, top + y, CellState::Alive);
+                }
+            }
+        }
+        field
+    }
+
+    pub fn to_description_string(&self) -> String {
+        let mut description = String::from(self.symmetry.get_name());
+        if let Some(region_size) = self.region_size {
+            description += &(String::from(", ") + &region_size.to_string() + "x" + &region_size.to_string() + " region");
+        }
+        description += &(String::from(", ") + &self.probability_living_cell.to_string() + "% living cells");
+        match self.gradient {
+            SoupGradient::Uniform => {}
+            SoupGradient::LeftToRight => description += &(String::from(" on the left to ") + &self.gradient_end_probability_living_cell.to_string() + "% on the right"),
+            SoupGradient::CentreToEdges => description += &(String::from(" at the centre to ") + &self.gradient_end_probability_living_cell.to_string() + "% at the corners"),
+        }
+        description
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_rows(field: &Field) -> Vec<String> {
+        (0..field.get_height())
+            .map(|y| (0..field.get_width()).map(|x| if field.get_cell_state(x, y) == CellState::Alive {'o'} else {'.'}).collect())
+            .collect()
+    }
+
+    /// The 6x4 soup with seed 12 and 50% living cells, which must not change so that recorded seeds keep giving the same soups
+    const GOLDEN_ROWS: [&str; 4] = [
+        "o..o..",
+        ".oooo.",
+        "oo.oo.",
+        "o.o..o"
+    ];
+
+    #[test]
+    fn soups_are_reproducible_from_their_seed() {
+        let settings = SoupSettings::uniform(50.0);
+        assert_eq!(to_rows(&settings.generate_field(16, 16, 12)), to_rows(&settings.generate_field(16, 16, 12)));
+        assert_ne!(to_rows(&settings.generate_field(16, 16, 12)), to_rows(&settings.generate_field(16, 16, 13)));
+        assert_eq!(to_rows(&settings.generate_field(6, 4, 12)), GOLDEN_ROWS);
+    }
+
+    #[test]
+    fn probabilities_out_of_range_are_clamped() {
+        for (probability_living_cell, is_alive) in [(150.0, true), (-10.0, false), (f64::NAN, false)] {
+            let field = SoupSettings::uniform(probability_living_cell).generate_field(4, 4, 1);
+            assert!(field.get_cells().iter().all(|cell| (*cell == CellState::Alive) == is_alive));
+        }
+    }
+
+    #[test]
+    fn regions_are_invariant_under_their_symmetry() {
+        for symmetry in SOUP_SYMMETRIES {
+            for (board_width, board_height) in [(12, 12), (9, 9), (12, 7), (7, 10)] {
+                let settings = SoupSettings{symmetry, probability_living_cell: 50.0, ..SoupSettings::default()};
+                let (cells, width, height) = settings.generate_region(board_width, board_height, 1);
+                assert!(cells.contains(&true) && cells.contains(&false));
+                for y in 0..height {
+                    for x in 0..width {
+                        let u = 2 * x as i64 + 1 - width as i64;
+                        let v = 2 * y as i64 + 1 - height as i64;
+                        for [a, b, c, d] in symmetry.get_transforms() {
+                            let image_x = ((a * u + b * v + width as i64 - 1) / 2) as usize;
+                            let image_y = ((c * u + d * v + height as i64 - 1) / 2) as usize;
+                            assert_eq!(cells[x + y * width], cells[image_x + image_y * width], "{} at ({x}, {y})", symmetry.get_name());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regions_have_the_expected_symmetries() {
+        let generate = |symmetry: SoupSymmetry, size: usize| {
+            let settings = SoupSettings{symmetry, probability_living_cell: 50.0, ..SoupSettings::default()};
+            let (cells, width, height) = settings.generate_region(size, size, 7);
+            assert_eq!((width, height), (size, size));
+            move |x: usize, y: usize| cells[x + y * size]
+        };
+        let c2_4 = generate(SoupSymmetry::C2_4, 8);
+        let d4_x1 = generate(SoupSymmetry::D4_X1, 9);
+        let d8_4 = generate(SoupSymmetry::D8_4, 8);
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(c2_4(x, y), c2_4(7 - x, 7 - y));
+                assert_eq!(d8_4(x, y), d8_4(7 - y, x));
+                assert_eq!(d8_4(x, y), d8_4(7 - x, y));
+            }
+        }
+        for y in 0..9 {
+            for x in 0..9 {
+                assert_eq!(d4_x1(x, y), d4_x1(y, x));
+                assert_eq!(d4_x1(x, y), d4_x1(8 - y, 8 - x));
+            }
+        }
+    }
+
+    #[test]
+    fn regions_fit_in_small_boards() {
+        for symmetry in SOUP_SYMMETRIES {
+            for board_width in 0..5 {
+                for board_height in 0..5 {
+                    let (width, height) = symmetry.get_region_size(board_width, board_height);
+                    assert!(width <= board_width && height <= board_height, "{} on {board_width}x{board_height}", symmetry.get_name());
+                    let settings = SoupSettings{symmetry, region_size: Some(3), ..SoupSettings::default()};
+                    let field = settings.generate_field(board_width, board_height, 1);
+                    assert_eq!((field.get_width(), field.get_height()), (board_width, board_height));
+                }
+            }
+        }
+        assert_eq!(SoupSymmetry::C2_4.get_region_size(1, 1), (0, 0));
+        assert_eq!(SoupSymmetry::C2_1.get_region_size(4, 4), (3, 3));
+        assert_eq!(SoupSymmetry::D2_Plus2.get_region_size(5, 5), (5, 4));
+    }
+}
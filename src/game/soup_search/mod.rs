@@ -0,0 +1,553 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use super::field::CellState;
+use super::rule::Rule;
+use super::soup::SoupSettings;
+
+/// Longest period looked for, both when waiting for soups to stabilise and when classifying objects
+pub const MAX_PERIOD: usize = 64;
+
+/// Objects of Conway's Game of Life not worth flagging, as rows of 'o' (alive) and '.' (dead) separated by '/'
+pub const KNOWN_CONWAY_OBJECTS: [(&str, &str); 25] = [
+    ("Block", "oo/oo"),
+    ("Beehive", ".oo./o..o/.oo."),
+    ("Loaf", ".oo./o..o/.o.o/..o."),
+    ("Boat", "oo./o.o/.o."),
+    ("Tub", ".o./o.o/.o."),
+    ("Ship", "oo./o.o/.oo"),
+    ("Pond", ".oo./o..o/o..o/.oo."),
+    ("Long boat", "oo../o.o./.o.o/..o."),
+    ("Barge", ".o../o.o./.o.o/..o."),
+    ("Snake", "oo.o/o.oo"),
+    ("Aircraft carrier", "oo../o..o/..oo"),
+    ("Mango", ".oo../o..o./.o..o/..oo."),
+    ("Eater 1", "oo../o.o./..o./..oo"),
+    ("Long ship", "oo../o.o./.o.o/..oo"),
+    ("Blinker", "ooo"),
+    ("Toad", ".ooo/ooo."),
+    ("Beacon", "oo../oo../..oo/..oo"),
+    ("Clock", "..o./o.o./.o.o/.o.."),
+    ("Pulsar", "..ooo...ooo../............./o....o.o....o/o....o.o....o/o....o.o....o/..ooo...ooo../............./..ooo...ooo../o....o.o....o/o....o.o....o/o....o.o....o/............./..ooo...ooo.."),
+    ("Pentadecathlon", "..o....o../oo.oooo.oo/..o....o.."),
+    ("Figure eight", "oo..../oo.o../....o./.o..../..o.oo/....oo"),
+    ("Glider", ".o./..o/ooo"),
+    ("Lightweight spaceship", ".o..o/o..../o...o/oooo."),
+    ("Middleweight spaceship", "...o../.o...o/o...../o....o/ooooo."),
+    ("Heavyweight spaceship", "...oo../.o....o/o....../o.....o/oooooo.")
+];
+
+/// Hashes cell positions several times faster than the default hasher, whose protection against crafted keys isn't needed here
+#[derive(Default)]
+struct PositionHasher {
+    hash: u64
+}
+
+impl Hasher for PositionHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u64(*byte as u64);
+        }
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ value).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+type PositionSet = HashSet<(i64, i64), BuildHasherDefault<PositionHasher>>;
+type PositionMap<V> = HashMap<(i64, i64), V, BuildHasherDefault<PositionHasher>>;
+
+/// A pattern on an unbounded plane, as the set of its living cells.
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct SparsePattern {
+    cells: PositionSet
+}
+
+impl SparsePattern {
+    pub fn new(cells: impl IntoIterator<Item = (i64, i64)>) -> SparsePattern {
+        SparsePattern{cells: cells.into_iter().collect()}
+    }
+
+    /// Reads rows of 'o' (alive) and '.' (dead) separated by '/'
+    pub fn parse(text: &str) -> SparsePattern {
+        let mut cells = PositionSet::default();
+        for (y, row) in text.split('/').enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == 'o' {
+                    cells.insert((x as i64, y as i64));
+                }
+            }
+        }
+        SparsePattern{cells}
+    }
+
+    pub fn get_population(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// (min x, min y, max x, max y), or None if the pattern is empty
+    fn get_bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        let min_x = self.cells.iter().map(|(x, _)| *x).min()?;
+        let min_y = self.cells.iter().map(|(_, y)| *y).min()?;
+        let max_x = self.cells.iter().map(|(x, _)| *x).max()?;
+        let max_y = self.cells.iter().map(|(_, y)| *y).max()?;
+        Some((min_x, min_y, max_x, max_y))
+    }
+
+    /// The same cells, translated so that the bounding box starts at (0, 0)
+    fn normalized(&self) -> Vec<(i64, i64)> {
+        let Some((min_x, min_y, _, _)) = self.get_bounding_box() else {
+            return Vec::new();
+        };
+        let mut cells: Vec<(i64, i64)> = self.cells.iter().map(|(x, y)| (x - min_x, y - min_y)).collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    pub fn advance_generation(&self, rule: &Rule) -> SparsePattern {
+        let mut neighbour_counts: PositionMap<usize> = PositionMap::default();
+        for (x, y) in &self.cells {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx != 0 || dy != 0 {
+                        *neighbour_counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        // cells with no living neighbour are only born under B0 rules, which can't be run on an unbounded plane
+        let candidates = neighbour_counts.keys().chain(self.cells.iter());
+        let mut cells = PositionSet::default();
+        for position in candidates {
+            let cell_state = if self.cells.contains(position) {CellState::Alive} else {CellState::Dead};
+            let number_of_neighbours_alive = neighbour_counts.get(position).copied().unwrap_or(0);
+            if rule.get_next_cell_state(cell_state, number_of_neighbours_alive) == CellState::Alive {
+                cells.insert(*position);
+            }
+        }
+        SparsePattern{cells}
+    }
+
+    fn union(&self, other: &SparsePattern) -> SparsePattern {
+        SparsePattern{cells: self.cells.union(&other.cells).copied().collect()}
+    }
+
+    /// Splits the pattern into clusters of cells at most `distance` cells apart (in both directions)
+    fn split_into_clusters(&self, distance: i64) -> Vec<SparsePattern> {
+        let mut remaining: PositionSet = self.cells.clone();
+        let mut clusters: Vec<SparsePattern> = Vec::new();
+        while let Some(start) = remaining.iter().next().copied() {
+            remaining.remove(&start);
+            let mut cluster_cells = PositionSet::default();
+            let mut to_visit = vec![start];
+            while let Some((x, y)) = to_visit.pop() {
+                cluster_cells.insert((x, y));
+                for dy in -distance..=distance {
+                    for dx in -distance..=distance {
+                        if remaining.remove(&(x + dx, y + dy)) {
+                            to_visit.push((x + dx, y + dy));
+                        }
+                    }
+                }
+            }
+            clusters.push(SparsePattern{cells: cluster_cells});
+        }
+        clusters
+    }
+
+    /// Splits the pattern into objects: clusters of cells at most 2 cells apart, themselves split into their
+    /// connected parts whenever those parts evolve on their own exactly as they do together (such as two blinkers side by side)
+    pub fn split_into_objects(&self, rule: &Rule) -> Vec<SparsePattern> {
+        let mut objects: Vec<SparsePattern> = Vec::new();
+        for cluster in self.split_into_clusters(2) {
+            let mut parts = cluster.split_into_clusters(1);
+            let mut interacting = SparsePattern::new([]);
+            while let Some(part) = parts.pop() {
+                let rest = parts.iter().fold(interacting.clone(), |rest, other_part| rest.union(other_part));
+                if rest.get_population() > 0 && !evolve_independently(&part, &rest, rule) {
+                    interacting = interacting.union(&part);
+                }
+                else {
+                    objects.push(part);
+                }
+            }
+            if interacting.get_population() > 0 {
+                objects.push(interacting);
+            }
+        }
+        objects
+    }
+}
+
+/// Whether running the two patterns together gives the same cells as running them apart, for `MAX_PERIOD` generations
+fn evolve_independently(first: &SparsePattern, second: &SparsePattern, rule: &Rule) -> bool {
+    let mut first = first.clone();
+    let mut second = second.clone();
+    let mut together = first.union(&second);
+    for _ in 0..MAX_PERIOD {
+        first = first.advance_generation(rule);
+        second = second.advance_generation(rule);
+        together = together.advance_generation(rule);
+        if together != first.union(&second) {
+            return false;
+        }
+    }
+    true
+}
+
+/// What an object turned out to be when run on its own.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator{period: usize},
+    Spaceship{period: usize, dx: i64, dy: i64},
+    /// Didn't come back to itself (up to a translation) within `MAX_PERIOD` generations
+    Unclassified
+}
+
+impl ObjectKind {
+    pub fn to_description_string(self) -> String {
+        match self {
+            ObjectKind::StillLife => String::from("Still life"),
+            ObjectKind::Oscillator{period} => String::from("Period ") + &period.to_string() + " oscillator",
+            ObjectKind::Spaceship{period, dx, dy} => {
+                String::from("Period ") + &period.to_string() + " spaceship moving (" + &dx.to_string() + ", " + &dy.to_string() + ")"
+            }
+            ObjectKind::Unclassified => String::from("Unclassified"),
+        }
+    }
+}
+
+/// Runs the object on its own to find its period and displacement, and names it with a code that doesn't depend on
+/// its phase, orientation or position: "xs<population>" for still lifes, "xp<period>" for oscillators and "xq<period>"
+/// for spaceships, then the cells encoded in strips of 5 rows (one character per column, strips separated by 'z'),
+/// as in apgsearch's codes but without the abbreviations of runs of empty columns.
+pub fn classify_object(object: &SparsePattern, rule: &Rule) -> (ObjectKind, String) {
+    let Some((start_x, start_y, _, _)) = object.get_bounding_box() else {
+        return (ObjectKind::Unclassified, String::from("empty"));
+    };
+    let start = object.normalized();
+    let mut phases: Vec<SparsePattern> = vec![object.clone()];
+    let mut current = object.clone();
+    for period in 1..=MAX_PERIOD {
+        current = current.advance_generation(rule);
+        if current.normalized() == start {
+            let (current_x, current_y, _, _) = current.get_bounding_box().unwrap_or((start_x, start_y, 0, 0));
+            let (dx, dy) = (current_x - start_x, current_y - start_y);
+            let (kind, prefix) = if dx != 0 || dy != 0 {
+                (ObjectKind::Spaceship{period, dx, dy}, String::from("xq") + &period.to_string())
+            }
+            else if period == 1 {
+                (ObjectKind::StillLife, String::from("xs") + &object.get_population().to_string())
+            }
+            else {
+                (ObjectKind::Oscillator{period}, String::from("xp") + &period.to_string())
+            };
+            let code = phases.iter()
+                .flat_map(get_orientations)
+                .map(|cells| encode_cells(&cells))
+                .min_by(|first, second| first.len().cmp(&second.len()).then(first.cmp(second)))
+                .unwrap_or_default();
+            return (kind, prefix + "_" + &code);
+        }
+        phases.push(current.clone());
+        if current.get_population() == 0 {
+            break;
+        }
+    }
+    (ObjectKind::Unclassified, String::from("unclassified_") + &encode_cells(&start))
+}
+
+/// The normalized cells of the 8 rotations and reflections of the pattern
+fn get_orientations(pattern: &SparsePattern) -> Vec<Vec<(i64, i64)>> {
+    // linear maps (a, b, c, d), taking (x, y) to (a * x + b * y, c * x + d * y)
+    let transforms: [[i64; 4]; 8] = [
+        [1, 0, 0, 1], [0, -1, 1, 0], [-1, 0, 0, -1], [0, 1, -1, 0],
+        [-1, 0, 0, 1], [1, 0, 0, -1], [0, 1, 1, 0], [0, -1, -1, 0]
+    ];
+    transforms.iter()
+        .map(|[a, b, c, d]| SparsePattern::new(pattern.cells.iter().map(|(x, y)| (a * x + b * y, c * x + d * y))).normalized())
+        .collect()
+}
+
+fn encode_cells(cells: &[(i64, i64)]) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+    let width = cells.iter().map(|(x, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|(_, y)| y + 1).max().unwrap_or(0);
+    let mut strips: Vec<String> = Vec::new();
+    for strip_start in (0..height).step_by(5) {
+        let mut columns: Vec<u8> = vec![0; width as usize];
+        for (x, y) in cells.iter().filter(|(_, y)| *y >= strip_start && *y < strip_start + 5) {
+            columns[*x as usize] |= 1 << (y - strip_start);
+        }
+        let strip: String = columns.iter().map(|column| DIGITS[*column as usize] as char).collect();
+        strips.push(strip.trim_end_matches('0').to_string());
+    }
+    strips.join("z")
+}
+
+/// How a search of soups is run.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct SoupSearchSettings {
+    pub rule: Rule,
+    /// Symmetry, density and gradient of the soups; soups without a region are 16x16
+    pub soup_settings: SoupSettings,
+    /// Seed of the first soup; the following soups use the next seeds
+    pub first_seed: u64,
+    /// Soups still changing after this many generations are given up on
+    pub max_generations: usize
+}
+
+/// Census of a single soup.
+pub struct SoupCensus {
+    /// Objects found, by code
+    pub objects: Vec<(String, ObjectKind)>,
+    /// Whether the soup stabilised within the maximum number of generations
+    pub has_stabilised: bool
+}
+
+/// Generates the soup and runs it until it becomes periodic, then censuses its objects; None if `is_running` was cleared meanwhile
+pub fn search_soup(settings: &SoupSearchSettings, seed: u64, is_running: &AtomicBool) -> Option<SoupCensus> {
+    let board_size = settings.soup_settings.region_size.unwrap_or(16);
+    let (soup_cells, width, height) = settings.soup_settings.generate_region(board_size, board_size, seed);
+    let cells = (0..width * height)
+        .filter(|index| soup_cells[*index])
+        .map(|index| ((index % width) as i64, (index / width) as i64));
+    let stabilisation = run_until_stable(SparsePattern::new(cells), &settings.rule, settings.max_generations, is_running)?;
+    let objects = stabilisation.pattern.split_into_objects(&settings.rule).iter()
+        .map(|object| {
+            let (kind, code) = classify_object(object, &settings.rule);
+            (code, kind)
+        })
+        .collect();
+    Some(SoupCensus{objects, has_stabilised: stabilisation.has_stabilised})
+}
+
+/// What became of a pattern run until its state became periodic.
+pub struct Stabilisation {
+    pub pattern: SparsePattern,
+    /// Generation from which the state is periodic, or the maximum number of generations if it never became so
+    pub generations: usize,
+    pub has_stabilised: bool
+}
+
+/// A pattern run generation by generation until its state becomes periodic, up to where the spaceships it sent away
+/// have gone, or for a maximum number of generations. The run can be advanced a few generations at a time.
+pub struct StabilisationRun {
+    pattern: SparsePattern,
+    /// Hashes of the states of the pattern (see `get_state_hash`), one per generation run so far
+    state_hashes: Vec<u64>,
+    max_generations: usize,
+    /// Generation from which the state is periodic, once it is
+    first_periodic_generation: Option<usize>
+}
+
+impl StabilisationRun {
+    pub fn new(pattern: SparsePattern, max_generations: usize) -> StabilisationRun {
+        let state_hashes = vec![get_state_hash(&pattern)];
+        StabilisationRun{pattern, state_hashes, max_generations, first_periodic_generation: None}
+    }
+
+    /// Generations run so far
+    pub fn get_generation(&self) -> usize {
+        self.state_hashes.len() - 1
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.first_periodic_generation.is_some() || self.get_generation() >= self.max_generations
+    }
+
+    /// Runs at most `n_generations` more generations, stopping early once the run is finished
+    pub fn advance(&mut self, rule: &Rule, n_generations: usize) {
+        for _ in 0..n_generations {
+            if self.is_finished() {
+                return;
+            }
+            self.pattern = self.pattern.advance_generation(rule);
+            self.state_hashes.push(get_state_hash(&self.pattern));
+            if self.get_generation() % 10 != 0 {
+                continue;
+            }
+            if let Some(period) = get_state_period(&self.state_hashes) {
+                // go back to the first generation of the periodic part
+                let mut first_periodic_generation = self.state_hashes.len() - 1 - period;
+                while first_periodic_generation > 0
+                    && self.state_hashes[first_periodic_generation - 1] == self.state_hashes[first_periodic_generation - 1 + period] {
+                    first_periodic_generation -= 1;
+                }
+                self.first_periodic_generation = Some(first_periodic_generation);
+            }
+        }
+    }
+
+    pub fn into_stabilisation(self) -> Stabilisation {
+        match self.first_periodic_generation {
+            Some(generations) => Stabilisation{pattern: self.pattern, generations, has_stabilised: true},
+            None => Stabilisation{pattern: self.pattern, generations: self.max_generations, has_stabilised: false},
+        }
+    }
+}
+
+/// Runs the pattern to the end of its `StabilisationRun`; None if `is_running` was cleared before then
+pub fn run_until_stable(pattern: SparsePattern, rule: &Rule, max_generations: usize, is_running: &AtomicBool) -> Option<Stabilisation> {
+    let mut run = StabilisationRun::new(pattern, max_generations);
+    while !run.is_finished() {
+        if !is_running.load(Ordering::Relaxed) {
+            return None;
+        }
+        run.advance(rule, 1);
+    }
+    Some(run.into_stabilisation())
+}
+
+/// Hash of the cells of the pattern, taking each of its clusters wherever it is: once the pattern has stabilised, every
+/// cluster comes back to the same cells after a period, only translated for the spaceships
+fn get_state_hash(pattern: &SparsePattern) -> u64 {
+    let mut cluster_hashes: Vec<u64> = pattern.split_into_clusters(2).iter()
+        .map(|cluster| {
+            let mut hasher = PositionHasher::default();
+            cluster.normalized().hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    cluster_hashes.sort_unstable();
+    let mut hasher = PositionHasher::default();
+    cluster_hashes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Period (up to `MAX_PERIOD`) with which the last states repeat, over a window long enough to trust it
+fn get_state_period(state_hashes: &[u64]) -> Option<usize> {
+    (1..=MAX_PERIOD).find(|period| {
+        let window = (4 * period).max(40);
+        state_hashes.len() > window + period && (0..window).all(|k| {
+            let index = state_hashes.len() - 1 - k;
+            state_hashes[index] == state_hashes[index - period]
+        })
+    })
+}
+
+/// Running totals of an object.
+#[derive(Clone)]
+pub struct ObjectTally {
+    pub kind: ObjectKind,
+    /// Name from the known objects list, if it's in it
+    pub name: Option<String>,
+    pub count: usize,
+    /// Seed of the first soup the object appeared in
+    pub first_seed: u64
+}
+
+/// Results of a search so far.
+#[derive(Clone)]
+#[derive(Default)]
+pub struct SoupSearchResults {
+    pub n_soups_searched: usize,
+    /// Object tallies, by code
+    pub tallies: HashMap<String, ObjectTally>,
+    /// Seeds of the soups that didn't stabilise
+    pub unstabilised_seeds: Vec<u64>
+}
+
+impl SoupSearchResults {
+    pub fn add_census(&mut self, census: SoupCensus, seed: u64, known_objects: &HashMap<String, String>) {
+        self.n_soups_searched += 1;
+        if !census.has_stabilised {
+            self.unstabilised_seeds.push(seed);
+        }
+        for (code, kind) in census.objects {
+            let tally = self.tallies.entry(code.clone()).or_insert_with(|| ObjectTally{
+                kind,
+                name: known_objects.get(&code).cloned(),
+                count: 0,
+                first_seed: seed
+            });
+            tally.count += 1;
+        }
+    }
+
+    /// Tallies sorted by decreasing count
+    pub fn get_sorted_tallies(&self) -> Vec<(&String, &ObjectTally)> {
+        let mut tallies: Vec<(&String, &ObjectTally)> = self.tallies.iter().collect();
+        tallies.sort_by(|(first_code, first), (second_code, second)| second.count.cmp(&first.count).then(first_code.cmp(second_code)));
+        tallies
+    }
+
+    /// Objects that are not in the known objects list, in the order they were found
+    pub fn get_unknown_objects(&self) -> Vec<(&String, &ObjectTally)> {
+        let mut unknown_objects: Vec<(&String, &ObjectTally)> = self.tallies.iter().filter(|(_, tally)| tally.name.is_none()).collect();
+        unknown_objects.sort_by_key(|(_, tally)| tally.first_seed);
+        unknown_objects
+    }
+}
+
+/// Codes of the known objects of the rule, along with their names; only Conway's Game of Life has any
+pub fn get_known_objects(rule: &Rule) -> HashMap<String, String> {
+    let mut known_objects: HashMap<String, String> = HashMap::new();
+    if *rule == Rule::conway() {
+        for (name, pattern) in KNOWN_CONWAY_OBJECTS {
+            let (_, code) = classify_object(&SparsePattern::parse(pattern), rule);
+            known_objects.insert(code, String::from(name));
+        }
+    }
+    known_objects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_objects() {
+        let rule = Rule::conway();
+        let (kind, code) = classify_object(&SparsePattern::parse("oo/oo"), &rule);
+        assert_eq!((kind, code.as_str()), (ObjectKind::StillLife, "xs4_33"));
+        let (kind, code) = classify_object(&SparsePattern::parse("ooo"), &rule);
+        assert_eq!((kind, code.as_str()), (ObjectKind::Oscillator{period: 2}, "xp2_7"));
+        let (kind, code) = classify_object(&SparsePattern::parse(".o./..o/ooo"), &rule);
+        assert_eq!((kind, code.as_str()), (ObjectKind::Spaceship{period: 4, dx: 1, dy: 1}, "xq4_153"));
+    }
+
+    #[test]
+    fn waits_for_the_state_to_repeat() {
+        let rule = Rule::conway();
+        let is_running = AtomicBool::new(true);
+        let blinker = run_until_stable(SparsePattern::parse("ooo"), &rule, 1000, &is_running).unwrap();
+        assert!(blinker.has_stabilised);
+        assert_eq!(blinker.generations, 0);
+
+        // the R-pentomino settles after 1103 generations, sending 6 gliders away
+        let r_pentomino = run_until_stable(SparsePattern::parse(".oo/oo./.o."), &rule, 5000, &is_running).unwrap();
+        assert!(r_pentomino.has_stabilised);
+        assert_eq!(r_pentomino.generations, 1103);
+        assert_eq!(r_pentomino.pattern.get_population(), 116);
+        let n_gliders = r_pentomino.pattern.split_into_objects(&rule).iter()
+            .filter(|object| classify_object(object, &rule).1 == "xq4_153")
+            .count();
+        assert_eq!(n_gliders, 6);
+
+        let unfinished = run_until_stable(SparsePattern::parse(".oo/oo./.o."), &rule, 100, &is_running).unwrap();
+        assert!(!unfinished.has_stabilised);
+    }
+
+    #[test]
+    fn stops_when_asked() {
+        let is_running = AtomicBool::new(false);
+        assert!(run_until_stable(SparsePattern::parse(".oo/oo./.o."), &Rule::conway(), 5000, &is_running).is_none());
+    }
+}
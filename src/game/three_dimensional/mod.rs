@@ -0,0 +1,190 @@
+use super::field::CellState;
+use super::rule::Rule;
+use super::field_error::FieldError;
+
+/// Carter Bays's Life 4555: born with 5 of the 26 neighbours, survives with 4 or 5
+pub const BAYS_4555: &str = "B5/S45";
+/// Carter Bays's Life 5766: born with 6 of the 26 neighbours, survives with 5 to 7
+pub const BAYS_5766: &str = "B6/S567";
+
+/// Which cells count as neighbours in three dimensions: the 26 cells sharing at least a vertex (Moore),
+/// or the 6 cells sharing a face (von Neumann).
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum VoxelNeighbourhood {
+    #[default]
+    Moore,
+    VonNeumann
+}
+
+impl VoxelNeighbourhood {
+    pub fn get_neighbour_offsets(&self) -> Vec<(isize, isize, isize)> {
+        let mut offsets: Vec<(isize, isize, isize)> = Vec::new();
+        for dz in -1isize..=1 {
+            for dy in -1isize..=1 {
+                for dx in -1isize..=1 {
+                    let distance = dx.abs() + dy.abs() + dz.abs();
+                    let is_neighbour = match self {
+                        VoxelNeighbourhood::Moore => distance > 0,
+                        VoxelNeighbourhood::VonNeumann => distance == 1,
+                    };
+                    if is_neighbour {
+                        offsets.push((dx, dy, dz));
+                    }
+                }
+            }
+        }
+        offsets
+    }
+}
+
+/// How a three-dimensional universe is shown: one Z slice at a time, or the layers up to the slice in isometric perspective.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum ThreeDimensionalView {
+    #[default]
+    Slice,
+    Isometric
+}
+
+/// Like `Field`, but with cells (voxels) stacked in `depth` layers along the Z axis.
+#[derive(Clone)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct VoxelField {
+    width: usize,
+    height: usize,
+    depth: usize,
+    #[serde(with = "super::save_format::packed_cells")]
+    cells: Vec<CellState>
+}
+
+impl VoxelField {
+    pub fn new(width: usize, height: usize, depth: usize) -> VoxelField {
+        VoxelField{width, height, depth, cells: vec![CellState::Dead; width * height * depth]}
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The cells layer by layer, row by row: the cell (x, y, z) is at x + (y + z * height) * width
+    pub fn get_cells(&self) -> &[CellState] {
+        &self.cells
+    }
+
+    pub(crate) fn get_cells_mut(&mut self) -> &mut [CellState] {
+        &mut self.cells
+    }
+
+    pub fn is_within_boundaries(&self, x: usize, y: usize, z: usize) -> bool {
+        x < self.width && y < self.height && z < self.depth
+    }
+
+    /// Cells outside of the universe are dead
+    pub fn get_cell_state(&self, x: usize, y: usize, z: usize) -> CellState {
+        if self.is_within_boundaries(x, y, z) {
+            return self.cells[x + (y + z * self.height) * self.width]
+        }
+        CellState::Dead
+    }
+
+    pub fn set_cell_state(&mut self, x: usize, y: usize, z: usize, cell_state: CellState) {
+        if self.is_within_boundaries(x, y, z) {
+            self.cells[x + (y + z * self.height) * self.width] = cell_state
+        }
+    }
+
+    /// Like `get_cell_state`, but with an error rather than a dead cell outside of the universe
+    pub fn try_get_cell_state(&self, x: usize, y: usize, z: usize) -> Result<CellState, FieldError> {
+        self.check_boundaries(x, y, z)?;
+        Ok(self.cells[x + (y + z * self.height) * self.width])
+    }
+
+    /// Like `set_cell_state`, but with an error rather than nothing happening outside of the universe
+    pub fn try_set_cell_state(&mut self, x: usize, y: usize, z: usize, cell_state: CellState) -> Result<(), FieldError> {
+        self.check_boundaries(x, y, z)?;
+        self.cells[x + (y + z * self.height) * self.width] = cell_state;
+        Ok(())
+    }
+
+    fn check_boundaries(&self, x: usize, y: usize, z: usize) -> Result<(), FieldError> {
+        if self.is_within_boundaries(x, y, z) {
+            return Ok(());
+        }
+        Err(FieldError::VoxelOutOfBounds{x, y, z, width: self.width, height: self.height, depth: self.depth})
+    }
+
+    /// Whether the cells match the dimensions, which a damaged save may not
+    pub(crate) fn check_cell_count(&self) -> Result<(), FieldError> {
+        if self.cells.len() == self.width * self.height * self.depth {
+            return Ok(());
+        }
+        Err(FieldError::CellCountMismatch{n_cells: self.cells.len(), expected_n_cells: self.width * self.height * self.depth})
+    }
+
+    pub fn get_number_of_living_cells(&self) -> usize {
+        self.cells.iter().filter(|cell| **cell == CellState::Alive).count()
+    }
+
+    pub fn get_number_of_neighbours_alive(&self, x: usize, y: usize, z: usize, neighbour_offsets: &[(isize, isize, isize)], consider_extremes_adjacent: bool) -> usize {
+        let mut number_of_alive: usize = 0;
+        for (dx, dy, dz) in neighbour_offsets {
+            let neighbour_x = get_offset_coordinate(x, *dx, self.width, consider_extremes_adjacent);
+            let neighbour_y = get_offset_coordinate(y, *dy, self.height, consider_extremes_adjacent);
+            let neighbour_z = get_offset_coordinate(z, *dz, self.depth, consider_extremes_adjacent);
+            if let (Some(neighbour_x), Some(neighbour_y), Some(neighbour_z)) = (neighbour_x, neighbour_y, neighbour_z) {
+                if self.get_cell_state(neighbour_x, neighbour_y, neighbour_z) == CellState::Alive {
+                    number_of_alive += 1;
+                }
+            }
+        }
+        number_of_alive
+    }
+
+    pub fn advance_generation(&self, rule: &Rule, neighbourhood: VoxelNeighbourhood, consider_extremes_adjacent: bool) -> VoxelField {
+        let neighbour_offsets = neighbourhood.get_neighbour_offsets();
+        let mut next_field = self.clone();
+        for z in 0..self.depth {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let number_of_neighbours_alive = self.get_number_of_neighbours_alive(x, y, z, &neighbour_offsets, consider_extremes_adjacent);
+                    let new_cell_state = rule.get_next_cell_state(self.get_cell_state(x, y, z), number_of_neighbours_alive);
+                    next_field.set_cell_state(x, y, z, new_cell_state);
+                }
+            }
+        }
+        next_field
+    }
+}
+
+/// Coordinate + offset along an axis of the given length, wrapping around if the extremes are adjacent;
+/// None if it falls outside of the axis otherwise.
+fn get_offset_coordinate(coordinate: usize, offset: isize, length: usize, consider_extremes_adjacent: bool) -> Option<usize> {
+    let new_coordinate = coordinate as isize + offset;
+    if consider_extremes_adjacent {
+        return Some(new_coordinate.rem_euclid(length as isize) as usize);
+    }
+    if new_coordinate < 0 || new_coordinate >= length as isize {
+        return None;
+    }
+    Some(new_coordinate as usize)
+}
@@ -0,0 +1,321 @@
+/// Transition table of Langton's ant: turn right on colour 0, left on colour 1, flipping the colour
+pub const LANGTONS_ANT: &str = "{{{1,2,0},{0,8,0}}}";
+
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West
+}
+
+impl Direction {
+    pub fn turned(self, turn: Turn) -> Direction {
+        let quarter_turns_clockwise = match turn {
+            Turn::None => 0,
+            Turn::Right => 1,
+            Turn::Around => 2,
+            Turn::Left => 3,
+        };
+        let directions = [Direction::North, Direction::East, Direction::South, Direction::West];
+        directions[(self as usize + quarter_turns_clockwise) % 4]
+    }
+
+    /// (dx, dy) of a step forward, with y growing downwards as on the board
+    pub fn get_offset(self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+}
+
+/// Turns as encoded in turmite transition tables: 1 no turn, 2 right, 4 u-turn, 8 left
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum Turn {
+    None,
+    Right,
+    Around,
+    Left
+}
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct TurmiteTransition {
+    pub write_color: u8,
+    pub turn: Turn,
+    pub next_state: usize
+}
+
+/// A 2D turmite in the notation used by Golly and Ed Pegg Jr., e.g. "{{{1,2,0},{0,8,0}}}" for Langton's ant:
+/// for each internal state, for each colour of the cell under the ant, {colour to write, turn, next state}.
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "TurmiteTransitions")] // saved tables are checked like parsed ones, since empty ones can't be stepped
+pub struct Turmite {
+    transitions: Vec<Vec<TurmiteTransition>>
+}
+
+/// The transitions of a turmite as saved, before they are checked
+#[derive(serde::Deserialize)]
+#[serde(rename = "Turmite")]
+struct TurmiteTransitions {
+    transitions: Vec<Vec<TurmiteTransition>>
+}
+
+impl TryFrom<TurmiteTransitions> for Turmite {
+    type Error = String;
+
+    fn try_from(turmite_transitions: TurmiteTransitions) -> Result<Self, Self::Error> {
+        Turmite::from_transitions(turmite_transitions.transitions)
+    }
+}
+
+impl Default for Turmite {
+    fn default() -> Self {
+        Turmite::parse(LANGTONS_ANT).unwrap_or(Turmite{transitions: Vec::new()})
+    }
+}
+
+impl Turmite {
+    pub fn parse(specification: &str) -> Result<Turmite, String> {
+        let specification: String = specification.chars().filter(|c| !c.is_whitespace()).collect();
+        let (states, rest) = parse_nested_list(&specification)?;
+        if !rest.is_empty() {
+            return Err(String::from("Unexpected \"") + rest + "\" after the transition table");
+        }
+
+        let NestedList::List(states) = states else {
+            return Err(String::from("Expected a transition table like {{{1,2,0},{0,8,0}}}"));
+        };
+
+        let mut transitions: Vec<Vec<TurmiteTransition>> = Vec::new();
+        for state in &states {
+            let NestedList::List(colors) = state else {
+                return Err(String::from("Each state must be a list of transitions, one per colour"));
+            };
+            let mut state_transitions: Vec<TurmiteTransition> = Vec::new();
+            for transition in colors {
+                state_transitions.push(parse_transition(transition)?);
+            }
+            transitions.push(state_transitions);
+        }
+        Turmite::from_transitions(transitions)
+    }
+
+    /// Checks that every state has a transition for every colour, to colours and states in range
+    fn from_transitions(transitions: Vec<Vec<TurmiteTransition>>) -> Result<Turmite, String> {
+        if transitions.is_empty() {
+            return Err(String::from("The turmite needs at least one state"));
+        }
+        let n_colors = transitions[0].len();
+        if !(2..=256).contains(&n_colors) {
+            return Err(String::from("The turmite must handle between 2 and 256 colours"));
+        }
+        for state_transitions in &transitions {
+            if state_transitions.len() != n_colors {
+                return Err(String::from("Every state must have one transition per colour (") + &n_colors.to_string() + ")");
+            }
+            for transition in state_transitions {
+                if transition.write_color as usize >= n_colors {
+                    return Err(String::from("Colour ") + &transition.write_color.to_string() + " is out of range");
+                }
+                if transition.next_state >= transitions.len() {
+                    return Err(String::from("State ") + &transition.next_state.to_string() + " is out of range");
+                }
+            }
+        }
+
+        Ok(Turmite{transitions})
+    }
+
+    pub fn get_n_states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn get_n_colors(&self) -> usize {
+        self.transitions.first().map_or(0, |state_transitions| state_transitions.len())
+    }
+
+    pub fn to_specification_string(&self) -> String {
+        let turn_code = |turn: Turn| match turn {
+            Turn::None => 1,
+            Turn::Right => 2,
+            Turn::Around => 4,
+            Turn::Left => 8,
+        };
+        let states: Vec<String> = self.transitions.iter()
+            .map(|state_transitions| {
+                let colors: Vec<String> = state_transitions.iter()
+                    .map(|transition| String::from("{") + &transition.write_color.to_string() + "," + &turn_code(transition.turn).to_string() + "," + &transition.next_state.to_string() + "}")
+                    .collect();
+                String::from("{") + &colors.join(",") + "}"
+            })
+            .collect();
+        String::from("{") + &states.join(",") + "}"
+    }
+
+    /// Transition for an ant in the given state on a cell of the given colour; colours beyond the turmite's wrap around
+    pub fn get_transition(&self, state: usize, color: u8) -> TurmiteTransition {
+        let state_transitions = &self.transitions[state % self.transitions.len()];
+        state_transitions[color as usize % state_transitions.len()]
+    }
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Ant {
+    pub x: usize,
+    pub y: usize,
+    pub direction: Direction,
+    pub state: usize,
+    pub color: [u8; 3]
+}
+
+/// Distinct colours handed out to new ants in turn
+pub const ANT_COLORS: [[u8; 3]; 6] = [
+    [255, 0, 0],
+    [0, 0, 255],
+    [255, 0, 255],
+    [0, 200, 200],
+    [255, 140, 0],
+    [128, 0, 255]
+];
+
+enum NestedList {
+    Number(usize),
+    List(Vec<NestedList>)
+}
+
+/// Parses a brace-delimited, comma-separated nested list of numbers, returning it along with what follows it
+fn parse_nested_list(text: &str) -> Result<(NestedList, &str), String> {
+    let Some(mut rest) = text.strip_prefix('{') else {
+        let digits_length = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        let number = text[..digits_length].parse::<usize>()
+            .map_err(|_| String::from("Expected a number or '{' at \"") + text + "\"")?;
+        return Ok((NestedList::Number(number), &text[digits_length..]));
+    };
+
+    let mut items: Vec<NestedList> = Vec::new();
+    loop {
+        let (item, after_item) = parse_nested_list(rest)?;
+        items.push(item);
+        if let Some(after_comma) = after_item.strip_prefix(',') {
+            rest = after_comma;
+        }
+        else if let Some(after_brace) = after_item.strip_prefix('}') {
+            return Ok((NestedList::List(items), after_brace));
+        }
+        else {
+            return Err(String::from("Expected ',' or '}' at \"") + after_item + "\"");
+        }
+    }
+}
+
+fn parse_transition(transition: &NestedList) -> Result<TurmiteTransition, String> {
+    let invalid_transition = || String::from("Each transition must be {colour to write, turn, next state}");
+    let NestedList::List(items) = transition else {
+        return Err(invalid_transition());
+    };
+    let numbers: Vec<usize> = items.iter()
+        .map(|item| match item {
+            NestedList::Number(number) => Some(*number),
+            NestedList::List(_) => None,
+        })
+        .collect::<Option<Vec<usize>>>()
+        .ok_or_else(invalid_transition)?;
+    let [write_color, turn, next_state] = numbers[..] else {
+        return Err(invalid_transition());
+    };
+    let turn = match turn {
+        1 => Turn::None,
+        2 => Turn::Right,
+        4 => Turn::Around,
+        8 => Turn::Left,
+        _ => return Err(String::from("Invalid turn ") + &turn.to_string() + ", expected 1 (none), 2 (right), 4 (u-turn) or 8 (left)"),
+    };
+    let write_color = u8::try_from(write_color).map_err(|_| String::from("Colour ") + &write_color.to_string() + " is out of range")?;
+    Ok(TurmiteTransition{write_color, turn, next_state})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Game;
+    use super::super::field::CellState;
+
+    #[test]
+    fn transition_tables_are_parsed() {
+        let turmite = Turmite::parse(LANGTONS_ANT).unwrap();
+        assert_eq!((turmite.get_n_states(), turmite.get_n_colors()), (1, 2));
+        let transition = turmite.get_transition(0, 1);
+        assert_eq!((transition.write_color, transition.turn, transition.next_state), (0, Turn::Left, 0));
+        assert_eq!(turmite.to_specification_string(), LANGTONS_ANT);
+
+        // Fibonacci spiral, with spaces
+        let turmite = Turmite::parse("{{{1, 8, 1}, {1, 8, 1}}, {{1, 2, 1}, {0, 1, 0}}}").unwrap();
+        assert_eq!((turmite.get_n_states(), turmite.get_n_colors()), (2, 2));
+        assert_eq!(turmite.get_transition(1, 1).turn, Turn::None);
+        assert_eq!(turmite.to_specification_string(), "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}");
+
+        for invalid_specification in ["", "{}", "{{}}", "{{{1,2,0}}}", "{{{1,3,0},{0,8,0}}}", "{{{1,2,1},{0,8,0}}}", "{{{2,2,0},{0,8,0}}}",
+            "{{{1,2},{0,8,0}}}", "{{{1,2,0},{0,8,0}}", "{{{1,2,0},{0,8,0}}}x", "{{{1,2,0},{0,8,0}},{{1,2,0}}}"] {
+            assert!(Turmite::parse(invalid_specification).is_err(), "{invalid_specification}");
+        }
+    }
+
+    #[test]
+    fn empty_transition_tables_are_not_loaded() {
+        let saved_turmite = ron::to_string(&Turmite::default()).unwrap();
+        assert!(ron::from_str::<Turmite>(&saved_turmite).is_ok());
+        assert!(ron::from_str::<Turmite>("(transitions: [])").is_err());
+        assert!(ron::from_str::<Turmite>("(transitions: [[]])").is_err());
+    }
+
+    fn get_game_with_ant(width: usize, height: usize) -> Game {
+        let mut game = Game::new(width, height);
+        game.get_ants_mut().push(Ant{x: width / 2, y: height / 2, direction: Direction::North, state: 0, color: ANT_COLORS[0]});
+        game
+    }
+
+    #[test]
+    fn langtons_ant_draws_a_square_then_a_highway() {
+        let mut game = get_game_with_ant(10, 10);
+        game.advance_generations(4, true);
+        let ant = &game.get_ants()[0];
+        assert_eq!((ant.x, ant.y, ant.direction), (5, 5, Direction::North));
+        assert_eq!(game.get_field().get_population(), 4);
+        for (x, y) in [(5, 5), (6, 5), (6, 6), (5, 6)] {
+            assert_eq!(game.get_field().get_cell_state(x, y), CellState::Alive);
+        }
+
+        // after about 10000 chaotic steps, the ant builds a highway, moving 2 cells diagonally every 104 steps
+        let mut game = get_game_with_ant(80, 80);
+        game.advance_generations(11000, false);
+        let (x, y, direction) = (game.get_ants()[0].x, game.get_ants()[0].y, game.get_ants()[0].direction);
+        let population = game.get_field().get_population();
+        game.advance_generations(104, false);
+        let ant = &game.get_ants()[0];
+        assert_eq!((ant.x.abs_diff(x), ant.y.abs_diff(y), ant.direction), (2, 2, direction));
+        assert_eq!(game.get_field().get_population(), population + 12);
+    }
+}
@@ -0,0 +1,140 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Order in which the cells of a B/S field are updated.
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum UpdateMode {
+    /// Every cell is updated at once from the previous generation
+    #[default]
+    Synchronous,
+    /// Cells are updated one at a time, in a new random order every generation, each seeing the updates made before it
+    RandomOrder,
+    /// Every cell is updated at once, but each only with probability `synchrony_rate` (α); the others keep their state
+    AlphaAsynchronous
+}
+
+/// How a generation is computed from the previous one with B/S rules.
+#[derive(PartialEq)]
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct UpdateSettings {
+    pub mode: UpdateMode,
+    /// Probability (α) of each cell being updated in the α-asynchronous mode
+    pub synchrony_rate: f64,
+    /// Probability of a birth allowed by the rule actually happening
+    pub birth_probability: f64,
+    /// Probability of a survival allowed by the rule actually happening; otherwise the cell dies
+    pub survival_probability: f64
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        UpdateSettings{mode: UpdateMode::Synchronous, synchrony_rate: 0.5, birth_probability: 1.0, survival_probability: 1.0}
+    }
+}
+
+impl UpdateSettings {
+    /// Whether updates use random numbers at all; deterministic settings leave the random number generator untouched
+    pub fn is_stochastic(&self) -> bool {
+        self.mode != UpdateMode::Synchronous || self.birth_probability < 1.0 || self.survival_probability < 1.0
+    }
+}
+
+/// Random number generator of the stochastic update modes: runs from the same seed and settings are identical.
+#[derive(Clone)]
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct UpdateRng {
+    seed: u64,
+    pub rng: ChaCha8Rng
+}
+
+impl Default for UpdateRng {
+    fn default() -> Self {
+        UpdateRng::new(0)
+    }
+}
+
+impl UpdateRng {
+    pub fn new(seed: u64) -> UpdateRng {
+        UpdateRng{seed, rng: ChaCha8Rng::seed_from_u64(seed)}
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Game;
+    use super::super::save_format::{load_game, save_game};
+    use super::super::soup::SoupSettings;
+
+    fn get_game(update_settings: UpdateSettings, seed: u64) -> Game {
+        let mut game = Game::new(20, 16);
+        game.set_field(SoupSettings::uniform(35.0).generate_field(20, 16, 3)).unwrap();
+        game.set_n_threads(1);
+        game.set_update_settings(update_settings);
+        game.set_update_seed(seed);
+        game
+    }
+
+    fn advance(game: &mut Game, n_generations: usize) {
+        for _ in 0..n_generations {
+            game.advance_generation(true);
+        }
+    }
+
+    #[test]
+    fn certain_updates_match_the_synchronous_step() {
+        let mut game = get_game(UpdateSettings::default(), 0);
+        let alpha_settings = UpdateSettings{mode: UpdateMode::AlphaAsynchronous, synchrony_rate: 1.0, ..UpdateSettings::default()};
+        assert!(alpha_settings.is_stochastic());
+        let mut alpha_game = get_game(alpha_settings, 0);
+        advance(&mut game, 10);
+        advance(&mut alpha_game, 10);
+        assert_eq!(alpha_game.get_field().get_cells(), game.get_field().get_cells());
+    }
+
+    #[test]
+    fn cells_never_updated_keep_their_state() {
+        let mut game = get_game(UpdateSettings{mode: UpdateMode::AlphaAsynchronous, synchrony_rate: 0.0, ..UpdateSettings::default()}, 0);
+        let cells = game.get_field().get_cells().to_vec();
+        advance(&mut game, 10);
+        assert_eq!(game.get_field().get_cells(), cells);
+    }
+
+    #[test]
+    fn runs_are_reproducible_from_their_seed() {
+        for mode in [UpdateMode::RandomOrder, UpdateMode::AlphaAsynchronous, UpdateMode::Synchronous] {
+            let update_settings = UpdateSettings{mode, birth_probability: 0.8, survival_probability: 0.9, ..UpdateSettings::default()};
+            let (mut game, mut same_game, mut other_game) = (get_game(update_settings.clone(), 5), get_game(update_settings.clone(), 5), get_game(update_settings, 6));
+            advance(&mut game, 10);
+            advance(&mut same_game, 10);
+            advance(&mut other_game, 10);
+            assert_eq!(same_game.get_field().get_cells(), game.get_field().get_cells());
+            assert_ne!(other_game.get_field().get_cells(), game.get_field().get_cells());
+        }
+    }
+
+    #[test]
+    fn reloaded_games_continue_the_same_random_sequence() {
+        let mut game = get_game(UpdateSettings{mode: UpdateMode::RandomOrder, birth_probability: 0.7, ..UpdateSettings::default()}, 9);
+        advance(&mut game, 5);
+        let mut reloaded_game = load_game(&save_game(&game).unwrap()).unwrap();
+        reloaded_game.set_n_threads(1);
+        advance(&mut game, 5);
+        advance(&mut reloaded_game, 5);
+        assert_eq!(reloaded_game.get_update_seed(), 9);
+        assert_eq!(reloaded_game.get_field().get_cells(), game.get_field().get_cells());
+    }
+}
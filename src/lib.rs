@@ -1,4 +1,23 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+//! Cellular automata engine of the Rusty life app.
+//!
+//! The engine runs on its own: build a `Game` (square, triangular, one- or three-dimensional), set its rule, edit its
+//! `Field` and advance it generation by generation. Topology is chosen at each step, by whether the extremes of the
+//! board are adjacent (a torus) or not (a bounded plane). The `game` module holds the rest of the engine: multi-state
+//! rule tables, turmites, Margolus and continuous rules, pattern formats and saves. Checked cell accesses fail with a
+//! `FieldError`. The pattern searches are internal to the app.
+//!
+//! The app itself, `TemplateApp`, is behind the opt-in `gui` feature; by default only the engine is built, without
+//! egui and eframe.
+
+pub mod game;
+pub use game::{AntPhases, Game, GridType};
+pub use game::field::{CellPosition, CellRegion, CellState, Field, TriangularNeighbourhood};
+pub use game::field_error::FieldError;
+pub use game::rule::Rule;
+
+#[cfg(feature = "gui")]
 mod app;
-pub use app::TemplateApp;
+#[cfg(feature = "gui")]
+pub use app::{TemplateApp, run_animation_export_command};
@@ -6,6 +6,21 @@
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    // Headless export, without opening a window
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|command| command == "export-animation") {
+        match rusty_life::run_animation_export_command(&args[2..]) {
+            Ok(message) => {
+                println!("{message}");
+                return Ok(());
+            }
+            Err(error) => {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        }
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])